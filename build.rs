@@ -14,13 +14,27 @@ fn main() {
         panic!("Platform '{sys}' not supported.");
     }
 
+    // The functions are resolved from the MinHook DLL of the host process at runtime
+    if env::var_os("CARGO_FEATURE_DYNAMIC").is_some() {
+        return;
+    }
+
+    if env::var_os("CARGO_FEATURE_VENDORED").is_some() {
+        build_vendored(&root_dir, arch);
+    } else {
+        link_prebuilt();
+    }
+}
+
+// Compiles the MinHook sources of the submodule, which works with both the MSVC and GNU toolchains
+fn build_vendored(root_dir: &str, arch: &str) {
     let hde = match arch {
         "i686" => "hde/hde32.c",
         "x86_64" => "hde/hde64.c",
         _ => panic!("Architecture '{arch}' not supported."),
     };
 
-    let mh_src_dir = Path::new(&root_dir).join("minhook/src");
+    let mh_src_dir = Path::new(root_dir).join("minhook/src");
 
     cc::Build::new()
         .file(mh_src_dir.join("buffer.c"))
@@ -36,3 +50,16 @@ fn main() {
         env::var("OUT_DIR").unwrap()
     );
 }
+
+// Links a prebuilt static MinHook library from MINHOOK_LIB_DIR, named by MINHOOK_LIB_NAME
+fn link_prebuilt() {
+    println!("cargo:rerun-if-env-changed=MINHOOK_LIB_DIR");
+    println!("cargo:rerun-if-env-changed=MINHOOK_LIB_NAME");
+
+    if let Ok(lib_dir) = env::var("MINHOOK_LIB_DIR") {
+        println!("cargo:rustc-link-search=native={lib_dir}");
+    }
+
+    let lib_name = env::var("MINHOOK_LIB_NAME").unwrap_or_else(|_| "minhook".to_string());
+    println!("cargo:rustc-link-lib=static={lib_name}");
+}
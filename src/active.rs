@@ -0,0 +1,123 @@
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+// How long `ActiveCalls::wait_idle` sleeps between checks
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Counts the threads that are running a detour, so removing the hook can wait until no thread is inside the detour
+/// or the trampoline anymore.
+///
+/// The detour enters a call with [`ActiveCalls::enter`] before anything else, and keeps the returned guard until it
+/// returns. To remove the hook, it is disabled first so no new calls arrive, then [`ActiveCalls::wait_idle`] waits for
+/// the running calls, and then the hook can be removed or the DLL of the detour unloaded.
+/// [`MhHook::remove_when_idle`](crate::MhHook::remove_when_idle) takes all three steps. The guard is dropped right
+/// before the detour returns, so a few instructions of the detour may still run after the count drops to zero.
+///
+/// # Example
+///
+/// ```rust
+/// use minhook::{ActiveCalls, MhHook, MH_STATUS};
+/// use std::time::Duration;
+///
+/// static CALLS: ActiveCalls = ActiveCalls::new();
+///
+/// fn main() -> Result<(), MH_STATUS> {
+///     unsafe {
+///         let hook = MhHook::from_fns::<fn() -> i32>(return_0, return_1)?;
+///         hook.enable()?;
+///         assert_eq!(return_0(), 1);
+///
+///         assert!(hook.remove_when_idle(&CALLS, Duration::from_secs(1))?);
+///     }
+///
+///     Ok(())
+/// }
+///
+/// fn return_0() -> i32 {
+///     0
+/// }
+///
+/// fn return_1() -> i32 {
+///     let _call = CALLS.enter();
+///     1
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct ActiveCalls {
+    count: AtomicUsize,
+}
+
+impl ActiveCalls {
+    /// Creates a counter without active calls.
+    pub const fn new() -> Self {
+        Self {
+            count: AtomicUsize::new(0),
+        }
+    }
+
+    /// Enters a call, which stays active until the guard is dropped.
+    pub fn enter(&self) -> ActiveCall<'_> {
+        self.count.fetch_add(1, Ordering::SeqCst);
+
+        ActiveCall { calls: self }
+    }
+
+    /// Returns the number of active calls.
+    pub fn count(&self) -> usize {
+        self.count.load(Ordering::SeqCst)
+    }
+
+    /// Waits until no call is active. This returns `false` if calls were still active after the timeout.
+    pub fn wait_idle(&self, timeout: Duration) -> bool {
+        let start = Instant::now();
+        while self.count() > 0 {
+            if start.elapsed() >= timeout {
+                return false;
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+
+        true
+    }
+}
+
+/// A call entered through [`ActiveCalls::enter`], which is active until the guard is dropped.
+#[derive(Debug)]
+#[must_use = "the call is no longer active once the guard is dropped"]
+pub struct ActiveCall<'a> {
+    calls: &'a ActiveCalls,
+}
+
+impl Drop for ActiveCall<'_> {
+    fn drop(&mut self) {
+        self.calls.count.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Barrier;
+
+    #[test]
+    fn test_active_calls() {
+        static CALLS: ActiveCalls = ActiveCalls::new();
+        static ENTERED: Barrier = Barrier::new(2);
+
+        let call = CALLS.enter();
+        assert_eq!(CALLS.count(), 1);
+        assert!(!CALLS.wait_idle(Duration::from_millis(10)));
+
+        // Waiting returns once the call ends on another thread.
+        let thread = thread::spawn(move || {
+            ENTERED.wait();
+            drop(call);
+        });
+        ENTERED.wait();
+        assert!(CALLS.wait_idle(Duration::from_secs(10)));
+        thread.join().unwrap();
+    }
+}
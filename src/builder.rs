@@ -0,0 +1,251 @@
+use crate::{MH_STATUS, MhHook, ModuleName, logging::debug};
+use std::ffi::c_void;
+
+// How the target of a hook is resolved
+#[derive(Debug, Clone)]
+enum Target {
+    Address(*mut c_void),
+    Api(ModuleName, String),
+    Rva(ModuleName, usize),
+}
+
+/// What a builder does when the target already jumps to the detour of another hooking library, such as an overlay.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ForeignDetour {
+    /// Hooks the target anyway. The new detour runs first, and the trampoline calls the foreign detour, which is
+    /// recorded in [`MhHook::foreign_detour`].
+    #[default]
+    Wrap,
+    /// Fails with `MH_ERROR_ALREADY_CREATED` instead of hooking the target.
+    Reject,
+}
+
+/// A builder for a [`MhHook`], which resolves the target and creates the hook in [`MhHookBuilder::build`].
+///
+/// # Example
+///
+/// ```rust
+/// use minhook::{MhHookBuilder, MH_STATUS};
+///
+/// fn main() -> Result<(), MH_STATUS> {
+///     let hook = unsafe {
+///         MhHookBuilder::new()
+///             .target_addr(return_0 as fn() -> i32 as _)
+///             .detour(return_1 as fn() -> i32 as _)
+///             .name("return_0")
+///             .enabled(true)
+///             .build()?
+///     };
+///
+///     assert_eq!(hook.name(), Some("return_0"));
+///     assert_eq!(return_0(), 1);
+///
+///     Ok(())
+/// }
+///
+/// fn return_0() -> i32 {
+///     0
+/// }
+///
+/// fn return_1() -> i32 {
+///     1
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MhHookBuilder {
+    target: Option<Target>,
+    detour: Option<*mut c_void>,
+    enabled: bool,
+    name: Option<String>,
+    foreign_detour: ForeignDetour,
+}
+
+impl MhHookBuilder {
+    /// Creates a builder without a target or detour.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Hooks the function at the address.
+    pub fn target_addr(mut self, target: *mut c_void) -> Self {
+        self.target = Some(Target::Address(target));
+        self
+    }
+
+    /// Hooks an exported function, which is looked up by its module and function name like [`MhHook::new_api`].
+    pub fn target_api<M: Into<ModuleName>, P: Into<String>>(
+        mut self,
+        module_name: M,
+        proc_name: P,
+    ) -> Self {
+        self.target = Some(Target::Api(module_name.into(), proc_name.into()));
+        self
+    }
+
+    /// Hooks the function at a relative virtual address in a loaded module like [`MhHook::new_rva`].
+    pub fn target_rva<M: Into<ModuleName>>(mut self, module_name: M, rva: usize) -> Self {
+        self.target = Some(Target::Rva(module_name.into(), rva));
+        self
+    }
+
+    /// Detours the target to the function at the address.
+    pub fn detour(mut self, detour: *mut c_void) -> Self {
+        self.detour = Some(detour);
+        self
+    }
+
+    /// Enables the hook right after it is created. Hooks are created disabled by default.
+    pub fn enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
+    /// Names the hook, which is available through [`MhHook::name`].
+    pub fn name<N: Into<String>>(mut self, name: N) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Chooses what happens if the target already jumps to the detour of another hooking library. Such hooks are
+    /// wrapped by default.
+    pub fn foreign_detour(mut self, foreign_detour: ForeignDetour) -> Self {
+        self.foreign_detour = foreign_detour;
+        self
+    }
+
+    /// Resolves the target and creates the hook.
+    ///
+    /// Returns `MH_ERROR_NOT_EXECUTABLE` if the target or detour is missing, and otherwise the same errors as the
+    /// constructor of [`MhHook`] for the kind of target. If the hook can't be enabled, it is removed again.
+    ///
+    /// # Safety
+    pub unsafe fn build(self) -> Result<MhHook, MH_STATUS> {
+        let enabled = self.enabled;
+        let hook = unsafe { self.create()? };
+
+        if enabled && let Err(status) = unsafe { hook.enable() } {
+            let _ = unsafe { hook.remove() };
+            return Err(status);
+        }
+
+        Ok(hook)
+    }
+
+    // The address of the target if it is given as an address, or null if it still has to be resolved
+    pub(crate) fn target_address(&self) -> *mut c_void {
+        match self.target {
+            Some(Target::Address(target)) => target,
+            _ => std::ptr::null_mut(),
+        }
+    }
+
+    // Resolves the target and creates the hook, without enabling it
+    pub(crate) unsafe fn create(self) -> Result<MhHook, MH_STATUS> {
+        let (Some(target), Some(detour)) = (self.target, self.detour) else {
+            return Err(MH_STATUS::MH_ERROR_NOT_EXECUTABLE);
+        };
+
+        let hook = match target {
+            Target::Address(target) => unsafe { MhHook::new(target, detour)? },
+            Target::Api(module_name, proc_name) => unsafe {
+                MhHook::new_api(module_name, proc_name, detour)?
+            },
+            Target::Rva(module_name, rva) => unsafe { MhHook::new_rva(module_name, rva, detour)? },
+        };
+        if self.foreign_detour == ForeignDetour::Reject
+            && let Some(foreign_detour) = hook.foreign_detour()
+        {
+            debug!(
+                "Rejected {:?}, which jumps to {:?}",
+                hook.target(),
+                foreign_detour
+            );
+            let _ = unsafe { hook.remove() };
+            return Err(MH_STATUS::MH_ERROR_ALREADY_CREATED);
+        }
+
+        let hook = hook.with_name(self.name);
+        debug!("Built hook {:?} for {:?}", hook.name(), hook.target());
+
+        Ok(hook)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::HookTest;
+
+    #[test]
+    fn test_builder() {
+        let _test = HookTest::start();
+
+        unsafe {
+            let hook = MhHookBuilder::new()
+                .target_addr(test_fn as FnType as *mut c_void)
+                .detour(test_fn_hook as FnType as *mut c_void)
+                .name("test_fn")
+                .build()
+                .unwrap();
+            assert_eq!(hook.name(), Some("test_fn"));
+            assert!(!hook.is_enabled());
+            hook.remove().unwrap();
+
+            let hook = MhHookBuilder::new()
+                .target_addr(test_fn as FnType as *mut c_void)
+                .detour(test_fn_hook as FnType as *mut c_void)
+                .enabled(true)
+                .build()
+                .unwrap();
+            assert_eq!(test_fn(), 1);
+            hook.remove().unwrap();
+        }
+
+        type FnType = fn() -> i32;
+
+        #[inline(never)]
+        fn test_fn() -> i32 {
+            0
+        }
+
+        fn test_fn_hook() -> i32 {
+            1
+        }
+    }
+
+    #[test]
+    fn test_builder_api() {
+        let _test = HookTest::start();
+
+        unsafe {
+            let hook = MhHookBuilder::new()
+                .target_api("kernel32.dll", "GetCurrentProcess")
+                .detour(get_current_process_hook as FnType as *mut c_void)
+                .build()
+                .unwrap();
+            assert!(!hook.target().is_null());
+
+            // A missing target or detour is reported instead of creating the hook.
+            let status = MhHookBuilder::new()
+                .detour(get_current_process_hook as FnType as *mut c_void)
+                .build()
+                .unwrap_err();
+            assert_eq!(status, MH_STATUS::MH_ERROR_NOT_EXECUTABLE);
+
+            let status = MhHookBuilder::new()
+                .target_rva("not_loaded.dll", 0x1000)
+                .detour(get_current_process_hook as FnType as *mut c_void)
+                .build()
+                .unwrap_err();
+            assert_eq!(status, MH_STATUS::MH_ERROR_MODULE_NOT_FOUND);
+
+            hook.remove().unwrap();
+        }
+
+        type FnType = unsafe extern "system" fn() -> *mut c_void;
+
+        unsafe extern "system" fn get_current_process_hook() -> *mut c_void {
+            std::ptr::null_mut()
+        }
+    }
+}
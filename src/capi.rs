@@ -0,0 +1,191 @@
+//! A C ABI for registering hooks, so plugins written in other languages that are hosted in the same process share the
+//! MinHook instance managed by this crate instead of initializing their own.
+//!
+//! The functions are exported under their names when the crate is linked into a DLL. All of them work on the
+//! [global registry](crate::HookRegistry::global) and return a [`MH_STATUS`], or `MH_UNKNOWN` for a name that is null
+//! or not valid UTF-8. A C header declares them as:
+//!
+//! ```c
+//! MH_STATUS mh_register_hook(const char *name, void *target, void *detour, void **original);
+//! MH_STATUS mh_enable(const char *name);
+//! MH_STATUS mh_disable(const char *name);
+//! MH_STATUS mh_remove(const char *name);
+//! ```
+
+use crate::{HookRegistry, MH_STATUS, MhHook};
+use std::{
+    ffi::{CStr, c_char, c_void},
+    ptr::null_mut,
+};
+
+/// Creates a hook for the target and registers it under the name, without enabling it. The trampoline is written to
+/// `original` unless it is null, before the hook is registered, so it is in place once another thread can enable the
+/// hook. Null is written to `original` again if the registration fails.
+///
+/// Returns `MH_ERROR_ALREADY_CREATED` if a hook is already registered under the name, and otherwise the same errors as
+/// `MH_CreateHook`.
+///
+/// # Safety
+///
+/// The name must be null or a null-terminated string, and `original` must be null or valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn mh_register_hook(
+    name: *const c_char,
+    target: *mut c_void,
+    detour: *mut c_void,
+    original: *mut *mut c_void,
+) -> MH_STATUS {
+    let Some(name) = (unsafe { hook_name(name) }) else {
+        return MH_STATUS::MH_UNKNOWN;
+    };
+
+    let hook = match unsafe { MhHook::new(target, detour) } {
+        Ok(hook) => hook.with_name(Some(name.to_owned())),
+        Err(status) => return status,
+    };
+    // The detour may call the trampoline as soon as another thread enables the registered hook
+    if !original.is_null() {
+        unsafe { original.write(hook.trampoline()) };
+    }
+
+    // Another plugin may register the name at the same time, so the name is checked and taken under one lock
+    if let Err(hook) = HookRegistry::global().register_new(name, hook) {
+        if !original.is_null() {
+            unsafe { original.write(null_mut()) };
+        }
+        let _ = unsafe { hook.remove() };
+        return MH_STATUS::MH_ERROR_ALREADY_CREATED;
+    }
+
+    MH_STATUS::MH_OK
+}
+
+/// Enables the hook registered under the name.
+///
+/// # Safety
+///
+/// The name must be null or a null-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn mh_enable(name: *const c_char) -> MH_STATUS {
+    match unsafe { hook_name(name) } {
+        Some(name) => status(unsafe { HookRegistry::global().enable(name) }),
+        None => MH_STATUS::MH_UNKNOWN,
+    }
+}
+
+/// Disables the hook registered under the name.
+///
+/// # Safety
+///
+/// The name must be null or a null-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn mh_disable(name: *const c_char) -> MH_STATUS {
+    match unsafe { hook_name(name) } {
+        Some(name) => status(unsafe { HookRegistry::global().disable(name) }),
+        None => MH_STATUS::MH_UNKNOWN,
+    }
+}
+
+/// Unregisters the hook with the name and removes it, which also disables it.
+///
+/// Returns `MH_ERROR_NOT_CREATED` if no hook is registered under the name.
+///
+/// # Safety
+///
+/// The name must be null or a null-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn mh_remove(name: *const c_char) -> MH_STATUS {
+    let Some(name) = (unsafe { hook_name(name) }) else {
+        return MH_STATUS::MH_UNKNOWN;
+    };
+
+    match HookRegistry::global().unregister(name) {
+        Some(hook) => status(unsafe { hook.remove() }),
+        None => MH_STATUS::MH_ERROR_NOT_CREATED,
+    }
+}
+
+unsafe fn hook_name<'a>(name: *const c_char) -> Option<&'a str> {
+    if name.is_null() {
+        return None;
+    }
+
+    unsafe { CStr::from_ptr(name) }.to_str().ok()
+}
+
+fn status(result: Result<(), MH_STATUS>) -> MH_STATUS {
+    result.err().unwrap_or(MH_STATUS::MH_OK)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::HookTest;
+
+    #[test]
+    fn test_capi() {
+        let _test = HookTest::start();
+
+        unsafe {
+            let mut original = null_mut();
+            let status = mh_register_hook(
+                c"test_fn".as_ptr(),
+                test_fn as FnType as *mut c_void,
+                test_fn_hook as FnType as *mut c_void,
+                &mut original,
+            );
+            assert_eq!(status, MH_STATUS::MH_OK);
+            assert!(!original.is_null());
+
+            let status = mh_register_hook(
+                c"test_fn".as_ptr(),
+                test_fn as FnType as *mut c_void,
+                test_fn_hook as FnType as *mut c_void,
+                null_mut(),
+            );
+            assert_eq!(status, MH_STATUS::MH_ERROR_ALREADY_CREATED);
+
+            // A hook created for a name that is taken is removed again, and its trampoline is not handed out.
+            let mut other = null_mut();
+            let status = mh_register_hook(
+                c"test_fn".as_ptr(),
+                test_fn_other as FnType as *mut c_void,
+                test_fn_hook as FnType as *mut c_void,
+                &mut other,
+            );
+            assert_eq!(status, MH_STATUS::MH_ERROR_ALREADY_CREATED);
+            assert!(other.is_null());
+            assert!(!crate::state::is_created(
+                test_fn_other as FnType as *mut c_void
+            ));
+
+            assert_eq!(mh_enable(c"test_fn".as_ptr()), MH_STATUS::MH_OK);
+            assert_eq!(test_fn(), 71);
+            assert_eq!(mh_disable(c"test_fn".as_ptr()), MH_STATUS::MH_OK);
+            assert_eq!(test_fn(), 70);
+
+            assert_eq!(mh_remove(c"test_fn".as_ptr()), MH_STATUS::MH_OK);
+            assert_eq!(
+                mh_remove(c"test_fn".as_ptr()),
+                MH_STATUS::MH_ERROR_NOT_CREATED
+            );
+            assert_eq!(mh_enable(std::ptr::null()), MH_STATUS::MH_UNKNOWN);
+        }
+
+        type FnType = extern "C" fn() -> i32;
+
+        #[inline(never)]
+        extern "C" fn test_fn() -> i32 {
+            std::hint::black_box(70)
+        }
+
+        #[inline(never)]
+        extern "C" fn test_fn_other() -> i32 {
+            std::hint::black_box(72)
+        }
+
+        extern "C" fn test_fn_hook() -> i32 {
+            71
+        }
+    }
+}
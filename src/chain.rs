@@ -0,0 +1,319 @@
+use crate::{FnPtr, MH_STATUS, MhHook, MinHook};
+use std::sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// Several detours on the same target, called one after another in order of their priority.
+///
+/// MinHook only allows one hook per target, so the chain owns that hook and detours it to the detour with the highest
+/// priority. Each detour calls the next one with [`HookChain::next`], and the last detour is handed the trampoline.
+/// Detours with the same priority are called in the order they were inserted.
+///
+/// The chain keeps its hook while the first detour changes, and only switches the detour the hook jumps to, so the
+/// trampoline stays valid for the detours that are running. On x86, where MinHook can't switch the detour, the hook is
+/// recreated instead, and a thread running a detour may still call a trampoline that is being freed. There, change the
+/// chain while the target is not being called.
+///
+/// # Example
+///
+/// ```rust
+/// use minhook::{HookChain, MH_STATUS};
+///
+/// static CHAIN: HookChain<fn(i32) -> i32> = HookChain::new(add_1);
+///
+/// fn main() -> Result<(), MH_STATUS> {
+///     unsafe {
+///         CHAIN.insert(0, times_2)?;
+///         CHAIN.insert(1, plus_10)?;
+///     }
+///
+///     // plus_10 runs first, then times_2, then the original function
+///     assert_eq!(add_1(1), 23);
+///
+///     Ok(())
+/// }
+///
+/// fn add_1(x: i32) -> i32 {
+///     x + 1
+/// }
+///
+/// fn plus_10(x: i32) -> i32 {
+///     CHAIN.next(plus_10)(x + 10)
+/// }
+///
+/// fn times_2(x: i32) -> i32 {
+///     CHAIN.next(times_2)(x * 2)
+/// }
+/// ```
+#[derive(Debug)]
+pub struct HookChain<F: FnPtr> {
+    target: F,
+    state: RwLock<ChainState<F>>,
+}
+
+#[derive(Debug)]
+struct ChainState<F: FnPtr> {
+    hook: Option<ChainHook<F>>,
+    links: Vec<(i32, F)>,
+}
+
+// The hook of the target, detoured to the first detour of the chain
+#[derive(Debug)]
+struct ChainHook<F: FnPtr> {
+    hook: MhHook,
+    detour: F,
+    original: F,
+}
+
+impl<F: FnPtr> HookChain<F> {
+    /// Creates an empty chain for the target function. The target is not hooked until a detour is inserted.
+    pub const fn new(target: F) -> Self {
+        Self {
+            target,
+            state: RwLock::new(ChainState {
+                hook: None,
+                links: Vec::new(),
+            }),
+        }
+    }
+
+    /// Inserts a detour into the chain. Detours with a higher priority are called first.
+    ///
+    /// Returns `MH_ERROR_ALREADY_CREATED` if the detour is already in the chain.
+    ///
+    /// # Safety
+    pub unsafe fn insert(&self, priority: i32, detour: F) -> Result<(), MH_STATUS> {
+        let mut state = self.write();
+        if state.position(detour).is_some() {
+            return Err(MH_STATUS::MH_ERROR_ALREADY_CREATED);
+        }
+
+        let index = state
+            .links
+            .iter()
+            .position(|&(link_priority, _)| link_priority < priority)
+            .unwrap_or(state.links.len());
+        state.links.insert(index, (priority, detour));
+
+        let result = unsafe { self.rebuild(&mut state) };
+        if result.is_err() {
+            state.links.remove(index);
+        }
+
+        result
+    }
+
+    /// Removes a detour from the chain, and removes the hook once the chain is empty.
+    ///
+    /// Returns `MH_ERROR_NOT_CREATED` if the detour is not in the chain.
+    ///
+    /// # Safety
+    pub unsafe fn remove(&self, detour: F) -> Result<(), MH_STATUS> {
+        let mut state = self.write();
+        let index = state
+            .position(detour)
+            .ok_or(MH_STATUS::MH_ERROR_NOT_CREATED)?;
+
+        let link = state.links.remove(index);
+
+        let result = unsafe { self.rebuild(&mut state) };
+        if result.is_err() {
+            state.links.insert(index, link);
+        }
+
+        result
+    }
+
+    /// Returns the function the detour should call next: the next detour in the chain, or the trampoline after the last
+    /// detour.
+    ///
+    /// If the detour is not in the chain, because it was removed while it was running, this returns the trampoline, or
+    /// the target itself if the target is no longer hooked.
+    pub fn next(&self, detour: F) -> F {
+        let state = self.read();
+        let trampoline = state
+            .hook
+            .as_ref()
+            .map_or(self.target, |hook| hook.original);
+
+        match state.position(detour) {
+            Some(index) => state
+                .links
+                .get(index + 1)
+                .map_or(trampoline, |&(_, next)| next),
+            None => trampoline,
+        }
+    }
+
+    /// Returns the number of detours in the chain.
+    pub fn len(&self) -> usize {
+        self.read().links.len()
+    }
+
+    /// Returns whether the chain has no detours.
+    pub fn is_empty(&self) -> bool {
+        self.read().links.is_empty()
+    }
+
+    // Detours the hook of the target to the first detour, creating or removing the hook as the chain fills or empties
+    unsafe fn rebuild(&self, state: &mut ChainState<F>) -> Result<(), MH_STATUS> {
+        let head = state.links.first().map(|&(_, detour)| detour);
+        let current = state.hook.as_ref().map(|hook| hook.detour);
+
+        match (current, head) {
+            (Some(current), Some(head)) if current.as_ptr() == head.as_ptr() => Ok(()),
+            (None, None) => Ok(()),
+            (None, Some(head)) => {
+                state.hook = Some(unsafe { self.create(head)? });
+                Ok(())
+            }
+            (Some(_), None) => {
+                if let Some(chain_hook) = state.hook.take() {
+                    // The hook is kept if it can't be removed, so the chain still owns it
+                    if let Err(status) = unsafe { MinHook::remove_hook(chain_hook.hook.target()) } {
+                        state.hook = Some(chain_hook);
+                        return Err(status);
+                    }
+                }
+                Ok(())
+            }
+            (Some(current), Some(head)) => {
+                let Some(chain_hook) = state.hook.as_mut() else {
+                    return Ok(());
+                };
+
+                // Switching the detour in place keeps the target hooked and the trampoline alive
+                match unsafe { chain_hook.hook.set_detour(head.as_ptr()) } {
+                    Ok(()) => {
+                        chain_hook.detour = head;
+                        Ok(())
+                    }
+                    Err(MH_STATUS::MH_ERROR_UNSUPPORTED_FUNCTION) => unsafe {
+                        self.recreate(state, current, head)
+                    },
+                    Err(status) => Err(status),
+                }
+            }
+        }
+    }
+
+    // Recreates the hook for another detour, and puts the old hook back if that fails
+    unsafe fn recreate(
+        &self,
+        state: &mut ChainState<F>,
+        current: F,
+        head: F,
+    ) -> Result<(), MH_STATUS> {
+        if let Some(chain_hook) = &state.hook {
+            unsafe { MinHook::remove_hook(chain_hook.hook.target())? };
+            state.hook = None;
+        }
+
+        match unsafe { self.create(head) } {
+            Ok(chain_hook) => {
+                state.hook = Some(chain_hook);
+                Ok(())
+            }
+            Err(status) => {
+                state.hook = unsafe { self.create(current) }.ok();
+                Err(status)
+            }
+        }
+    }
+
+    // Creates and enables the hook of the target for the detour
+    unsafe fn create(&self, detour: F) -> Result<ChainHook<F>, MH_STATUS> {
+        let hook = unsafe { MhHook::from_fns(self.target, detour)? };
+        let original = unsafe { F::from_ptr(hook.trampoline()) };
+        if let Err(status) = unsafe { hook.enable() } {
+            let _ = unsafe { hook.remove() };
+            return Err(status);
+        }
+
+        Ok(ChainHook {
+            hook,
+            detour,
+            original,
+        })
+    }
+
+    // The state stays consistent even if a panic happened while it was locked
+    fn read(&self) -> RwLockReadGuard<'_, ChainState<F>> {
+        self.state.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, ChainState<F>> {
+        self.state.write().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<F: FnPtr> ChainState<F> {
+    fn position(&self, detour: F) -> Option<usize> {
+        self.links
+            .iter()
+            .position(|&(_, link)| link.as_ptr() == detour.as_ptr())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::HookTest;
+
+    #[test]
+    fn test_hook_chain() {
+        let _test = HookTest::start();
+
+        static CHAIN: HookChain<fn(i32) -> i32> = HookChain::new(test_fn);
+
+        unsafe {
+            CHAIN.insert(0, test_fn_times_2).unwrap();
+            assert_eq!(test_fn(1), 3);
+
+            // The detour with the higher priority is called first.
+            let trampoline = || {
+                CHAIN
+                    .read()
+                    .hook
+                    .as_ref()
+                    .map(|hook| hook.original.as_ptr())
+            };
+            let before = trampoline();
+            CHAIN.insert(1, test_fn_plus_10).unwrap();
+            assert_eq!(CHAIN.len(), 2);
+            assert_eq!(test_fn(1), 23);
+
+            // On x64, the hook and its trampoline are kept while the first detour changes.
+            if cfg!(target_pointer_width = "64") {
+                assert_eq!(trampoline(), before);
+            }
+            assert_eq!(
+                CHAIN.insert(2, test_fn_plus_10),
+                Err(MH_STATUS::MH_ERROR_ALREADY_CREATED)
+            );
+
+            CHAIN.remove(test_fn_plus_10).unwrap();
+            assert_eq!(test_fn(1), 3);
+
+            // The target is unhooked once the chain is empty.
+            CHAIN.remove(test_fn_times_2).unwrap();
+            assert!(CHAIN.is_empty());
+            assert_eq!(test_fn(1), 2);
+            assert_eq!(
+                CHAIN.remove(test_fn_times_2),
+                Err(MH_STATUS::MH_ERROR_NOT_CREATED)
+            );
+        }
+
+        #[inline(never)]
+        fn test_fn(x: i32) -> i32 {
+            x + 1
+        }
+
+        fn test_fn_plus_10(x: i32) -> i32 {
+            CHAIN.next(test_fn_plus_10)(x + 10)
+        }
+
+        fn test_fn_times_2(x: i32) -> i32 {
+            CHAIN.next(test_fn_times_2)(x * 2)
+        }
+    }
+}
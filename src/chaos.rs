@@ -0,0 +1,294 @@
+//! Hooks that make a function fail on purpose, usually declared through [`chaos_hook!`](crate::chaos_hook!), to test
+//! how an application handles failing Windows APIs.
+//!
+//! A [`FaultPolicy`] decides which calls fail: every Nth call, a percentage of the calls picked at random, or both. A
+//! failing call returns the error value of the policy without calling the original function, and every call can be
+//! slowed down by a fixed latency.
+
+use crate::{FnPtr, Hook, MH_STATUS, StaticHook};
+use std::{
+    sync::{
+        PoisonError, RwLock,
+        atomic::{AtomicU64, Ordering},
+    },
+    thread,
+    time::Duration,
+};
+
+// The seed used unless the policy is given one, any value but 0 works for xorshift
+const DEFAULT_SEED: u64 = 0x2545_F491_4F6C_DD1D;
+
+/// Which calls of a [`ChaosHook`] fail, and what they return instead.
+///
+/// A policy without any faults or latency lets every call through to the original function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FaultPolicy<R> {
+    error: R,
+    every: u64,
+    percent: u8,
+    latency: Duration,
+    seed: u64,
+}
+
+impl<R: Copy> FaultPolicy<R> {
+    /// Creates a policy whose failing calls return the error value, such as `FALSE` or an `NTSTATUS`.
+    pub const fn new(error: R) -> Self {
+        Self {
+            error,
+            every: 0,
+            percent: 0,
+            latency: Duration::ZERO,
+            seed: DEFAULT_SEED,
+        }
+    }
+
+    /// Fails every Nth call, starting with the Nth one. A value of 0 disables this fault.
+    pub const fn fail_every(mut self, every: u64) -> Self {
+        self.every = every;
+        self
+    }
+
+    /// Fails the given percentage of the calls, picked at random. Values above 100 fail every call.
+    pub const fn fail_percent(mut self, percent: u8) -> Self {
+        self.percent = percent;
+        self
+    }
+
+    /// Delays every call, failing or not, by the latency.
+    pub const fn latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    /// Seeds the random numbers that pick the calls for [`FaultPolicy::fail_percent`], so a test fails the same calls
+    /// each time it runs. A seed of 0 is replaced by the default seed, since the generator would only return 0.
+    pub const fn seed(mut self, seed: u64) -> Self {
+        self.seed = if seed == 0 { DEFAULT_SEED } else { seed };
+        self
+    }
+}
+
+/// A hook that injects the faults of a [`FaultPolicy`] into the calls of a function, usually declared through
+/// [`chaos_hook!`](crate::chaos_hook!).
+///
+/// The detour is generated by the macro and asks [`ChaosHook::inject`] whether the call fails. `R` is the return type.
+#[derive(Debug)]
+pub struct ChaosHook<F: FnPtr, R> {
+    hook: StaticHook<F>,
+    detour: F,
+    policy: RwLock<Option<FaultPolicy<R>>>,
+    random: AtomicU64,
+    calls: AtomicU64,
+    faults: AtomicU64,
+}
+
+impl<F: FnPtr, R: Copy> ChaosHook<F, R> {
+    /// Creates a hook that isn't initialized yet, with the detour generated by [`chaos_hook!`](crate::chaos_hook!).
+    pub const fn new(detour: F) -> Self {
+        Self {
+            hook: StaticHook::new(),
+            detour,
+            policy: RwLock::new(None),
+            random: AtomicU64::new(0),
+            calls: AtomicU64::new(0),
+            faults: AtomicU64::new(0),
+        }
+    }
+
+    /// Creates the hook for the target function if it doesn't exist yet, and enables it with the policy. The counts of
+    /// calls and faults start over. If the hook is already enabled, only the policy is replaced.
+    ///
+    /// Returns `MH_ERROR_ALREADY_CREATED` if the hook was already created for another target.
+    ///
+    /// # Safety
+    pub unsafe fn enable(&self, target: F, policy: FaultPolicy<R>) -> Result<(), MH_STATUS> {
+        // The policy stays consistent even if a panic happened while it was locked
+        *self.policy.write().unwrap_or_else(PoisonError::into_inner) = Some(policy);
+        self.random.store(policy.seed, Ordering::Relaxed);
+        self.calls.store(0, Ordering::Relaxed);
+        self.faults.store(0, Ordering::Relaxed);
+
+        match unsafe { self.hook.enable(target, self.detour) } {
+            Err(MH_STATUS::MH_ERROR_ENABLED) => Ok(()),
+            result => result,
+        }
+    }
+
+    /// Disables the hook. Returns `MH_ERROR_NOT_CREATED` if the hook was never enabled.
+    ///
+    /// # Safety
+    pub unsafe fn disable(&self) -> Result<(), MH_STATUS> {
+        unsafe { self.hook.disable() }
+    }
+
+    /// Returns the hook, if it was created.
+    pub fn get(&self) -> Option<&Hook<F>> {
+        self.hook.get()
+    }
+
+    /// Returns the trampoline, which calls the original target function.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the hook was never enabled.
+    pub fn original(&self) -> F {
+        self.hook.original()
+    }
+
+    /// Returns the number of calls since the hook was enabled.
+    pub fn calls(&self) -> u64 {
+        self.calls.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of calls that failed since the hook was enabled.
+    pub fn faults(&self) -> u64 {
+        self.faults.load(Ordering::Relaxed)
+    }
+
+    /// Counts a call of the generated detour and waits for the latency of the policy. This returns the error value if the
+    /// call fails, and `None` if it goes through to the original function.
+    pub fn inject(&self) -> Option<R> {
+        let policy = (*self.policy.read().unwrap_or_else(PoisonError::into_inner))?;
+        let call = self.calls.fetch_add(1, Ordering::Relaxed) + 1;
+
+        if !policy.latency.is_zero() {
+            thread::sleep(policy.latency);
+        }
+
+        let fail = (policy.every != 0 && call.is_multiple_of(policy.every))
+            || (policy.percent != 0 && self.random_percent() < policy.percent);
+        if !fail {
+            return None;
+        }
+
+        self.faults.fetch_add(1, Ordering::Relaxed);
+        Some(policy.error)
+    }
+
+    // Draws a number below 100 from a xorshift generator
+    fn random_percent(&self) -> u8 {
+        let next = |mut x: u64| {
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            x
+        };
+        let previous = self
+            .random
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |x| Some(next(x)))
+            .unwrap_or_default();
+
+        (next(previous) % 100) as u8
+    }
+}
+
+/// Declares a static [`ChaosHook`] for a function signature with named arguments, and generates its detour.
+///
+/// Safe and unsafe function signatures with an `extern` ABI are supported as well, like for
+/// [`instrument_hook!`](crate::instrument_hook).
+///
+/// # Example
+///
+/// ```rust
+/// use minhook::{chaos::FaultPolicy, chaos_hook, MH_STATUS};
+///
+/// chaos_hook! {
+///     static WRITE_HOOK: fn(len: usize) -> bool;
+/// }
+///
+/// fn main() -> Result<(), MH_STATUS> {
+///     unsafe { WRITE_HOOK.enable(write, FaultPolicy::new(false).fail_every(3))? };
+///
+///     // Every third write fails
+///     let results: Vec<_> = (0..6).map(write).collect();
+///     assert_eq!(results, [true, true, false, true, true, false]);
+///
+///     Ok(())
+/// }
+///
+/// fn write(_len: usize) -> bool {
+///     true
+/// }
+/// ```
+#[macro_export]
+macro_rules! chaos_hook {
+    ($(#[$attr:meta])* $vis:vis static $name:ident: fn($($arg:ident: $ty:ty),* $(,)?) $(-> $ret:ty)?;) => {
+        $crate::chaos_hook!(@impl [$(#[$attr])*] $vis $name [] [$($arg: $ty),*] [$($ret)?]);
+    };
+    ($(#[$attr:meta])* $vis:vis static $name:ident: extern $abi:literal fn($($arg:ident: $ty:ty),* $(,)?) $(-> $ret:ty)?;) => {
+        $crate::chaos_hook!(@impl [$(#[$attr])*] $vis $name [extern $abi] [$($arg: $ty),*] [$($ret)?]);
+    };
+    ($(#[$attr:meta])* $vis:vis static $name:ident: unsafe extern $abi:literal fn($($arg:ident: $ty:ty),* $(,)?) $(-> $ret:ty)?;) => {
+        $crate::chaos_hook!(@impl [$(#[$attr])*] $vis $name [unsafe extern $abi] [$($arg: $ty),*] [$($ret)?]);
+    };
+    (@impl [$($attr:tt)*] $vis:vis $name:ident [$($qual:tt)*] [$($arg:ident: $ty:ty),*] [$($ret:ty)?]) => {
+        $($attr)*
+        $vis static $name: $crate::chaos::ChaosHook<
+            $($qual)* fn($($ty),*) -> $crate::chaos_hook!(@ret $($ret)?),
+            $crate::chaos_hook!(@ret $($ret)?),
+        > = $crate::chaos::ChaosHook::new({
+            #[allow(unused_unsafe, clippy::too_many_arguments)]
+            $($qual)* fn detour($($arg: $ty),*) -> $crate::chaos_hook!(@ret $($ret)?) {
+                if let Some(error) = $name.inject() {
+                    return error;
+                }
+
+                unsafe { ($name.original())($($arg),*) }
+            }
+
+            detour
+        });
+    };
+    (@ret) => { () };
+    (@ret $ret:ty) => { $ret };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::HookTest;
+
+    #[test]
+    fn test_chaos_hook() {
+        let _test = HookTest::start();
+
+        crate::chaos_hook! {
+            static TEST_FN_HOOK: unsafe extern "system" fn(x: u32) -> u32;
+        }
+
+        unsafe {
+            TEST_FN_HOOK
+                .enable(test_fn, FaultPolicy::new(0).fail_every(2))
+                .unwrap();
+            assert_eq!(test_fn(140), 141);
+            assert_eq!(test_fn(140), 0);
+            assert_eq!(test_fn(142), 143);
+            assert_eq!((TEST_FN_HOOK.calls(), TEST_FN_HOOK.faults()), (3, 1));
+
+            // A percentage of 0 lets every call through, and 100 fails every call.
+            TEST_FN_HOOK
+                .enable(test_fn, FaultPolicy::new(0).fail_percent(0))
+                .unwrap();
+            assert!((0..10).all(|_| test_fn(140) == 141));
+            TEST_FN_HOOK
+                .enable(test_fn, FaultPolicy::new(0).fail_percent(100))
+                .unwrap();
+            assert!((0..10).all(|_| test_fn(140) == 0));
+            assert_eq!(TEST_FN_HOOK.faults(), 10);
+
+            // A seed of 0 still picks calls at random, instead of failing every call.
+            TEST_FN_HOOK
+                .enable(test_fn, FaultPolicy::new(0).fail_percent(50).seed(0))
+                .unwrap();
+            assert!((0..10).any(|_| test_fn(140) == 141));
+
+            TEST_FN_HOOK.disable().unwrap();
+            assert_eq!(test_fn(140), 141);
+        }
+
+        #[inline(never)]
+        unsafe extern "system" fn test_fn(x: u32) -> u32 {
+            x + 1
+        }
+    }
+}
@@ -0,0 +1,245 @@
+use crate::{FnPtr, Hook, MH_STATUS, StaticHook, hook, state};
+use std::sync::{
+    PoisonError, RwLock,
+    atomic::{AtomicUsize, Ordering},
+};
+
+/// A hook whose detour only runs for calls whose arguments match a predicate, usually declared through
+/// [`conditional_hook!`](crate::conditional_hook).
+///
+/// The stub generated by the macro checks the predicate first, and calls the original function right away for calls
+/// that don't match, so a detour that only cares about one file path doesn't run for every other path. `A` is the
+/// tuple of the argument types.
+///
+/// With [`ConditionalHook::enable_for_calls`], the hook disables itself after the detour ran a given number of times,
+/// for example to capture the arguments of the next call and then get out of the way.
+#[derive(Debug)]
+pub struct ConditionalHook<F: FnPtr, A> {
+    hook: StaticHook<F>,
+    stub: F,
+    detour: RwLock<Option<Detour<F, A>>>,
+    remaining_calls: AtomicUsize,
+}
+
+// The predicate and the detour that runs for the calls matching it
+type Detour<F, A> = (fn(&A) -> bool, F);
+
+impl<F: FnPtr, A> ConditionalHook<F, A> {
+    /// Creates a hook that isn't initialized yet, with the stub generated by
+    /// [`conditional_hook!`](crate::conditional_hook).
+    pub const fn new(stub: F) -> Self {
+        Self {
+            hook: StaticHook::new(),
+            stub,
+            detour: RwLock::new(None),
+            remaining_calls: AtomicUsize::new(UNLIMITED_CALLS),
+        }
+    }
+
+    /// Creates the hook for the target function if it doesn't exist yet, and enables it so calls that match the
+    /// predicate run the detour.
+    ///
+    /// Returns `MH_ERROR_ALREADY_CREATED` if the hook was already created for another target.
+    ///
+    /// # Safety
+    pub unsafe fn enable(
+        &self,
+        target: F,
+        predicate: fn(&A) -> bool,
+        detour: F,
+    ) -> Result<(), MH_STATUS> {
+        unsafe { self.enable_for_calls(target, predicate, detour, UNLIMITED_CALLS) }
+    }
+
+    /// Enables the hook like [`ConditionalHook::enable`], and disables it again once the detour ran for `calls` calls
+    /// that match the predicate.
+    ///
+    /// The stub of the last call queues the disable and applies the queued hooks, so other hooks that are queued by
+    /// then are applied as well. Matching calls that are already running the stub by then go to the original function.
+    ///
+    /// # Safety
+    pub unsafe fn enable_for_calls(
+        &self,
+        target: F,
+        predicate: fn(&A) -> bool,
+        detour: F,
+        calls: usize,
+    ) -> Result<(), MH_STATUS> {
+        // The detour stays consistent even if a panic happened while it was locked
+        *self.detour.write().unwrap_or_else(PoisonError::into_inner) = Some((predicate, detour));
+        self.remaining_calls.store(calls, Ordering::Release);
+
+        unsafe { self.hook.enable(target, self.stub) }
+    }
+
+    /// Disables the hook. Returns `MH_ERROR_NOT_CREATED` if the hook was never enabled.
+    ///
+    /// # Safety
+    pub unsafe fn disable(&self) -> Result<(), MH_STATUS> {
+        unsafe { self.hook.disable() }
+    }
+
+    /// Returns the hook, if it was created.
+    pub fn get(&self) -> Option<&Hook<F>> {
+        self.hook.get()
+    }
+
+    /// Returns the trampoline, which calls the original target function.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the hook was never enabled.
+    pub fn original(&self) -> F {
+        self.hook.original()
+    }
+
+    /// Returns the function the generated stub calls with the arguments: the detour if they match the predicate, and
+    /// the original function otherwise.
+    pub fn select(&self, args: &A) -> F {
+        let detour = *self.detour.read().unwrap_or_else(PoisonError::into_inner);
+
+        match detour {
+            Some((predicate, detour)) if predicate(args) && self.take_call() => detour,
+            _ => self.original(),
+        }
+    }
+
+    // Counts a call of the detour, and disables the hook after the last one. This returns false once no calls remain.
+    fn take_call(&self) -> bool {
+        let remaining_calls =
+            self.remaining_calls
+                .fetch_update(Ordering::AcqRel, Ordering::Acquire, |calls| match calls {
+                    UNLIMITED_CALLS => Some(UNLIMITED_CALLS),
+                    0 => None,
+                    calls => Some(calls - 1),
+                });
+
+        match remaining_calls {
+            Ok(1) => {
+                if let Some(hook) = self.get() {
+                    let target = hook.target();
+                    if let Some(epoch) = state::epoch(target) {
+                        unsafe { hook::expire(target, epoch) };
+                    }
+                }
+                true
+            }
+            Ok(_) => true,
+            Err(_) => false,
+        }
+    }
+}
+
+// The remaining calls of a hook that doesn't expire
+const UNLIMITED_CALLS: usize = usize::MAX;
+
+/// Declares a static [`ConditionalHook`] for a function signature with named arguments, and generates its stub.
+///
+/// Safe and unsafe function signatures with an `extern` ABI are supported as well, like for
+/// [`instrument_hook!`](crate::instrument_hook).
+///
+/// # Example
+///
+/// ```rust
+/// use minhook::{conditional_hook, MH_STATUS};
+///
+/// conditional_hook! {
+///     static DIVIDE_HOOK: fn(x: i32, y: i32) -> i32;
+/// }
+///
+/// fn main() -> Result<(), MH_STATUS> {
+///     unsafe { DIVIDE_HOOK.enable(divide, |&(_, y)| y == 0, divide_by_zero)? };
+///
+///     // Only the division by zero runs the detour
+///     assert_eq!(divide(6, 3), 2);
+///     assert_eq!(divide(6, 0), i32::MAX);
+///
+///     Ok(())
+/// }
+///
+/// fn divide(x: i32, y: i32) -> i32 {
+///     x.checked_div(y).unwrap_or(0)
+/// }
+///
+/// fn divide_by_zero(_: i32, _: i32) -> i32 {
+///     i32::MAX
+/// }
+/// ```
+#[macro_export]
+macro_rules! conditional_hook {
+    ($(#[$attr:meta])* $vis:vis static $name:ident: fn($($arg:ident: $ty:ty),* $(,)?) $(-> $ret:ty)?;) => {
+        $crate::conditional_hook!(@impl [$(#[$attr])*] $vis $name [] [$($arg: $ty),*] [$($ret)?]);
+    };
+    ($(#[$attr:meta])* $vis:vis static $name:ident: extern $abi:literal fn($($arg:ident: $ty:ty),* $(,)?) $(-> $ret:ty)?;) => {
+        $crate::conditional_hook!(@impl [$(#[$attr])*] $vis $name [extern $abi] [$($arg: $ty),*] [$($ret)?]);
+    };
+    ($(#[$attr:meta])* $vis:vis static $name:ident: unsafe extern $abi:literal fn($($arg:ident: $ty:ty),* $(,)?) $(-> $ret:ty)?;) => {
+        $crate::conditional_hook!(@impl [$(#[$attr])*] $vis $name [unsafe extern $abi] [$($arg: $ty),*] [$($ret)?]);
+    };
+    (@impl [$($attr:tt)*] $vis:vis $name:ident [$($qual:tt)*] [$($arg:ident: $ty:ty),*] [$($ret:ty)?]) => {
+        $($attr)*
+        $vis static $name: $crate::ConditionalHook<
+            $($qual)* fn($($ty),*) -> $crate::conditional_hook!(@ret $($ret)?),
+            ($($ty,)*),
+        > = $crate::ConditionalHook::new({
+            #[allow(unused_unsafe, clippy::too_many_arguments)]
+            $($qual)* fn stub($($arg: $ty),*) -> $crate::conditional_hook!(@ret $($ret)?) {
+                let args = ($($arg,)*);
+                let function = $name.select(&args);
+
+                let ($($arg,)*) = args;
+                unsafe { function($($arg),*) }
+            }
+
+            stub
+        });
+    };
+    (@ret) => { () };
+    (@ret $ret:ty) => { $ret };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::HookTest;
+
+    #[test]
+    fn test_conditional_hook() {
+        let _test = HookTest::start();
+
+        crate::conditional_hook! {
+            static TEST_FN_HOOK: extern "C" fn(x: u32) -> u32;
+        }
+
+        unsafe {
+            TEST_FN_HOOK
+                .enable(test_fn, |&(x,)| x % 2 == 0, test_fn_hook)
+                .unwrap();
+
+            // Calls that don't match go straight to the original function.
+            assert_eq!(test_fn(3), 4);
+            assert_eq!(test_fn(4), 120);
+
+            TEST_FN_HOOK.disable().unwrap();
+            assert_eq!(test_fn(4), 5);
+
+            // The hook disables itself after the last matching call.
+            TEST_FN_HOOK
+                .enable_for_calls(test_fn, |&(x,)| x % 2 == 0, test_fn_hook, 2)
+                .unwrap();
+            assert_eq!(test_fn(3), 4);
+            assert_eq!(test_fn(4), 120);
+            assert_eq!(test_fn(6), 122);
+            assert!(!TEST_FN_HOOK.get().unwrap().is_enabled());
+            assert_eq!(test_fn(8), 9);
+        }
+
+        #[inline(never)]
+        extern "C" fn test_fn(x: u32) -> u32 {
+            x + 1
+        }
+
+        extern "C" fn test_fn_hook(x: u32) -> u32 {
+            TEST_FN_HOOK.original()(x) + 115
+        }
+    }
+}
@@ -0,0 +1,256 @@
+use crate::{FnPtr, Hook, MH_STATUS, logging::debug};
+use std::{ffi::c_void, sync::OnceLock};
+
+/// A hook whose target is resolved when it is first enabled instead of when it is declared.
+///
+/// The resolver runs on the first call to [`DeferredHook::enable`], so a hook can be declared as a static even if its
+/// target is only found later, by scanning for a pattern or in a module that is loaded at runtime. If the resolver
+/// fails, it runs again on the next call.
+///
+/// A deferred hook is only meant to be declared as a static and is enabled on its own. It can't be added to a
+/// [`MhHooks`](crate::MhHooks) set, which needs its hooks to be created when they are added, so a set is given a hook
+/// created with [`MhHook::new`](crate::MhHook::new) once the target is resolved instead.
+///
+/// # Example
+///
+/// ```rust
+/// use minhook::{DeferredHook, MH_STATUS};
+/// use std::ffi::c_void;
+///
+/// static RETURN_0_HOOK: DeferredHook<fn() -> i32> = DeferredHook::new(find_return_0, return_1);
+///
+/// fn main() -> Result<(), MH_STATUS> {
+///     unsafe { RETURN_0_HOOK.enable()? };
+///     assert_eq!(return_0(), 1);
+///
+///     Ok(())
+/// }
+///
+/// // Stands in for a pattern scan or a lookup in a module loaded later
+/// fn find_return_0() -> Result<*mut c_void, MH_STATUS> {
+///     Ok(return_0 as fn() -> i32 as *mut c_void)
+/// }
+///
+/// fn return_0() -> i32 {
+///     0
+/// }
+///
+/// fn return_1() -> i32 {
+///     1
+/// }
+/// ```
+#[derive(Debug)]
+pub struct DeferredHook<F: FnPtr> {
+    resolve: fn() -> Result<*mut c_void, MH_STATUS>,
+    detour: F,
+    hook: OnceLock<Hook<F>>,
+}
+
+impl<F: FnPtr> DeferredHook<F> {
+    /// Creates a hook whose target is found by the resolver once the hook is enabled.
+    pub const fn new(resolve: fn() -> Result<*mut c_void, MH_STATUS>, detour: F) -> Self {
+        Self {
+            resolve,
+            detour,
+            hook: OnceLock::new(),
+        }
+    }
+
+    /// Resolves the target and creates the hook if it doesn't exist yet, and enables it.
+    ///
+    /// Returns the error of the resolver, or `MH_ERROR_NOT_EXECUTABLE` if it resolves to null. Otherwise this returns
+    /// the same errors as [`Hook::new`] and [`MhHook::enable`](crate::MhHook::enable).
+    ///
+    /// # Safety
+    ///
+    /// The resolved target must have the signature `F`.
+    pub unsafe fn enable(&self) -> Result<(), MH_STATUS> {
+        let hook = match self.hook.get() {
+            Some(hook) => hook,
+            None => {
+                let target = (self.resolve)()?;
+                if target.is_null() {
+                    return Err(MH_STATUS::MH_ERROR_NOT_EXECUTABLE);
+                }
+                debug!("Resolved deferred hook target to {:?}", target);
+
+                let hook = unsafe { Hook::new(F::from_ptr(target), self.detour)? };
+                self.hook.get_or_init(|| hook)
+            }
+        };
+
+        unsafe { hook.enable() }
+    }
+
+    /// Disables the hook. Returns `MH_ERROR_NOT_CREATED` if the hook was never enabled.
+    ///
+    /// # Safety
+    pub unsafe fn disable(&self) -> Result<(), MH_STATUS> {
+        match self.hook.get() {
+            Some(hook) => unsafe { hook.disable() },
+            None => Err(MH_STATUS::MH_ERROR_NOT_CREATED),
+        }
+    }
+
+    /// Returns the hook, if the target was resolved and the hook created.
+    pub fn get(&self) -> Option<&Hook<F>> {
+        self.hook.get()
+    }
+
+    /// Returns the trampoline, which calls the original target function, if the hook was created.
+    pub fn original(&self) -> Option<F> {
+        self.hook.get().map(Hook::original)
+    }
+}
+
+/// Declares statics holding a [`DeferredHook`] whose target is an export or the only match of a byte pattern in a
+/// module, which is looked up when the hook is first enabled.
+///
+/// Exports are found by [`module::find_export`](crate::module::find_export), and patterns by
+/// [`scan::find_one_in_module`](crate::scan::find_one_in_module). A pattern that can't be parsed fails with
+/// `MH_ERROR_FUNCTION_NOT_FOUND`.
+///
+/// # Example
+///
+/// ```rust
+/// use minhook::{deferred_hook, MH_STATUS};
+///
+/// deferred_hook! {
+///     static GET_TICK_COUNT_HOOK: unsafe extern "system" fn() -> u32 =
+///         api("kernel32.dll", "GetTickCount") => get_tick_count_hook;
+///     static GAME_UPDATE_HOOK: fn(f32) =
+///         pattern("game.dll", "48 89 5C 24 ?? 57 48 83 EC 20") => game_update_hook;
+/// }
+///
+/// fn main() -> Result<(), MH_STATUS> {
+///     unsafe { GET_TICK_COUNT_HOOK.enable()? };
+///
+///     // The module is not loaded, so its pattern can't be found yet
+///     assert_eq!(unsafe { GAME_UPDATE_HOOK.enable() }, Err(MH_STATUS::MH_ERROR_MODULE_NOT_FOUND));
+///
+///     Ok(())
+/// }
+///
+/// unsafe extern "system" fn get_tick_count_hook() -> u32 {
+///     unsafe { GET_TICK_COUNT_HOOK.original().unwrap()() }
+/// }
+///
+/// fn game_update_hook(delta: f32) {
+///     GAME_UPDATE_HOOK.original().unwrap()(delta * 0.5)
+/// }
+/// ```
+#[macro_export]
+macro_rules! deferred_hook {
+    ($($(#[$attr:meta])* $vis:vis static $name:ident: $ty:ty = $kind:ident($($args:tt)*) => $detour:expr;)*) => {
+        $(
+            $(#[$attr])*
+            $vis static $name: $crate::DeferredHook<$ty> = $crate::DeferredHook::new(
+                {
+                    fn resolve() -> ::std::result::Result<*mut ::std::ffi::c_void, $crate::MH_STATUS> {
+                        $crate::deferred_hook!(@resolve $kind($($args)*))
+                    }
+                    resolve
+                },
+                $detour as $ty,
+            );
+        )*
+    };
+    (@resolve api($module:expr, $proc_name:expr $(,)?)) => {{
+        let (module, proc_name) = ($module, $proc_name);
+        unsafe { $crate::module::find_export(module, proc_name) }
+    }};
+    (@resolve pattern($module:expr, $pattern:expr $(,)?)) => {{
+        let (module, pattern) = ($module, $pattern);
+        let pattern = pattern
+            .parse::<$crate::scan::Pattern>()
+            .map_err(|_| $crate::MH_STATUS::MH_ERROR_FUNCTION_NOT_FOUND)?;
+        unsafe { $crate::scan::find_one_in_module(module, &pattern) }
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::HookTest;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    #[test]
+    fn test_deferred_hook() {
+        let _test = HookTest::start();
+
+        static TEST_FN_HOOK: DeferredHook<FnType> = DeferredHook::new(resolve, test_fn_hook);
+        static FOUND: AtomicBool = AtomicBool::new(false);
+
+        unsafe {
+            // The resolver runs again after it failed.
+            assert_eq!(
+                TEST_FN_HOOK.enable(),
+                Err(MH_STATUS::MH_ERROR_FUNCTION_NOT_FOUND)
+            );
+            assert!(TEST_FN_HOOK.get().is_none());
+
+            FOUND.store(true, Ordering::SeqCst);
+            TEST_FN_HOOK.enable().unwrap();
+            assert_eq!(test_fn(), 1);
+            assert_eq!(TEST_FN_HOOK.original().unwrap()(), 0);
+
+            TEST_FN_HOOK.disable().unwrap();
+            assert_eq!(test_fn(), 0);
+        }
+
+        type FnType = fn() -> i32;
+
+        fn resolve() -> Result<*mut c_void, MH_STATUS> {
+            if FOUND.load(Ordering::SeqCst) {
+                Ok(test_fn as FnType as *mut c_void)
+            } else {
+                Err(MH_STATUS::MH_ERROR_FUNCTION_NOT_FOUND)
+            }
+        }
+
+        #[inline(never)]
+        fn test_fn() -> i32 {
+            0
+        }
+
+        fn test_fn_hook() -> i32 {
+            1
+        }
+    }
+
+    #[test]
+    fn test_deferred_hook_macro() {
+        let _test = HookTest::start();
+
+        crate::deferred_hook! {
+            static GET_LOGICAL_DRIVES_HOOK: FnType = api("kernel32.dll", "GetLogicalDrives") => get_logical_drives_hook;
+            static NOT_LOADED_HOOK: FnType = pattern("not_loaded.dll", "48 8B ?? 90") => get_logical_drives_hook;
+            static INVALID_PATTERN_HOOK: FnType = pattern("kernel32.dll", "48 XX") => get_logical_drives_hook;
+        }
+
+        unsafe extern "system" {
+            fn GetLogicalDrives() -> u32;
+        }
+
+        unsafe {
+            GET_LOGICAL_DRIVES_HOOK.enable().unwrap();
+            assert_eq!(GetLogicalDrives(), 0x5A5A);
+            GET_LOGICAL_DRIVES_HOOK.disable().unwrap();
+
+            assert_eq!(
+                NOT_LOADED_HOOK.enable(),
+                Err(MH_STATUS::MH_ERROR_MODULE_NOT_FOUND)
+            );
+            assert_eq!(
+                INVALID_PATTERN_HOOK.enable(),
+                Err(MH_STATUS::MH_ERROR_FUNCTION_NOT_FOUND)
+            );
+        }
+
+        type FnType = unsafe extern "system" fn() -> u32;
+
+        unsafe extern "system" fn get_logical_drives_hook() -> u32 {
+            0x5A5A
+        }
+    }
+}
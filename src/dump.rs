@@ -0,0 +1,118 @@
+use crate::{
+    logging::debug,
+    module::{self, ModuleOffset},
+    state,
+};
+use std::{ffi::c_void, fmt};
+
+/// A hook created through this crate, as listed by [`dump_hooks`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HookInfo {
+    /// The name of the hook, if it was given one.
+    pub name: Option<String>,
+    /// The address of the target function.
+    pub target: *mut c_void,
+    /// The module the target is in, or `None` if it's not in a loaded module.
+    pub module: Option<ModuleOffset>,
+    /// The address of the detour function.
+    pub detour: *mut c_void,
+    /// The address of the trampoline to the original function.
+    pub trampoline: *mut c_void,
+    /// Whether the hook is enabled.
+    pub enabled: bool,
+    /// The detour of another hooking library the target already jumped to when the hook was created, which the
+    /// trampoline calls.
+    pub foreign_detour: Option<*mut c_void>,
+}
+
+impl fmt::Display for HookInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {:?}",
+            self.name.as_deref().unwrap_or("<unnamed>"),
+            self.target
+        )?;
+        if let Some(module) = &self.module {
+            write!(f, " ({module})")?;
+        }
+        write!(
+            f,
+            " -> {:?}, trampoline {:?}, {}",
+            self.detour,
+            self.trampoline,
+            if self.enabled { "enabled" } else { "disabled" }
+        )?;
+        if let Some(foreign_detour) = self.foreign_detour {
+            write!(f, ", wraps {foreign_detour:?}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns all hooks that are created through this crate, ordered by the address of their target.
+///
+/// This is meant for diagnostics, such as writing the hooks to a log before a crash dump is created. Hooks created
+/// directly through [`sys`](crate::sys) are not listed.
+pub fn dump_hooks() -> Vec<HookInfo> {
+    let mut hooks = state::created();
+    for hook in &mut hooks {
+        hook.module = module::locate(hook.target);
+    }
+
+    hooks
+}
+
+/// Logs all hooks returned by [`dump_hooks`], one per line.
+pub fn log_hooks() {
+    let hooks = dump_hooks();
+    debug!("{} hooks are created", hooks.len());
+    for hook in &hooks {
+        debug!("{}", hook);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MhHookBuilder;
+    use crate::testing::HookTest;
+
+    #[test]
+    fn test_dump_hooks() {
+        let _test = HookTest::start();
+
+        unsafe {
+            let hook = MhHookBuilder::new()
+                .target_api("kernel32.dll", "GetCurrentThreadStackLimits")
+                .detour(get_current_thread_stack_limits_hook as FnType as *mut c_void)
+                .name("GetCurrentThreadStackLimits")
+                .build()
+                .unwrap();
+
+            let target = hook.target();
+            let hooks = dump_hooks();
+            let info = hooks.iter().find(|info| info.target == target).unwrap();
+            assert_eq!(info.name.as_deref(), Some("GetCurrentThreadStackLimits"));
+            assert_eq!(
+                info.detour,
+                get_current_thread_stack_limits_hook as FnType as *mut c_void
+            );
+            assert_eq!(info.trampoline, hook.trampoline());
+            assert!(!info.enabled);
+            assert!(info.module.is_some());
+
+            hook.remove().unwrap();
+            assert!(dump_hooks().iter().all(|info| info.target != target));
+        }
+
+        type FnType = unsafe extern "system" fn(*mut usize, *mut usize);
+
+        unsafe extern "system" fn get_current_thread_stack_limits_hook(
+            _: *mut usize,
+            _: *mut usize,
+        ) {
+        }
+    }
+}
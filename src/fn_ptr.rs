@@ -0,0 +1,74 @@
+use std::ffi::c_void;
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// A function pointer type that can be hooked.
+///
+/// This trait is sealed. It is implemented for safe and unsafe function pointers with up to 12 arguments using the
+/// `Rust`, `C` or `system` ABI, and on 32-bit x86 also the `stdcall`, `fastcall` or `thiscall` ABI.
+pub trait FnPtr: Copy + sealed::Sealed {
+    /// Returns the address of the function.
+    fn as_ptr(self) -> *mut c_void;
+
+    /// Creates the function pointer from an address.
+    ///
+    /// # Safety
+    ///
+    /// The address must be non-null and point to a function with this signature.
+    unsafe fn from_ptr(ptr: *mut c_void) -> Self;
+}
+
+macro_rules! impl_fn_ptr {
+    ($($arg:ident),*) => {
+        impl_fn_ptr!(@abi "Rust", $($arg),*);
+        impl_fn_ptr!(@abi "C", $($arg),*);
+        impl_fn_ptr!(@abi "system", $($arg),*);
+        #[cfg(target_arch = "x86")]
+        impl_fn_ptr!(@abi "stdcall", $($arg),*);
+        #[cfg(target_arch = "x86")]
+        impl_fn_ptr!(@abi "fastcall", $($arg),*);
+        #[cfg(target_arch = "x86")]
+        impl_fn_ptr!(@abi "thiscall", $($arg),*);
+    };
+    (@abi $abi:literal, $($arg:ident),*) => {
+        impl<R, $($arg),*> sealed::Sealed for extern $abi fn($($arg),*) -> R {}
+
+        impl<R, $($arg),*> FnPtr for extern $abi fn($($arg),*) -> R {
+            fn as_ptr(self) -> *mut c_void {
+                self as *mut c_void
+            }
+
+            unsafe fn from_ptr(ptr: *mut c_void) -> Self {
+                unsafe { std::mem::transmute::<*mut c_void, Self>(ptr) }
+            }
+        }
+
+        impl<R, $($arg),*> sealed::Sealed for unsafe extern $abi fn($($arg),*) -> R {}
+
+        impl<R, $($arg),*> FnPtr for unsafe extern $abi fn($($arg),*) -> R {
+            fn as_ptr(self) -> *mut c_void {
+                self as *mut c_void
+            }
+
+            unsafe fn from_ptr(ptr: *mut c_void) -> Self {
+                unsafe { std::mem::transmute::<*mut c_void, Self>(ptr) }
+            }
+        }
+    };
+}
+
+impl_fn_ptr!();
+impl_fn_ptr!(A);
+impl_fn_ptr!(A, B);
+impl_fn_ptr!(A, B, C);
+impl_fn_ptr!(A, B, C, D);
+impl_fn_ptr!(A, B, C, D, E);
+impl_fn_ptr!(A, B, C, D, E, F);
+impl_fn_ptr!(A, B, C, D, E, F, G);
+impl_fn_ptr!(A, B, C, D, E, F, G, H);
+impl_fn_ptr!(A, B, C, D, E, F, G, H, I);
+impl_fn_ptr!(A, B, C, D, E, F, G, H, I, J);
+impl_fn_ptr!(A, B, C, D, E, F, G, H, I, J, K);
+impl_fn_ptr!(A, B, C, D, E, F, G, H, I, J, K, L);
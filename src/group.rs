@@ -0,0 +1,132 @@
+use crate::{ApplyError, ApplyStatus, MhHook, MhHooks};
+use std::ops::{Deref, DerefMut};
+
+/// A named set of hooks, such as all hooks of a "rendering" or "input" feature, that is toggled as a unit.
+///
+/// Toggling the group queues all of its hooks and applies them with a single `MH_ApplyQueued`. The underlying
+/// [`MhHooks`] is reachable through `Deref`, so hooks can be added to and taken out of the group.
+#[derive(Debug)]
+pub struct HookGroup {
+    name: String,
+    hooks: MhHooks,
+}
+
+impl HookGroup {
+    /// Creates a disabled group of hooks.
+    pub fn new<N: Into<String>, I: IntoIterator<Item = MhHook>>(name: N, hooks: I) -> Self {
+        Self {
+            name: name.into(),
+            hooks: MhHooks::new(hooks),
+        }
+    }
+
+    /// Returns the name of the group.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Enables all hooks of the group.
+    ///
+    /// # Safety
+    pub unsafe fn enable(&mut self) -> Result<ApplyStatus, ApplyError> {
+        unsafe { self.hooks.apply() }
+    }
+
+    /// Disables all hooks of the group.
+    ///
+    /// # Safety
+    pub unsafe fn disable(&mut self) -> Result<ApplyStatus, ApplyError> {
+        unsafe { self.hooks.unapply() }
+    }
+
+    /// Enables or disables all hooks of the group.
+    ///
+    /// # Safety
+    pub unsafe fn set_enabled(&mut self, enabled: bool) -> Result<ApplyStatus, ApplyError> {
+        if enabled {
+            unsafe { self.enable() }
+        } else {
+            unsafe { self.disable() }
+        }
+    }
+
+    /// Enables the group if it is disabled and the other way around. This returns whether the group is enabled now.
+    ///
+    /// # Safety
+    pub unsafe fn toggle(&mut self) -> Result<bool, ApplyError> {
+        let enabled = !self.is_enabled();
+        unsafe { self.set_enabled(enabled)? };
+
+        Ok(enabled)
+    }
+
+    /// Returns whether the group is enabled.
+    pub fn is_enabled(&self) -> bool {
+        self.hooks.is_applied()
+    }
+}
+
+impl Deref for HookGroup {
+    type Target = MhHooks;
+
+    fn deref(&self) -> &Self::Target {
+        &self.hooks
+    }
+}
+
+impl DerefMut for HookGroup {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.hooks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::HookTest;
+
+    #[test]
+    fn test_hook_group() {
+        let _test = HookTest::start();
+
+        unsafe {
+            let mut group = HookGroup::new(
+                "test",
+                [
+                    MhHook::from_fns::<FnType>(test_fn1, test_fn1_hook).unwrap(),
+                    MhHook::from_fns::<FnType>(test_fn2, test_fn2_hook).unwrap(),
+                ],
+            );
+            assert_eq!(group.name(), "test");
+            assert!(!group.is_enabled());
+
+            assert!(group.toggle().unwrap());
+            assert_eq!(test_fn1(), 1);
+            assert_eq!(test_fn2(), 3);
+
+            assert!(!group.toggle().unwrap());
+            assert_eq!(test_fn1(), 0);
+            assert_eq!(test_fn2(), 2);
+        }
+
+        type FnType = fn() -> i32;
+
+        #[inline(never)]
+        fn test_fn1() -> i32 {
+            0
+        }
+
+        fn test_fn1_hook() -> i32 {
+            1
+        }
+
+        #[inline(never)]
+        fn test_fn2() -> i32 {
+            2
+        }
+
+        fn test_fn2_hook() -> i32 {
+            3
+        }
+    }
+}
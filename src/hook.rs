@@ -0,0 +1,1615 @@
+use crate::{
+    ActiveCalls, FnPtr, MH_STATUS, MinHook, ModuleName,
+    logging::{debug, warning},
+    module,
+    module::DebugAddress,
+    state,
+};
+use std::{
+    ffi::c_void,
+    fmt,
+    ptr::null_mut,
+    sync::{Mutex, MutexGuard, PoisonError},
+    thread,
+    time::Duration,
+};
+
+// The size of the relative jump MinHook patches over the prologue of the target
+pub(crate) const PATCH_SIZE: usize = 5;
+
+// The size of the memory slot MinHook allocates for each trampoline
+#[cfg(target_pointer_width = "64")]
+const TRAMPOLINE_SIZE: usize = 64;
+#[cfg(target_pointer_width = "32")]
+const TRAMPOLINE_SIZE: usize = 32;
+
+// The size of the absolute jump MinHook uses on x64, `jmp qword ptr [rip+0]` followed by the address
+#[cfg(target_pointer_width = "64")]
+const ABSOLUTE_JUMP_SIZE: usize = 14;
+
+// The size of the jump MinHook uses to leave a trampoline
+#[cfg(target_pointer_width = "64")]
+const LEAVE_JUMP_SIZE: usize = ABSOLUTE_JUMP_SIZE;
+#[cfg(target_pointer_width = "32")]
+const LEAVE_JUMP_SIZE: usize = PATCH_SIZE;
+
+/// A hook created through MinHook.
+///
+/// The hook is created in a disabled state and keeps the addresses needed to work with it afterwards.
+///
+/// Its `Debug` output shows the target as `module.dll+0x1234`, followed by the name of its symbol if the `symbols`
+/// feature is enabled.
+pub struct MhHook {
+    handle: HookHandle,
+    adopted: bool,
+    name: Option<String>,
+    disabled_scopes: Mutex<DisabledScopes>,
+}
+
+// The scopes of `MhHook::with_disabled` that are running, and whether the last one enables the hook again
+#[derive(Debug, Default)]
+struct DisabledScopes {
+    depth: usize,
+    enable: bool,
+}
+
+// The addresses of a hook, which never change once it is created. They are only passed to MinHook or read as code,
+// never as Rust data, so they are kept as integers and the hook is `Send` and `Sync`. MinHook synchronizes its own
+// state internally.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct HookHandle {
+    target: usize,
+    trampoline: usize,
+}
+
+impl HookHandle {
+    fn new(target: *mut c_void, trampoline: *mut c_void) -> Self {
+        Self {
+            target: target as usize,
+            trampoline: trampoline as usize,
+        }
+    }
+
+    fn target(self) -> *mut c_void {
+        self.target as *mut c_void
+    }
+
+    fn trampoline(self) -> *mut c_void {
+        self.trampoline as *mut c_void
+    }
+}
+
+impl MhHook {
+    /// Creates a hook for the target function and detours it to the detour function.
+    ///
+    /// # Safety
+    pub unsafe fn new(target: *mut c_void, detour: *mut c_void) -> Result<Self, MH_STATUS> {
+        let trampoline = unsafe { MinHook::create_hook(target, detour)? };
+
+        Ok(Self {
+            handle: HookHandle::new(target, trampoline),
+            adopted: false,
+            name: None,
+            disabled_scopes: Mutex::default(),
+        })
+    }
+
+    /// Creates a hook from function pointers of the same type, so the target and detour are guaranteed to have the
+    /// same signature.
+    ///
+    /// # Safety
+    pub unsafe fn from_fns<F: FnPtr>(target: F, detour: F) -> Result<Self, MH_STATUS> {
+        unsafe { Self::new(target.as_ptr(), detour.as_ptr()) }
+    }
+
+    /// Creates a hook for an exported function, which is looked up by its module and function name.
+    ///
+    /// The hook is created with `MH_CreateHookApiEx`, so the resolved address of the function is available through
+    /// [`MhHook::target`]. MinHook resolves the function with `GetProcAddress`, which follows forwarded exports, so
+    /// an export such as `kernel32!AcquireSRWLockExclusive` hooks its implementation in `ntdll`. Use
+    /// [`module::exports`] to see where an export is forwarded to.
+    ///
+    /// Returns `MH_ERROR_MODULE_NOT_FOUND` if the module is not loaded, and `MH_ERROR_FUNCTION_NOT_FOUND` if the module
+    /// does not export the function.
+    ///
+    /// # Safety
+    pub unsafe fn new_api<M: Into<ModuleName>, P: AsRef<str>>(
+        module_name: M,
+        proc_name: P,
+        detour: *mut c_void,
+    ) -> Result<Self, MH_STATUS> {
+        let (trampoline, target) =
+            unsafe { MinHook::create_hook_api_ex(module_name, proc_name, detour)? };
+
+        Ok(Self {
+            handle: HookHandle::new(target, trampoline),
+            adopted: false,
+            name: None,
+            disabled_scopes: Mutex::default(),
+        })
+    }
+
+    /// Creates a hook for an exported function, like [`MhHook::new_api`], from a UTF-16 function name.
+    ///
+    /// The export is looked up in the export table of the module instead of by MinHook, so neither the module nor the
+    /// function name have to be representable in the ANSI code page.
+    ///
+    /// Returns `MH_ERROR_MODULE_NOT_FOUND` if the module is not loaded, and `MH_ERROR_FUNCTION_NOT_FOUND` if the name
+    /// is not valid UTF-16 or the module does not export the function.
+    ///
+    /// # Safety
+    pub unsafe fn new_api_wide<M: Into<ModuleName>>(
+        module_name: M,
+        proc_name: &[u16],
+        detour: *mut c_void,
+    ) -> Result<Self, MH_STATUS> {
+        let proc_name =
+            String::from_utf16(proc_name).map_err(|_| MH_STATUS::MH_ERROR_FUNCTION_NOT_FOUND)?;
+        let target = unsafe { module::find_export(module_name, &proc_name)? };
+
+        unsafe { Self::new(target, detour) }
+    }
+
+    /// Creates a hook for a function exported by a module under the ordinal, for modules that export functions without
+    /// names.
+    ///
+    /// Returns `MH_ERROR_MODULE_NOT_FOUND` if the module is not loaded, and `MH_ERROR_FUNCTION_NOT_FOUND` if the module
+    /// does not export the ordinal.
+    ///
+    /// # Safety
+    pub unsafe fn new_api_ordinal<M: Into<ModuleName>>(
+        module_name: M,
+        ordinal: u16,
+        detour: *mut c_void,
+    ) -> Result<Self, MH_STATUS> {
+        let target = unsafe { module::find_export_by_ordinal(module_name, ordinal)? };
+
+        unsafe { Self::new(target, detour) }
+    }
+
+    /// Creates a hook for the function at the relative virtual address `rva` in a loaded module.
+    ///
+    /// Returns `MH_ERROR_MODULE_NOT_FOUND` if the module is not loaded, and `MH_ERROR_NOT_EXECUTABLE` if the address is
+    /// outside the image of the module.
+    ///
+    /// # Safety
+    pub unsafe fn new_rva<M: Into<ModuleName>>(
+        module_name: M,
+        rva: usize,
+        detour: *mut c_void,
+    ) -> Result<Self, MH_STATUS> {
+        let target = unsafe { module::resolve_rva(module_name, rva)? };
+
+        unsafe { Self::new(target, detour) }
+    }
+
+    /// Creates a hook for the entry point of a loaded module, which the loader calls with the `DllMain` arguments when
+    /// the module is loaded, unloaded and when threads start and exit.
+    ///
+    /// Returns `MH_ERROR_MODULE_NOT_FOUND` if the module is not loaded, and `MH_ERROR_FUNCTION_NOT_FOUND` if it has no
+    /// entry point. The TLS callbacks of a module are found with [`module::tls_callbacks`].
+    ///
+    /// # Safety
+    pub unsafe fn new_entry_point<M: Into<ModuleName>>(
+        module_name: M,
+        detour: *mut c_void,
+    ) -> Result<Self, MH_STATUS> {
+        let target = unsafe { module::entry_point(module_name)? };
+
+        unsafe { Self::new(target, detour) }
+    }
+
+    /// Creates a hook for the target function, or adopts the hook if the target is already hooked.
+    ///
+    /// MinHook returns `MH_ERROR_ALREADY_CREATED` when a hook for the target already exists. Instead of failing, the
+    /// existing hook is adopted: it can still be enabled and disabled through its target, but MinHook does not hand out
+    /// the trampoline of an existing hook, so the original function can't be called through an adopted hook.
+    ///
+    /// Only hooks created through the same MinHook instance are detected.
+    ///
+    /// # Safety
+    pub unsafe fn new_or_existing(
+        target: *mut c_void,
+        detour: *mut c_void,
+    ) -> Result<Self, MH_STATUS> {
+        match unsafe { Self::new(target, detour) } {
+            Err(MH_STATUS::MH_ERROR_ALREADY_CREATED) => {
+                debug!("Adopting existing hook for {:?}", target);
+
+                Ok(Self {
+                    handle: HookHandle::new(target, null_mut()),
+                    adopted: true,
+                    name: None,
+                    disabled_scopes: Mutex::default(),
+                })
+            }
+            result => result,
+        }
+    }
+
+    /// Creates a hook for the target function, or returns another handle to the hook this crate already created for
+    /// the target with the same detour.
+    ///
+    /// Unlike [`MhHook::new_or_existing`], the handle has the trampoline of the existing hook, so independent parts of a
+    /// program can hook the same target without agreeing on who creates the hook. The handle counts as adopted, so
+    /// removing it removes the existing hook. Returns `MH_ERROR_ALREADY_CREATED` if the existing hook has another
+    /// detour, which [`HookChain`](crate::HookChain) combines instead.
+    ///
+    /// # Safety
+    pub unsafe fn new_or_get(target: *mut c_void, detour: *mut c_void) -> Result<Self, MH_STATUS> {
+        match unsafe { Self::new(target, detour) } {
+            Err(MH_STATUS::MH_ERROR_ALREADY_CREATED) if state::detour(target) == Some(detour) => {
+                debug!("Sharing existing hook for {:?}", target);
+                let trampoline = state::trampoline(target).unwrap_or(null_mut());
+
+                Ok(Self {
+                    handle: HookHandle::new(target, trampoline),
+                    adopted: true,
+                    name: None,
+                    disabled_scopes: Mutex::default(),
+                })
+            }
+            result => result,
+        }
+    }
+
+    /// Enables the hook.
+    ///
+    /// # Safety
+    pub unsafe fn enable(&self) -> Result<(), MH_STATUS> {
+        unsafe { MinHook::enable_hook(self.handle.target()) }
+    }
+
+    /// Disables the hook.
+    ///
+    /// # Safety
+    pub unsafe fn disable(&self) -> Result<(), MH_STATUS> {
+        unsafe { MinHook::disable_hook(self.handle.target()) }
+    }
+
+    /// Queues the hook for enabling. The hook is enabled by the next call to [`MinHook::apply_queued`], so enables and
+    /// disables of different hooks can be applied while suspending the other threads only once.
+    ///
+    /// # Safety
+    pub unsafe fn queue_enable(&self) -> Result<(), MH_STATUS> {
+        unsafe { MinHook::queue_enable_hook(self.handle.target()) }
+    }
+
+    /// Queues the hook for disabling. The hook is disabled by the next call to [`MinHook::apply_queued`].
+    ///
+    /// # Safety
+    pub unsafe fn queue_disable(&self) -> Result<(), MH_STATUS> {
+        unsafe { MinHook::queue_disable_hook(self.handle.target()) }
+    }
+
+    /// Disables and removes the hook with `MH_RemoveHook`, which frees its trampoline. For adopted hooks, this removes
+    /// the existing hook that was adopted.
+    ///
+    /// # Safety
+    ///
+    /// The trampoline must not be used after the hook is removed.
+    pub unsafe fn remove(self) -> Result<(), MH_STATUS> {
+        unsafe { MinHook::remove_hook(self.handle.target()) }
+    }
+
+    /// Disables the hook, waits until none of the calls counted in `calls` is active anymore, and removes the hook.
+    ///
+    /// Returns `false` if calls were still active after the timeout. The hook is then left disabled and is not removed,
+    /// so the trampoline stays valid for the calls that are still running.
+    ///
+    /// # Safety
+    ///
+    /// The detour must count all of its calls in `calls`, and the trampoline must not be used after the hook is removed.
+    pub unsafe fn remove_when_idle(
+        self,
+        calls: &ActiveCalls,
+        timeout: Duration,
+    ) -> Result<bool, MH_STATUS> {
+        match unsafe { self.disable() } {
+            Ok(()) | Err(MH_STATUS::MH_ERROR_DISABLED) => {}
+            Err(status) => return Err(status),
+        }
+
+        if !calls.wait_idle(timeout) {
+            warning!(
+                "Hook {:?} still has {} active calls, leaving it disabled",
+                self.handle.target(),
+                calls.count()
+            );
+            return Ok(false);
+        }
+
+        unsafe { self.remove() }.map(|()| true)
+    }
+
+    /// Detours the target to another function, keeping the hook and its trampoline.
+    ///
+    /// On x64, MinHook patches the target with a jump to a relay next to the trampoline, which jumps on to the detour.
+    /// Only the address in the relay is replaced, in a single write that a thread calling the target sees either
+    /// before or after. Returns `MH_ERROR_UNSUPPORTED_FUNCTION` on x86, where the target jumps to the detour directly,
+    /// and for adopted hooks or trampolines without a relay.
+    ///
+    /// # Safety
+    ///
+    /// The new detour must have the same signature as the target.
+    pub unsafe fn set_detour(&self, detour: *mut c_void) -> Result<(), MH_STATUS> {
+        #[cfg(target_pointer_width = "64")]
+        {
+            use crate::{
+                os_error,
+                win::{
+                    CURRENT_PROCESS, FlushInstructionCache, PAGE_EXECUTE_READWRITE, VirtualProtect,
+                },
+            };
+
+            // The relay follows the jump back to the target
+            let (offset, _) =
+                unsafe { self.jump_back() }.ok_or(MH_STATUS::MH_ERROR_UNSUPPORTED_FUNCTION)?;
+            let relay_offset = offset + ABSOLUTE_JUMP_SIZE;
+            if relay_offset + ABSOLUTE_JUMP_SIZE > TRAMPOLINE_SIZE {
+                return Err(MH_STATUS::MH_ERROR_UNSUPPORTED_FUNCTION);
+            }
+
+            let relay = unsafe { self.handle.trampoline().add(relay_offset) };
+            let code = unsafe { read_bytes(relay, ABSOLUTE_JUMP_SIZE) };
+            if jump_destination(&code, relay as usize).is_none() {
+                return Err(MH_STATUS::MH_ERROR_UNSUPPORTED_FUNCTION);
+            }
+
+            // The trampolines are aligned to their size, so the address doesn't cross a cache line
+            let address = unsafe { relay.add(ABSOLUTE_JUMP_SIZE - size_of::<u64>()) };
+            let size = size_of::<u64>();
+            let mut protect = 0;
+            if unsafe { VirtualProtect(address, size, PAGE_EXECUTE_READWRITE, &mut protect) } == 0 {
+                os_error::capture(MH_STATUS::MH_ERROR_MEMORY_PROTECT);
+                return Err(MH_STATUS::MH_ERROR_MEMORY_PROTECT);
+            }
+
+            unsafe {
+                (address as *mut u64).write_unaligned(detour as u64);
+                VirtualProtect(address, size, protect, &mut protect);
+                FlushInstructionCache(CURRENT_PROCESS, address, size);
+            }
+            debug!("Set detour of {:?} to {:?}", self.handle.target(), detour);
+            state::record_detour(self.handle.target(), detour);
+
+            Ok(())
+        }
+
+        #[cfg(target_pointer_width = "32")]
+        {
+            let _ = detour;
+            Err(MH_STATUS::MH_ERROR_UNSUPPORTED_FUNCTION)
+        }
+    }
+
+    /// Disables the hook while the closure runs, so the original target function can be called directly, and enables
+    /// it again afterwards, also when the closure panics.
+    ///
+    /// Scopes can be nested and used from several threads at once: the hook is enabled again when the last scope ends,
+    /// and only if it was enabled when the first scope started.
+    ///
+    /// # Safety
+    pub unsafe fn with_disabled<R, F: FnOnce() -> R>(&self, f: F) -> Result<R, MH_STATUS> {
+        {
+            let mut scopes = self.disabled_scopes();
+            if scopes.depth == 0 {
+                scopes.enable = match unsafe { self.disable() } {
+                    Ok(()) => true,
+                    Err(MH_STATUS::MH_ERROR_DISABLED) => false,
+                    Err(status) => return Err(status),
+                };
+            }
+            scopes.depth += 1;
+        }
+
+        let _scope = DisabledScope { hook: self };
+
+        Ok(f())
+    }
+
+    /// Enables the hook until the returned guard is dropped.
+    ///
+    /// # Safety
+    pub unsafe fn enable_scoped(&self) -> Result<EnabledHookGuard<'_>, MH_STATUS> {
+        unsafe { self.enable()? };
+
+        Ok(EnabledHookGuard {
+            hook: self,
+            remove: false,
+        })
+    }
+
+    /// Enables the hook, and disables it again once the duration has elapsed, for example to capture the calls of the
+    /// next few seconds and then get out of the way.
+    ///
+    /// A background thread queues the disable and applies the queued hooks, so other hooks that are queued by then are
+    /// applied as well. Calling this on an enabled hook starts the duration over. Nothing happens once the duration has
+    /// elapsed if the hook was enabled, disabled or removed in the meantime, or if a hook was created again for the
+    /// target.
+    ///
+    /// # Safety
+    pub unsafe fn enable_for(&self, duration: Duration) -> Result<(), MH_STATUS> {
+        match unsafe { self.enable() } {
+            Ok(()) | Err(MH_STATUS::MH_ERROR_ENABLED) => (),
+            Err(status) => return Err(status),
+        }
+        let epoch =
+            state::renew_epoch(self.handle.target()).ok_or(MH_STATUS::MH_ERROR_NOT_CREATED)?;
+
+        let handle = self.handle;
+        thread::spawn(move || {
+            thread::sleep(duration);
+            unsafe { expire(handle.target(), epoch) };
+        });
+
+        Ok(())
+    }
+
+    /// Returns the address of the hooked target function. For hooks created by name, this is the address MinHook
+    /// resolved from the module's exports.
+    pub fn target(&self) -> *mut c_void {
+        self.handle.target()
+    }
+
+    /// Returns the trampoline, which calls the original target function. This is null for hooks adopted by
+    /// [`MhHook::new_or_existing`].
+    pub fn trampoline(&self) -> *mut c_void {
+        self.handle.trampoline()
+    }
+
+    /// Returns the detour the target jumps to, or `None` once the hook is removed. For adopted hooks, this is the
+    /// detour of the existing hook if this crate created it.
+    pub fn detour(&self) -> Option<*mut c_void> {
+        state::detour(self.handle.target())
+    }
+
+    /// Returns the target and the trampoline of the hook, so it can be handed across FFI or stored by a framework that
+    /// manages its lifetime, and turned back into a hook with [`MhHook::from_raw_parts`].
+    ///
+    /// The hook stays created and keeps its state, so nothing is passed to MinHook.
+    pub fn into_raw_parts(self) -> (*mut c_void, *mut c_void) {
+        (self.handle.target(), self.handle.trampoline())
+    }
+
+    /// Turns the target and the trampoline returned by [`MhHook::into_raw_parts`] back into a hook, without creating
+    /// it again. The name of the hook is restored if it was created through this crate, and a hook without a
+    /// trampoline counts as adopted, like the hooks adopted by [`MhHook::new_or_existing`].
+    ///
+    /// # Safety
+    ///
+    /// The parts must come from [`MhHook::into_raw_parts`], and the hook must not have been removed since, which
+    /// would leave the trampoline dangling.
+    pub unsafe fn from_raw_parts(target: *mut c_void, trampoline: *mut c_void) -> Self {
+        Self {
+            handle: HookHandle::new(target, trampoline),
+            adopted: trampoline.is_null(),
+            name: state::name(target),
+            disabled_scopes: Mutex::default(),
+        }
+    }
+
+    /// Returns the trampoline as a function pointer, or `None` if the trampoline is null.
+    ///
+    /// # Safety
+    ///
+    /// `F` must match the signature of the target function.
+    pub unsafe fn trampoline_as<F: FnPtr>(&self) -> Option<F> {
+        if self.handle.trampoline().is_null() {
+            None
+        } else {
+            Some(unsafe { F::from_ptr(self.handle.trampoline()) })
+        }
+    }
+
+    /// Copies `len` bytes of the original code that MinHook relocated into the trampoline. This is empty for adopted
+    /// hooks.
+    ///
+    /// # Safety
+    ///
+    /// The trampoline must have at least `len` readable bytes.
+    pub unsafe fn original_bytes(&self, len: usize) -> Vec<u8> {
+        if self.handle.trampoline().is_null() {
+            Vec::new()
+        } else {
+            unsafe { read_bytes(self.handle.trampoline(), len) }
+        }
+    }
+
+    /// Copies the first `len` bytes of the target function, which are patched while the hook is enabled.
+    ///
+    /// # Safety
+    ///
+    /// The target function must have at least `len` readable bytes.
+    pub unsafe fn current_bytes(&self, len: usize) -> Vec<u8> {
+        unsafe { read_bytes(self.handle.target(), len) }
+    }
+
+    /// Returns the number of bytes of the prologue of the target that MinHook relocated into the trampoline, or `None`
+    /// for adopted hooks and for trampolines that don't jump back to the target.
+    ///
+    /// The length is found by looking for the jump back to the target at the end of the relocated code.
+    ///
+    /// # Safety
+    pub unsafe fn stolen_len(&self) -> Option<usize> {
+        unsafe { self.jump_back() }.map(|(_, len)| len)
+    }
+
+    /// Copies the relocated code in the trampoline, up to its jump back to the target. Relocated instructions may be
+    /// longer than the originals, for example when relative jumps are rewritten to absolute jumps.
+    ///
+    /// Returns `None` for adopted hooks and for trampolines that don't jump back to the target.
+    ///
+    /// # Safety
+    pub unsafe fn stolen_bytes(&self) -> Option<Vec<u8>> {
+        let (offset, _) = unsafe { self.jump_back() }?;
+
+        Some(unsafe { read_bytes(self.handle.trampoline(), offset) })
+    }
+
+    /// Copies the bytes of the target that the jump to the detour is patched over, which are the original bytes
+    /// while the hook is disabled.
+    ///
+    /// # Safety
+    pub unsafe fn patched_bytes(&self) -> Vec<u8> {
+        unsafe { self.current_bytes(PATCH_SIZE) }
+    }
+
+    // Returns the offset of the jump back to the target in the trampoline, and the number of bytes it skips
+    unsafe fn jump_back(&self) -> Option<(usize, usize)> {
+        if self.handle.trampoline().is_null() {
+            return None;
+        }
+
+        // Only whole jumps inside the slot are decoded, so nothing past the slot is read
+        let code = unsafe { read_bytes(self.handle.trampoline(), TRAMPOLINE_SIZE) };
+        let target = self.handle.target() as usize;
+        (0..=TRAMPOLINE_SIZE - LEAVE_JUMP_SIZE).find_map(|offset| {
+            let jump = &code[offset..offset + LEAVE_JUMP_SIZE];
+            let destination = jump_destination(jump, self.handle.trampoline() as usize + offset)?;
+            let len = destination.wrapping_sub(target);
+
+            (PATCH_SIZE..TRAMPOLINE_SIZE)
+                .contains(&len)
+                .then_some((offset, len))
+        })
+    }
+
+    /// Returns whether the jump to the detour is still in place at the target, or whether it was overwritten, for
+    /// example by an integrity check restoring the original code or by another hooking library. Disabled hooks are
+    /// always intact.
+    ///
+    /// [`repair_hooks`](crate::repair_hooks) patches the jump in again.
+    ///
+    /// # Safety
+    pub unsafe fn is_intact(&self) -> bool {
+        if !self.is_enabled() {
+            return true;
+        }
+
+        let detour = state::detour(self.handle.target()).unwrap_or(null_mut());
+        unsafe { is_patch_intact(self.handle.target(), self.handle.trampoline(), detour) }
+    }
+
+    /// Returns whether the hook is enabled.
+    ///
+    /// This mirrors the statuses returned for the target by the functions of [`MinHook`], including enabling or
+    /// disabling all hooks at once and applying queued hooks.
+    pub fn is_enabled(&self) -> bool {
+        state::is_enabled(self.handle.target())
+    }
+
+    /// Returns whether the hook is still created, which is no longer the case once it is removed or MinHook is
+    /// uninitialized.
+    pub fn is_created(&self) -> bool {
+        state::is_created(self.handle.target())
+    }
+
+    /// Returns the last status MinHook returned for the target, or `None` if MinHook was uninitialized since.
+    pub fn last_status(&self) -> Option<MH_STATUS> {
+        state::last_status(self.handle.target())
+    }
+
+    // The scopes stay consistent even if a panic happened while they were locked
+    fn disabled_scopes(&self) -> MutexGuard<'_, DisabledScopes> {
+        self.disabled_scopes
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Returns the name given to the hook by [`MhHookBuilder::name`](crate::MhHookBuilder::name).
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub(crate) fn with_name(mut self, name: Option<String>) -> Self {
+        state::record_name(self.handle.target(), name.clone());
+        self.name = name;
+        self
+    }
+
+    /// Returns the detour of another hooking library, such as an overlay, that the target already jumped to when the hook
+    /// was created.
+    ///
+    /// MinHook relocates that jump into the trampoline, so this hook wraps the foreign detour: the detour of this hook
+    /// runs first, and calling the trampoline runs the foreign detour.
+    pub fn foreign_detour(&self) -> Option<*mut c_void> {
+        state::foreign_detour(self.handle.target())
+    }
+
+    /// Returns whether the hook was adopted from an existing hook instead of being created.
+    pub fn is_adopted(&self) -> bool {
+        self.adopted
+    }
+}
+
+impl fmt::Debug for MhHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MhHook")
+            .field("name", &self.name)
+            .field("target", &DebugAddress(self.target()))
+            .field("trampoline", &DebugAddress(self.trampoline()))
+            .field("adopted", &self.adopted)
+            .field("enabled", &self.is_enabled())
+            .finish()
+    }
+}
+
+/// A guard that keeps a hook enabled, returned by [`MhHook::enable_scoped`].
+///
+/// The hook is disabled when the guard is dropped, which also happens while unwinding from a panic.
+#[must_use = "the hook is disabled again when the guard is dropped"]
+#[derive(Debug)]
+pub struct EnabledHookGuard<'a> {
+    hook: &'a MhHook,
+    remove: bool,
+}
+
+impl EnabledHookGuard<'_> {
+    /// Removes the hook instead of only disabling it when the guard is dropped. The trampoline of the hook must not be
+    /// used after that.
+    pub fn remove_on_drop(mut self) -> Self {
+        self.remove = true;
+        self
+    }
+}
+
+impl Drop for EnabledHookGuard<'_> {
+    fn drop(&mut self) {
+        // Errors can't be returned from drop, and are already logged by MinHook
+        if self.remove {
+            let _ = unsafe { MinHook::remove_hook(self.hook.handle.target()) };
+        } else {
+            let _ = unsafe { self.hook.disable() };
+        }
+    }
+}
+
+// Ends a scope of `MhHook::with_disabled` when dropped
+struct DisabledScope<'a> {
+    hook: &'a MhHook,
+}
+
+impl Drop for DisabledScope<'_> {
+    fn drop(&mut self) {
+        let mut scopes = self.hook.disabled_scopes();
+        scopes.depth -= 1;
+
+        // Errors can't be returned from drop, and are already logged by MinHook
+        if scopes.depth == 0 && scopes.enable {
+            let _ = unsafe { self.hook.enable() };
+        }
+    }
+}
+
+unsafe fn read_bytes(address: *const c_void, len: usize) -> Vec<u8> {
+    unsafe { std::slice::from_raw_parts(address as *const u8, len).to_vec() }
+}
+
+// Returns the destination of the jump MinHook uses to leave a trampoline, if the code starts with one
+#[cfg(target_pointer_width = "64")]
+fn jump_destination(code: &[u8], _address: usize) -> Option<usize> {
+    // jmp qword ptr [rip+0], followed by the absolute address
+    match code {
+        [0xFF, 0x25, 0, 0, 0, 0, destination @ ..] if destination.len() >= 8 => {
+            Some(u64::from_le_bytes(destination[..8].try_into().unwrap()) as usize)
+        }
+        _ => None,
+    }
+}
+
+// Returns the destination of the jump MinHook uses to leave a trampoline, if the code starts with one
+#[cfg(target_pointer_width = "32")]
+fn jump_destination(code: &[u8], address: usize) -> Option<usize> {
+    relative_jump_destination(code, address)
+}
+
+// Returns the destination of the jump the target starts with, if another hooking library detoured it already. MinHook
+// relocates the jump into the trampoline, so the new detour runs first and the trampoline calls the foreign detour.
+pub(crate) unsafe fn foreign_detour(target: *mut c_void) -> Option<*mut c_void> {
+    let mut code = unsafe { read_bytes(target, PATCH_SIZE) };
+    if code.starts_with(&[0xFF, 0x25]) {
+        // The absolute address follows an indirect jump
+        code = unsafe { read_bytes(target, PATCH_SIZE + 9) };
+    }
+
+    relative_jump_destination(&code, target as usize)
+        .or_else(|| jump_destination(&code, target as usize))
+        .map(|destination| destination as *mut c_void)
+}
+
+// Returns whether the target still starts with the jump MinHook patched over it, which leads to the relay in the
+// trampoline's memory slot on 64-bit and straight to the detour on 32-bit
+pub(crate) unsafe fn is_patch_intact(
+    target: *mut c_void,
+    trampoline: *mut c_void,
+    detour: *mut c_void,
+) -> bool {
+    let mut address = target as usize;
+    let mut code = unsafe { read_bytes(target, PATCH_SIZE) };
+
+    // Hot-patchable functions start with a short jump back to the long jump in the padding before them
+    if code.starts_with(&[0xEB, 0xF9]) {
+        address -= PATCH_SIZE;
+        code = unsafe { read_bytes(address as *const c_void, PATCH_SIZE) };
+    }
+
+    let Some(destination) = relative_jump_destination(&code, address) else {
+        return false;
+    };
+    let trampoline = trampoline as usize;
+
+    destination == detour as usize
+        || (trampoline..trampoline + TRAMPOLINE_SIZE).contains(&destination)
+}
+
+// Returns the destination of a `jmp rel32` at the address, if the code starts with one
+fn relative_jump_destination(code: &[u8], address: usize) -> Option<usize> {
+    // jmp rel32, relative to the end of the jump
+    match code {
+        [0xE9, offset @ ..] if offset.len() >= 4 => {
+            let offset = i32::from_le_bytes(offset[..4].try_into().unwrap());
+            Some(
+                address
+                    .wrapping_add(PATCH_SIZE)
+                    .wrapping_add(offset as usize),
+            )
+        }
+        _ => None,
+    }
+}
+
+// Queues the disable of a hook that expired and applies it, unless the hook changed since its epoch
+pub(crate) unsafe fn expire(target: *mut c_void, epoch: u64) {
+    if state::epoch(target) != Some(epoch) {
+        debug!("Hook {:?} changed before it expired", target);
+        return;
+    }
+
+    if unsafe { MinHook::queue_disable_hook(target) }.is_err() {
+        return;
+    }
+
+    match unsafe { MinHook::apply_queued() } {
+        Ok(()) => debug!("Hook {:?} expired", target),
+        Err(status) => warning!("Failed to disable expired hook {:?}: {:?}", target, status),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::HookTest;
+
+    unsafe extern "system" {
+        fn GetCurrentThreadId() -> u32;
+        fn GetModuleHandleW(lpModuleName: *const u16) -> *mut c_void;
+        fn GetProcAddress(hModule: *mut c_void, lpProcName: *const u8) -> *mut c_void;
+    }
+
+    #[test]
+    fn test_new_api_target() {
+        let _test = HookTest::start();
+
+        unsafe {
+            let hook = MhHook::new_api(
+                "kernel32.dll",
+                "GetCurrentProcessorNumber",
+                get_current_processor_number_hook as FnType as *mut c_void,
+            )
+            .unwrap();
+
+            // The target is the same address the loader resolves for the export.
+            let module = GetModuleHandleW(ModuleName::from("kernel32.dll").as_ptr());
+            let address = GetProcAddress(module, c"GetCurrentProcessorNumber".as_ptr() as *const _);
+            assert_eq!(hook.target(), address);
+        }
+
+        type FnType = unsafe extern "system" fn() -> u32;
+
+        unsafe extern "system" fn get_current_processor_number_hook() -> u32 {
+            0
+        }
+    }
+
+    #[test]
+    fn test_new_api_trampoline() {
+        let _test = HookTest::start();
+
+        unsafe {
+            let hook = MhHook::new_api(
+                "kernel32.dll",
+                "GetCurrentThreadId",
+                get_current_thread_id_hook as FnType as *mut c_void,
+            )
+            .unwrap();
+            assert!(!hook.target().is_null());
+
+            // The trampoline calls the original function, even though the hook is not enabled.
+            let original = hook.trampoline_as::<FnType>().unwrap();
+            assert_eq!(original(), GetCurrentThreadId());
+        }
+
+        type FnType = unsafe extern "system" fn() -> u32;
+
+        unsafe extern "system" fn get_current_thread_id_hook() -> u32 {
+            0
+        }
+    }
+
+    #[test]
+    fn test_new_api_wide() {
+        let _test = HookTest::start();
+
+        // Kernelbase implements the function itself, while kernel32 may forward it.
+        unsafe {
+            let module_name = "kernelbase.dll".encode_utf16().collect::<Vec<_>>();
+            let proc_name = "GetCurrentThread".encode_utf16().collect::<Vec<_>>();
+            let detour = get_current_thread_hook as FnType as *mut c_void;
+
+            let hook =
+                MhHook::new_api_wide(ModuleName::from_wide(&module_name), &proc_name, detour)
+                    .unwrap();
+            let module = GetModuleHandleW(ModuleName::from("kernelbase.dll").as_ptr());
+            let address = GetProcAddress(module, c"GetCurrentThread".as_ptr() as *const _);
+            assert_eq!(hook.target(), address);
+
+            // Unpaired surrogates can't name an export.
+            let status = MhHook::new_api_wide("kernelbase.dll", &[0xD800], detour).unwrap_err();
+            assert_eq!(status, MH_STATUS::MH_ERROR_FUNCTION_NOT_FOUND);
+
+            hook.remove().unwrap();
+        }
+
+        type FnType = unsafe extern "system" fn() -> *mut c_void;
+
+        unsafe extern "system" fn get_current_thread_hook() -> *mut c_void {
+            null_mut()
+        }
+    }
+
+    #[test]
+    fn test_new_api_ordinal() {
+        let _test = HookTest::start();
+
+        unsafe {
+            let export = module::exports("kernelbase.dll")
+                .unwrap()
+                .find(|export| export.name.as_deref() == Some("GetCommandLineA"))
+                .unwrap();
+            let detour = get_command_line_a_hook as FnType as *mut c_void;
+
+            let hook = MhHook::new_api_ordinal("kernelbase.dll", export.ordinal, detour).unwrap();
+            assert_eq!(hook.target(), export.address);
+            hook.remove().unwrap();
+
+            let status = MhHook::new_api_ordinal("kernelbase.dll", u16::MAX, detour).unwrap_err();
+            assert_eq!(status, MH_STATUS::MH_ERROR_FUNCTION_NOT_FOUND);
+        }
+
+        type FnType = unsafe extern "system" fn() -> *const u8;
+
+        unsafe extern "system" fn get_command_line_a_hook() -> *const u8 {
+            std::ptr::null()
+        }
+    }
+
+    #[test]
+    fn test_new_api_not_found() {
+        let _test = HookTest::start();
+
+        unsafe {
+            let detour = get_tick_count_hook as FnType as *mut c_void;
+
+            let status = MhHook::new_api("not_loaded.dll", "GetTickCount", detour).unwrap_err();
+            assert_eq!(status, MH_STATUS::MH_ERROR_MODULE_NOT_FOUND);
+
+            let status = MhHook::new_api("kernel32.dll", "NotAnExport", detour).unwrap_err();
+            assert_eq!(status, MH_STATUS::MH_ERROR_FUNCTION_NOT_FOUND);
+
+            // Names with interior nulls are rejected instead of being truncated.
+            let status = MhHook::new_api("kernel32.dll\0x", "GetTickCount", detour).unwrap_err();
+            assert_eq!(status, MH_STATUS::MH_ERROR_MODULE_NOT_FOUND);
+
+            let status = MhHook::new_api("kernel32.dll", "GetTickCount\0x", detour).unwrap_err();
+            assert_eq!(status, MH_STATUS::MH_ERROR_FUNCTION_NOT_FOUND);
+        }
+
+        type FnType = unsafe extern "system" fn() -> u32;
+
+        unsafe extern "system" fn get_tick_count_hook() -> u32 {
+            0
+        }
+    }
+
+    #[test]
+    fn test_enable_disable() {
+        let _test = HookTest::start();
+
+        unsafe {
+            let hook = MhHook::from_fns::<FnType>(test_fn, test_fn_hook).unwrap();
+
+            hook.enable().unwrap();
+            assert_eq!(test_fn(), 1);
+            assert_eq!(hook.enable(), Err(MH_STATUS::MH_ERROR_ENABLED));
+
+            hook.disable().unwrap();
+            assert_eq!(test_fn(), 0);
+            assert_eq!(hook.disable(), Err(MH_STATUS::MH_ERROR_DISABLED));
+        }
+
+        type FnType = fn() -> i32;
+
+        #[inline(never)]
+        fn test_fn() -> i32 {
+            0
+        }
+
+        fn test_fn_hook() -> i32 {
+            1
+        }
+    }
+
+    #[test]
+    fn test_queue() {
+        let _test = HookTest::start();
+
+        unsafe {
+            let hook1 = MhHook::from_fns::<FnType>(test_fn1, test_fn1_hook).unwrap();
+            let hook2 = MhHook::from_fns::<FnType>(test_fn2, test_fn2_hook).unwrap();
+            hook1.enable().unwrap();
+
+            // Disable one hook and enable the other in the same apply.
+            hook1.queue_disable().unwrap();
+            hook2.queue_enable().unwrap();
+            MinHook::apply_queued().unwrap();
+            assert_eq!(test_fn1(), 0);
+            assert_eq!(test_fn2(), 3);
+
+            hook2.queue_disable().unwrap();
+            MinHook::apply_queued().unwrap();
+            assert_eq!(test_fn2(), 2);
+        }
+
+        type FnType = fn() -> i32;
+
+        #[inline(never)]
+        fn test_fn1() -> i32 {
+            0
+        }
+
+        fn test_fn1_hook() -> i32 {
+            1
+        }
+
+        #[inline(never)]
+        fn test_fn2() -> i32 {
+            2
+        }
+
+        fn test_fn2_hook() -> i32 {
+            3
+        }
+    }
+
+    #[test]
+    fn test_remove() {
+        let _test = HookTest::start();
+
+        unsafe {
+            let hook = MhHook::from_fns::<FnType>(test_fn, test_fn_hook).unwrap();
+            hook.enable().unwrap();
+            assert_eq!(test_fn(), 1);
+
+            // Removing the hook disables it, and allows creating it again.
+            hook.remove().unwrap();
+            assert_eq!(test_fn(), 0);
+
+            let hook = MhHook::from_fns::<FnType>(test_fn, test_fn_hook).unwrap();
+            hook.remove().unwrap();
+        }
+
+        type FnType = fn() -> i32;
+
+        #[inline(never)]
+        fn test_fn() -> i32 {
+            0
+        }
+
+        fn test_fn_hook() -> i32 {
+            1
+        }
+    }
+
+    #[test]
+    fn test_state() {
+        let _test = HookTest::start();
+
+        unsafe {
+            let hook = MhHook::from_fns::<FnType>(test_fn, test_fn_hook).unwrap();
+            assert!(hook.is_created());
+            assert!(!hook.is_enabled());
+            assert_eq!(hook.last_status(), Some(MH_STATUS::MH_OK));
+
+            hook.enable().unwrap();
+            assert!(hook.is_enabled());
+
+            // A failing call is recorded without changing the state.
+            assert_eq!(hook.enable(), Err(MH_STATUS::MH_ERROR_ENABLED));
+            assert!(hook.is_enabled());
+            assert_eq!(hook.last_status(), Some(MH_STATUS::MH_ERROR_ENABLED));
+
+            hook.queue_disable().unwrap();
+            assert!(hook.is_enabled());
+            MinHook::apply_queued().unwrap();
+            assert!(!hook.is_enabled());
+
+            let target = hook.target();
+            hook.remove().unwrap();
+            assert!(!state::is_created(target));
+        }
+
+        type FnType = fn() -> i32;
+
+        #[inline(never)]
+        fn test_fn() -> i32 {
+            0
+        }
+
+        fn test_fn_hook() -> i32 {
+            1
+        }
+    }
+
+    #[test]
+    fn test_with_disabled() {
+        let _test = HookTest::start();
+
+        unsafe {
+            let hook = MhHook::from_fns::<FnType>(test_fn, test_fn_hook).unwrap();
+            hook.enable().unwrap();
+
+            let result = hook.with_disabled(|| {
+                assert_eq!(test_fn(), 0);
+
+                // Nested scopes keep the hook disabled until the outer scope ends.
+                hook.with_disabled(|| assert_eq!(test_fn(), 0)).unwrap();
+                test_fn()
+            });
+            assert_eq!(result, Ok(0));
+            assert_eq!(test_fn(), 1);
+
+            // The hook is enabled again when the closure panics.
+            let result = std::panic::catch_unwind(|| {
+                hook.with_disabled(|| panic!("Panic while the hook is disabled"))
+            });
+            assert!(result.is_err());
+            assert_eq!(test_fn(), 1);
+
+            // A disabled hook stays disabled.
+            hook.disable().unwrap();
+            hook.with_disabled(|| ()).unwrap();
+            assert_eq!(test_fn(), 0);
+        }
+
+        type FnType = fn() -> i32;
+
+        #[inline(never)]
+        fn test_fn() -> i32 {
+            0
+        }
+
+        fn test_fn_hook() -> i32 {
+            1
+        }
+    }
+
+    #[test]
+    fn test_enable_scoped() {
+        let _test = HookTest::start();
+
+        unsafe {
+            let hook = MhHook::from_fns::<FnType>(test_fn, test_fn_hook).unwrap();
+
+            {
+                let _guard = hook.enable_scoped().unwrap();
+                assert_eq!(test_fn(), 1);
+            }
+            assert_eq!(test_fn(), 0);
+
+            // The hook is also disabled when unwinding.
+            let result = std::panic::catch_unwind(|| {
+                let _guard = hook.enable_scoped().unwrap();
+                assert_eq!(test_fn(), 1);
+                panic!("Panic while the hook is enabled");
+            });
+            assert!(result.is_err());
+            assert_eq!(test_fn(), 0);
+
+            // Removing the hook on drop allows creating it again.
+            drop(hook.enable_scoped().unwrap().remove_on_drop());
+            assert_eq!(test_fn(), 0);
+            MhHook::from_fns::<FnType>(test_fn, test_fn_hook).unwrap();
+        }
+
+        type FnType = fn() -> i32;
+
+        #[inline(never)]
+        fn test_fn() -> i32 {
+            0
+        }
+
+        fn test_fn_hook() -> i32 {
+            1
+        }
+    }
+
+    #[test]
+    fn test_new_rva() {
+        let _test = HookTest::start();
+
+        let executable = std::env::current_exe().unwrap();
+        let executable = executable.file_name().unwrap().to_str().unwrap();
+
+        unsafe {
+            let base = module::base_address(executable).unwrap();
+            let rva = (test_fn as FnType as usize) - (base as usize);
+
+            let hook =
+                MhHook::new_rva(executable, rva, test_fn_hook as FnType as *mut c_void).unwrap();
+            assert_eq!(hook.target(), test_fn as FnType as *mut c_void);
+
+            hook.enable().unwrap();
+            assert_eq!(test_fn(), 1);
+            hook.disable().unwrap();
+        }
+
+        type FnType = fn() -> i32;
+
+        #[inline(never)]
+        fn test_fn() -> i32 {
+            0
+        }
+
+        fn test_fn_hook() -> i32 {
+            1
+        }
+    }
+
+    #[test]
+    fn test_new_or_get() {
+        let _test = HookTest::start();
+
+        unsafe {
+            let hook = MhHook::from_fns::<FnType>(test_fn, test_fn_hook).unwrap();
+
+            // The shared handle calls the same trampoline.
+            let shared = MhHook::new_or_get(
+                test_fn as FnType as *mut c_void,
+                test_fn_hook as FnType as *mut c_void,
+            )
+            .unwrap();
+            assert!(shared.is_adopted());
+            assert_eq!(shared.trampoline(), hook.trampoline());
+            shared.enable().unwrap();
+            assert!(hook.is_enabled());
+            assert_eq!(test_fn(), 91);
+            assert_eq!(shared.trampoline_as::<FnType>().unwrap()(), 90);
+
+            assert_eq!(
+                MhHook::new_or_get(
+                    test_fn as FnType as *mut c_void,
+                    test_fn_other as FnType as *mut c_void,
+                )
+                .unwrap_err(),
+                MH_STATUS::MH_ERROR_ALREADY_CREATED
+            );
+            hook.remove().unwrap();
+        }
+
+        type FnType = fn() -> i32;
+
+        #[inline(never)]
+        fn test_fn() -> i32 {
+            std::hint::black_box(90)
+        }
+
+        fn test_fn_hook() -> i32 {
+            91
+        }
+
+        #[inline(never)]
+        fn test_fn_other() -> i32 {
+            92
+        }
+    }
+
+    #[test]
+    fn test_new_or_existing() {
+        let _test = HookTest::start();
+
+        unsafe {
+            let hook = MhHook::from_fns::<FnType>(test_fn, test_fn_hook).unwrap();
+            assert!(!hook.is_adopted());
+            assert!(!hook.trampoline().is_null());
+
+            // Hooking the same target again adopts the existing hook.
+            let adopted = MhHook::new_or_existing(
+                test_fn as FnType as *mut c_void,
+                test_fn_hook as FnType as *mut c_void,
+            )
+            .unwrap();
+            assert!(adopted.is_adopted());
+            assert!(adopted.trampoline().is_null());
+            assert!(adopted.trampoline_as::<FnType>().is_none());
+            assert_eq!(adopted.target(), hook.target());
+
+            // The adopted hook can still be enabled and disabled.
+            MinHook::enable_hook(adopted.target()).unwrap();
+            assert_eq!(test_fn(), 1);
+            MinHook::disable_hook(adopted.target()).unwrap();
+            assert_eq!(test_fn(), 0);
+        }
+
+        type FnType = fn() -> i32;
+
+        #[inline(never)]
+        fn test_fn() -> i32 {
+            0
+        }
+
+        fn test_fn_hook() -> i32 {
+            1
+        }
+    }
+
+    #[test]
+    fn test_set_detour() {
+        let _test = HookTest::start();
+
+        unsafe {
+            let hook = MhHook::from_fns::<FnType>(test_fn, test_fn_hook_1).unwrap();
+            hook.enable().unwrap();
+            assert_eq!(test_fn(), 1);
+
+            let status = hook.set_detour(test_fn_hook_2 as FnType as *mut c_void);
+            if cfg!(target_pointer_width = "64") {
+                status.unwrap();
+                assert_eq!(test_fn(), 2);
+                assert_eq!(hook.detour(), Some(test_fn_hook_2 as FnType as *mut c_void));
+
+                // The trampoline is kept, and MinHook keeps jumping to the relay.
+                assert_eq!(hook.trampoline_as::<FnType>().unwrap()(), 0);
+                hook.disable().unwrap();
+                hook.enable().unwrap();
+                assert_eq!(test_fn(), 2);
+            } else {
+                assert_eq!(status, Err(MH_STATUS::MH_ERROR_UNSUPPORTED_FUNCTION));
+            }
+
+            hook.remove().unwrap();
+        }
+
+        type FnType = fn() -> i32;
+
+        #[inline(never)]
+        fn test_fn() -> i32 {
+            std::hint::black_box(0)
+        }
+
+        fn test_fn_hook_1() -> i32 {
+            1
+        }
+
+        fn test_fn_hook_2() -> i32 {
+            2
+        }
+    }
+
+    #[test]
+    fn test_patched_bytes() {
+        let _test = HookTest::start();
+
+        unsafe {
+            let hook = MhHook::from_fns::<FnType>(test_fn, test_fn_hook).unwrap();
+            let before = hook.current_bytes(5);
+
+            // The trampoline starts with the original code.
+            assert_eq!(hook.original_bytes(1), before[..1]);
+            assert_eq!(hook.patched_bytes(), before);
+            let stolen_len = hook.stolen_len().unwrap();
+            assert!(stolen_len >= 5);
+            let (offset, _) = hook.jump_back().unwrap();
+            assert!(offset + LEAVE_JUMP_SIZE <= TRAMPOLINE_SIZE);
+            assert!(hook.stolen_bytes().unwrap().len() >= stolen_len);
+
+            // Enabling the hook patches a jump over the prologue.
+            MinHook::enable_hook(hook.target()).unwrap();
+            let after = hook.current_bytes(5);
+            assert_ne!(before, after);
+            assert_eq!(after[0], 0xE9);
+
+            // Disabling the hook restores the prologue.
+            MinHook::disable_hook(hook.target()).unwrap();
+            assert_eq!(hook.current_bytes(5), before);
+        }
+
+        type FnType = fn() -> i32;
+
+        #[inline(never)]
+        fn test_fn() -> i32 {
+            0
+        }
+
+        fn test_fn_hook() -> i32 {
+            1
+        }
+    }
+
+    #[test]
+    fn test_send_sync() {
+        let _test = HookTest::start();
+
+        // Hooks are thread-safe because of their fields, not because of unsafe impls.
+        fn assert_send_sync<T: Send + Sync>() {}
+
+        assert_send_sync::<MhHook>();
+        assert_send_sync::<crate::MhHooks>();
+        assert_send_sync::<crate::ApplyError>();
+    }
+
+    #[test]
+    fn test_debug() {
+        let _test = HookTest::start();
+
+        unsafe {
+            let hook = MhHook::from_fns::<FnType>(test_fn, test_fn_hook).unwrap();
+
+            // The target is in the test executable, while the trampoline is outside of any module.
+            let debug = format!("{hook:?}");
+            assert!(debug.contains(".exe+0x"), "{debug}");
+            assert!(debug.contains(&format!("trampoline: {:?}", hook.trampoline())));
+
+            hook.remove().unwrap();
+        }
+
+        type FnType = fn() -> i32;
+
+        #[inline(never)]
+        fn test_fn() -> i32 {
+            0
+        }
+
+        fn test_fn_hook() -> i32 {
+            1
+        }
+    }
+
+    #[test]
+    fn test_foreign_detour() {
+        let _test = HookTest::start();
+
+        use crate::{
+            ForeignDetour, MhHookBuilder,
+            win::{PAGE_EXECUTE_READWRITE, VirtualProtect},
+        };
+
+        unsafe {
+            // Detour the target to another function, like an overlay would.
+            let target = test_fn as FnType as *mut c_void;
+            let offset =
+                (test_fn_foreign as FnType as usize).wrapping_sub(target as usize + 5) as i32;
+            let mut jump = [0xE9, 0, 0, 0, 0];
+            jump[1..].copy_from_slice(&offset.to_le_bytes());
+            let mut protect = 0;
+            VirtualProtect(target, 5, PAGE_EXECUTE_READWRITE, &mut protect);
+            std::ptr::copy_nonoverlapping(jump.as_ptr(), target as *mut u8, 5);
+            VirtualProtect(target, 5, protect, &mut protect);
+
+            let status = MhHookBuilder::new()
+                .target_addr(target)
+                .detour(test_fn_hook as FnType as *mut c_void)
+                .foreign_detour(ForeignDetour::Reject)
+                .build()
+                .unwrap_err();
+            assert_eq!(status, MH_STATUS::MH_ERROR_ALREADY_CREATED);
+
+            // The hook wraps the foreign detour, which the trampoline calls.
+            let hook = MhHook::from_fns::<FnType>(test_fn, test_fn_hook).unwrap();
+            assert_eq!(
+                hook.foreign_detour(),
+                Some(test_fn_foreign as FnType as *mut c_void)
+            );
+            hook.enable().unwrap();
+            assert_eq!(test_fn(), 52);
+            assert_eq!(
+                std::mem::transmute::<*mut c_void, FnType>(hook.trampoline())(),
+                51
+            );
+            hook.remove().unwrap();
+        }
+
+        type FnType = fn() -> i32;
+
+        #[inline(never)]
+        fn test_fn() -> i32 {
+            std::hint::black_box(50)
+        }
+
+        #[inline(never)]
+        fn test_fn_foreign() -> i32 {
+            51
+        }
+
+        fn test_fn_hook() -> i32 {
+            52
+        }
+    }
+
+    #[test]
+    fn test_enable_for() {
+        let _test = HookTest::start();
+
+        unsafe {
+            let hook = MhHook::from_fns::<FnType>(test_fn, test_fn_hook).unwrap();
+            hook.enable_for(Duration::from_millis(10)).unwrap();
+            assert_eq!(test_fn(), 131);
+
+            // The hook is disabled from the background thread once it expires.
+            for _ in 0..100 {
+                if !hook.is_enabled() {
+                    break;
+                }
+                thread::sleep(Duration::from_millis(10));
+            }
+            assert!(!hook.is_enabled());
+            assert_eq!(test_fn(), 130);
+
+            // A hook that is disabled and enabled again is no longer disabled by the earlier timer.
+            hook.enable_for(Duration::from_millis(10)).unwrap();
+            hook.disable().unwrap();
+            hook.enable().unwrap();
+            thread::sleep(Duration::from_millis(50));
+            assert!(hook.is_enabled());
+            hook.remove().unwrap();
+        }
+
+        type FnType = fn() -> i32;
+
+        #[inline(never)]
+        fn test_fn() -> i32 {
+            std::hint::black_box(130)
+        }
+
+        fn test_fn_hook() -> i32 {
+            std::hint::black_box(131)
+        }
+    }
+
+    #[test]
+    fn test_raw_parts() {
+        let _test = HookTest::start();
+
+        unsafe {
+            let hook = MhHook::from_fns::<FnType>(test_fn, test_fn_hook)
+                .unwrap()
+                .with_name(Some("test_fn".to_owned()));
+            assert_eq!(hook.detour(), Some(test_fn_hook as FnType as *mut c_void));
+
+            // The hook survives the round trip without being created again.
+            let (target, trampoline) = hook.into_raw_parts();
+            let hook = MhHook::from_raw_parts(target, trampoline);
+            assert_eq!(hook.name(), Some("test_fn"));
+            assert_eq!(hook.trampoline(), trampoline);
+            assert!(!hook.is_adopted());
+            hook.enable().unwrap();
+            assert_eq!(test_fn(), 151);
+
+            // An adopted hook has no trampoline, and is still adopted after the round trip.
+            let adopted =
+                MhHook::new_or_existing(target, test_fn_hook as FnType as *mut c_void).unwrap();
+            let (target, trampoline) = adopted.into_raw_parts();
+            assert!(MhHook::from_raw_parts(target, trampoline).is_adopted());
+
+            hook.remove().unwrap();
+            assert_eq!(test_fn(), 150);
+        }
+
+        type FnType = fn() -> i32;
+
+        #[inline(never)]
+        fn test_fn() -> i32 {
+            std::hint::black_box(150)
+        }
+
+        fn test_fn_hook() -> i32 {
+            std::hint::black_box(151)
+        }
+    }
+
+    #[test]
+    fn test_new_entry_point() {
+        let _test = HookTest::start();
+
+        unsafe {
+            let hook =
+                MhHook::new_entry_point("kernel32.dll", entry_point_hook as FnType as _).unwrap();
+            assert_eq!(hook.target(), module::entry_point("kernel32.dll").unwrap());
+            hook.remove().unwrap();
+
+            assert_eq!(
+                MhHook::new_entry_point("not_loaded.dll", entry_point_hook as FnType as _)
+                    .unwrap_err(),
+                MH_STATUS::MH_ERROR_MODULE_NOT_FOUND
+            );
+        }
+
+        type FnType = unsafe extern "system" fn(*mut c_void, u32, *mut c_void) -> i32;
+
+        unsafe extern "system" fn entry_point_hook(_: *mut c_void, _: u32, _: *mut c_void) -> i32 {
+            1
+        }
+    }
+
+    #[test]
+    fn test_remove_when_idle() {
+        let _test = HookTest::start();
+
+        static CALLS: ActiveCalls = ActiveCalls::new();
+        let target = test_fn as FnType as *mut c_void;
+
+        unsafe {
+            // A hook with a running call is left disabled.
+            let call = CALLS.enter();
+            let hook = MhHook::from_fns::<FnType>(test_fn, test_fn_hook).unwrap();
+            hook.enable().unwrap();
+            assert_eq!(
+                hook.remove_when_idle(&CALLS, Duration::from_millis(10)),
+                Ok(false)
+            );
+            assert!(state::is_created(target));
+            assert_eq!(test_fn(), 210);
+            drop(call);
+            MinHook::remove_hook(target).unwrap();
+
+            let hook = MhHook::from_fns::<FnType>(test_fn, test_fn_hook).unwrap();
+            hook.enable().unwrap();
+            assert_eq!(
+                hook.remove_when_idle(&CALLS, Duration::from_secs(10)),
+                Ok(true)
+            );
+            assert!(!state::is_created(target));
+        }
+
+        type FnType = fn() -> i32;
+
+        #[inline(never)]
+        fn test_fn() -> i32 {
+            std::hint::black_box(210)
+        }
+
+        fn test_fn_hook() -> i32 {
+            std::hint::black_box(211)
+        }
+    }
+}
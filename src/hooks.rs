@@ -0,0 +1,966 @@
+use crate::{
+    MH_STATUS, MhHook, MhHookBuilder, MinHook,
+    logging::{debug, warning},
+    module, state,
+};
+use std::{ffi::c_void, fmt, ops::Deref, slice, vec};
+
+/// A set of hooks that are enabled and disabled together.
+///
+/// The hooks are queued and then applied at once, so all other threads are only suspended once for the whole set.
+/// Hooks can be added to and taken out of the set afterwards, and hooks added while the set is applied are enabled.
+///
+/// How hooks that fail are handled is decided by its [`ApplyPolicy`], which enables the other hooks by default. When
+/// the set is dropped, its hooks are handled according to its [`DropPolicy`], which disables them by default, one
+/// after another in the reverse order they were added.
+///
+/// Applying or unapplying the set is atomic with respect to other threads, since they are suspended while the queued
+/// hooks are applied, so no thread ever sees only a part of the set enabled.
+#[derive(Debug)]
+pub struct MhHooks {
+    hooks: Vec<MhHook>,
+    applied: bool,
+    apply_policy: ApplyPolicy,
+    drop_policy: DropPolicy,
+}
+
+/// What happens to the other hooks of a [`MhHooks`] set when some of its hooks can't be enabled or disabled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ApplyPolicy {
+    /// The failed hooks are skipped and the other hooks are still applied, so a target that is missing or unsupported
+    /// in one version of the host only loses its own hook.
+    #[default]
+    BestEffort,
+    /// None of the hooks are applied if any of them fails.
+    FailFast,
+}
+
+/// What happens to the hooks of a [`MhHooks`] set when the set is dropped.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum DropPolicy {
+    /// The hooks are disabled, so the targets run the original code again.
+    #[default]
+    Disable,
+    /// The hooks are removed, which also disables them and frees their trampolines.
+    Remove,
+    /// The hooks are left enabled or disabled as they are.
+    Leak,
+}
+
+impl MhHooks {
+    /// Creates a set of hooks.
+    pub fn new<I: IntoIterator<Item = MhHook>>(hooks: I) -> Self {
+        Self {
+            hooks: hooks.into_iter().collect(),
+            applied: false,
+            apply_policy: ApplyPolicy::default(),
+            drop_policy: DropPolicy::default(),
+        }
+    }
+
+    /// Creates a set of hooks from the results of creating them, or returns the first error. The hooks that were
+    /// created before the error are dropped, which leaves them created.
+    pub fn try_new<I: IntoIterator<Item = Result<MhHook, E>>, E>(hooks: I) -> Result<Self, E> {
+        hooks.into_iter().collect()
+    }
+
+    /// Creates the hooks of the builders and enables them with a single `MH_ApplyQueued`, so the other threads are only
+    /// suspended once no matter how many hooks are created. The builders are not enabled one by one, even if they are
+    /// set to be.
+    ///
+    /// With [`ApplyPolicy::BestEffort`], hooks that can't be created are skipped and the others are still enabled.
+    /// With [`ApplyPolicy::FailFast`], the hooks that were created are removed again if one can't be created, and
+    /// nothing is enabled. The set is returned either way, with an error that lists the failed hooks like for
+    /// [`MhHooks::apply`]. A hook that can't be created is listed with a null target if it wasn't given by address.
+    ///
+    /// # Safety
+    pub unsafe fn create_and_apply<I: IntoIterator<Item = MhHookBuilder>>(
+        builders: I,
+        apply_policy: ApplyPolicy,
+    ) -> (Self, Result<ApplyStatus, ApplyError>) {
+        let mut hooks = Vec::new();
+        let mut failed = Vec::new();
+        for builder in builders {
+            let target = builder.target_address();
+            match unsafe { builder.create() } {
+                Ok(hook) => hooks.push(hook),
+                Err(status) => failed.push((target, status)),
+            }
+        }
+
+        let mut set = Self::new(hooks).with_apply_policy(apply_policy);
+        if apply_policy == ApplyPolicy::FailFast && !failed.is_empty() {
+            debug!(
+                "Removing {} created hooks after {} hooks could not be created",
+                set.len(),
+                failed.len()
+            );
+            for hook in std::mem::take(&mut set.hooks) {
+                let _ = unsafe { hook.remove() };
+            }
+
+            return (set, Err(ApplyError::new(Vec::new(), failed)));
+        }
+
+        let result = match unsafe { set.apply() } {
+            Ok(status) if failed.is_empty() => Ok(status),
+            Ok(_) => Err(ApplyError::new(
+                set.hooks.iter().map(MhHook::target).collect(),
+                failed,
+            )),
+            Err(mut error) => {
+                let failed = failed
+                    .into_iter()
+                    .map(|(target, status)| (target as usize, status));
+                error.failed.splice(0..0, failed);
+                Err(error)
+            }
+        };
+
+        (set, result)
+    }
+
+    /// Sets what happens to the other hooks when some hooks of the set fail to be applied.
+    pub fn with_apply_policy(mut self, apply_policy: ApplyPolicy) -> Self {
+        self.apply_policy = apply_policy;
+        self
+    }
+
+    /// Returns what happens to the other hooks when some hooks of the set fail to be applied.
+    pub fn apply_policy(&self) -> ApplyPolicy {
+        self.apply_policy
+    }
+
+    /// Sets what happens to the hooks when the set is dropped. Like applying the set, this is deferred to a worker
+    /// thread if the set is dropped while the loader lock is held.
+    pub fn with_drop_policy(mut self, drop_policy: DropPolicy) -> Self {
+        self.drop_policy = drop_policy;
+        self
+    }
+
+    /// Returns what happens to the hooks when the set is dropped.
+    pub fn drop_policy(&self) -> DropPolicy {
+        self.drop_policy
+    }
+
+    /// Adds a hook to the set. The hook is enabled if the set is applied.
+    ///
+    /// # Safety
+    pub unsafe fn push(&mut self, hook: MhHook) -> Result<(), MH_STATUS> {
+        if self.applied {
+            match unsafe { hook.enable() } {
+                Ok(()) | Err(MH_STATUS::MH_ERROR_ENABLED) => (),
+                Err(status) => return Err(status),
+            }
+        }
+
+        self.hooks.push(hook);
+        Ok(())
+    }
+
+    /// Takes the hook for the target out of the set, and leaves it enabled or disabled as it is.
+    pub fn take(&mut self, target: *mut c_void) -> Option<MhHook> {
+        let index = self.hooks.iter().position(|hook| hook.target() == target)?;
+
+        Some(self.hooks.remove(index))
+    }
+
+    /// Takes the hook for the target out of the set, and removes it from MinHook, which also disables it. This returns
+    /// whether the set contained a hook for the target.
+    ///
+    /// # Safety
+    pub unsafe fn remove(&mut self, target: *mut c_void) -> Result<bool, MH_STATUS> {
+        match self.take(target) {
+            Some(hook) => unsafe { hook.remove() }.map(|()| true),
+            None => Ok(false),
+        }
+    }
+
+    /// Returns whether the set is applied, which is the case after [`MhHooks::apply`] until [`MhHooks::unapply`].
+    pub fn is_applied(&self) -> bool {
+        self.applied
+    }
+
+    /// Returns the hooks in the set.
+    pub fn hooks(&self) -> &[MhHook] {
+        &self.hooks
+    }
+
+    /// Returns the number of hooks in the set.
+    pub fn len(&self) -> usize {
+        self.hooks.len()
+    }
+
+    /// Returns whether the set is empty.
+    pub fn is_empty(&self) -> bool {
+        self.hooks.is_empty()
+    }
+
+    /// Enables all hooks in the set.
+    ///
+    /// With [`ApplyPolicy::BestEffort`], hooks that can't be queued are skipped and the others are still enabled. With
+    /// [`ApplyPolicy::FailFast`], no hook is enabled if one can't be queued. The error lists the hooks that failed with
+    /// their status, and the hooks that were enabled.
+    ///
+    /// Applying the queue suspends all other threads, which may deadlock while the current thread holds the loader
+    /// lock, such as in `DllMain`. The hooks are then applied by a worker thread once the loader lock is released, and
+    /// this returns [`ApplyStatus::Deferred`]. Failures of deferred hooks are only logged.
+    ///
+    /// # Safety
+    pub unsafe fn apply(&mut self) -> Result<ApplyStatus, ApplyError> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("apply", hooks = ?self.names()).entered();
+        let applied = std::mem::replace(&mut self.applied, true);
+
+        let targets = self.hooks.iter().map(MhHook::target).collect();
+        unsafe { self.queue_and_apply(targets, MinHook::queue_enable_hook) }.inspect_err(|error| {
+            if error.applied.is_empty() && self.apply_policy == ApplyPolicy::FailFast {
+                self.applied = applied;
+            }
+        })
+    }
+
+    /// Disables all hooks in the set.
+    ///
+    /// Hooks that can't be queued are handled according to the [`ApplyPolicy`] like for [`MhHooks::apply`]. The error
+    /// lists the hooks that failed with their status, and the hooks that were disabled. Like [`MhHooks::apply`], this is
+    /// deferred while the loader lock is held.
+    ///
+    /// # Safety
+    pub unsafe fn unapply(&mut self) -> Result<ApplyStatus, ApplyError> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("unapply", hooks = ?self.names()).entered();
+        let applied = std::mem::replace(&mut self.applied, false);
+
+        let targets = self.hooks.iter().map(MhHook::target).collect();
+        unsafe { self.queue_and_apply(targets, MinHook::queue_disable_hook) }.inspect_err(|error| {
+            if error.applied.is_empty() && self.apply_policy == ApplyPolicy::FailFast {
+                self.applied = applied;
+            }
+        })
+    }
+
+    /// Enables all hooks in the set, or none of them.
+    ///
+    /// The hooks are enabled one after another instead of through the queue, so a failing hook is detected before the
+    /// rest of the set is touched. If a hook fails, the hooks enabled so far are disabled again, and the error lists
+    /// the failed hook. Hooks that were already enabled before are left enabled. Any hook that could not be disabled
+    /// again is listed as applied in the error.
+    ///
+    /// # Safety
+    pub unsafe fn apply_transactional(&mut self) -> Result<(), ApplyError> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("apply_transactional", hooks = ?self.names()).entered();
+        let mut enabled = Vec::with_capacity(self.hooks.len());
+
+        for hook in &self.hooks {
+            match unsafe { hook.enable() } {
+                Ok(()) => enabled.push(hook.target()),
+                Err(MH_STATUS::MH_ERROR_ENABLED) => (),
+                Err(status) => {
+                    debug!(
+                        "Rolling back {} hooks after {:?} failed: {:?}",
+                        enabled.len(),
+                        hook.target(),
+                        status
+                    );
+
+                    // Roll back in reverse order, and keep the hooks that could not be disabled
+                    enabled.reverse();
+                    enabled.retain(|&target| unsafe { MinHook::disable_hook(target) }.is_err());
+
+                    return Err(ApplyError::new(enabled, vec![(hook.target(), status)]));
+                }
+            }
+        }
+
+        self.applied = true;
+        Ok(())
+    }
+
+    // The names of the hooks, or their targets if they have no name
+    #[cfg(feature = "tracing")]
+    fn names(&self) -> Vec<String> {
+        self.hooks
+            .iter()
+            .map(|hook| match hook.name() {
+                Some(name) => name.to_owned(),
+                None => format!("{:?}", hook.target()),
+            })
+            .collect()
+    }
+
+    unsafe fn queue_and_apply(
+        &self,
+        targets: Vec<*mut c_void>,
+        queue: QueueFn,
+    ) -> Result<ApplyStatus, ApplyError> {
+        let policy = self.apply_policy;
+        if !module::is_loader_lock_held() {
+            return unsafe { queue_and_apply(&targets, queue, policy) }
+                .map(|()| ApplyStatus::Applied);
+        }
+
+        debug!(
+            "Deferring {} hooks to a worker thread while the loader lock is held",
+            targets.len()
+        );
+
+        // The worker only starts running once the loader lock is released
+        let targets = targets
+            .into_iter()
+            .map(|target| target as usize)
+            .collect::<Vec<_>>();
+        std::thread::spawn(move || {
+            let targets = targets
+                .into_iter()
+                .map(|target| target as *mut c_void)
+                .collect::<Vec<_>>();
+            if let Err(error) = unsafe { queue_and_apply(&targets, queue, policy) } {
+                warning!("Deferred hooks could not be applied: {}", error);
+            }
+        });
+
+        Ok(ApplyStatus::Deferred)
+    }
+}
+
+impl Deref for MhHooks {
+    type Target = [MhHook];
+
+    fn deref(&self) -> &Self::Target {
+        &self.hooks
+    }
+}
+
+impl FromIterator<MhHook> for MhHooks {
+    fn from_iter<I: IntoIterator<Item = MhHook>>(hooks: I) -> Self {
+        Self::new(hooks)
+    }
+}
+
+impl Extend<MhHook> for MhHooks {
+    /// Adds the hooks to the set, and leaves them enabled or disabled as they are. Use [`MhHooks::push`] to enable
+    /// hooks that are added while the set is applied.
+    fn extend<I: IntoIterator<Item = MhHook>>(&mut self, hooks: I) {
+        self.hooks.extend(hooks);
+    }
+}
+
+impl IntoIterator for MhHooks {
+    type Item = MhHook;
+    type IntoIter = vec::IntoIter<MhHook>;
+
+    /// Takes the hooks out of the set, and leaves them enabled or disabled as they are like [`MhHooks::take`].
+    fn into_iter(mut self) -> Self::IntoIter {
+        std::mem::take(&mut self.hooks).into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a MhHooks {
+    type Item = &'a MhHook;
+    type IntoIter = slice::Iter<'a, MhHook>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.hooks.iter()
+    }
+}
+
+impl Drop for MhHooks {
+    fn drop(&mut self) {
+        let policy = self.drop_policy;
+        if policy == DropPolicy::Leak {
+            return;
+        }
+
+        let targets = self.hooks.iter().rev().map(MhHook::target);
+        if !module::is_loader_lock_held() {
+            for target in targets {
+                unsafe { drop_hook(target, policy) };
+            }
+            return;
+        }
+
+        debug!(
+            "Deferring the drop of {} hooks to a worker thread while the loader lock is held",
+            self.hooks.len()
+        );
+
+        // Like for `queue_and_apply`, the worker only starts running once the loader lock is released
+        let targets = targets.map(|target| target as usize).collect::<Vec<_>>();
+        std::thread::spawn(move || {
+            for target in targets {
+                unsafe { drop_hook(target as *mut c_void, policy) };
+            }
+        });
+    }
+}
+
+// Handles the hook of the target as the drop policy says, and logs if that fails
+unsafe fn drop_hook(target: *mut c_void, policy: DropPolicy) {
+    let result = match policy {
+        DropPolicy::Disable if state::is_enabled(target) => unsafe {
+            MinHook::disable_hook(target)
+        },
+        DropPolicy::Remove if state::is_created(target) => unsafe { MinHook::remove_hook(target) },
+        _ => Ok(()),
+    };
+
+    if let Err(status) = result {
+        warning!(
+            "Could not {:?} hook for {:?} on drop: {}",
+            policy,
+            target,
+            status
+        );
+    }
+}
+
+// Queues an operation on a hook, such as `MinHook::queue_enable_hook`
+pub(crate) type QueueFn = unsafe fn(*mut c_void) -> Result<(), MH_STATUS>;
+
+unsafe fn queue_and_apply(
+    targets: &[*mut c_void],
+    queue: QueueFn,
+    policy: ApplyPolicy,
+) -> Result<(), ApplyError> {
+    let operations = targets
+        .iter()
+        .map(|&target| (target, queue))
+        .collect::<Vec<_>>();
+
+    unsafe { apply_operations(&operations, policy) }
+}
+
+// Queues an operation for each target, and applies them all at once
+pub(crate) unsafe fn apply_operations(
+    operations: &[(*mut c_void, QueueFn)],
+    policy: ApplyPolicy,
+) -> Result<(), ApplyError> {
+    let mut queued = Vec::with_capacity(operations.len());
+    let mut failed = Vec::new();
+
+    for &(target, queue) in operations {
+        match unsafe { queue(target) } {
+            Ok(()) => queued.push(target),
+            Err(status) => failed.push((target, status)),
+        }
+    }
+
+    if policy == ApplyPolicy::FailFast && !failed.is_empty() {
+        debug!(
+            "Not applying {} hooks after {} hooks failed",
+            queued.len(),
+            failed.len()
+        );
+
+        // Queue the current state of each hook again, so applying the queue later leaves them as they are
+        for target in queued {
+            let _ = unsafe {
+                if state::is_enabled(target) {
+                    MinHook::queue_enable_hook(target)
+                } else {
+                    MinHook::queue_disable_hook(target)
+                }
+            };
+        }
+
+        return Err(ApplyError::new(Vec::new(), failed));
+    }
+
+    // None of the queued hooks are applied if applying the queue fails
+    let applied = match unsafe { MinHook::apply_queued() } {
+        Ok(()) => queued,
+        Err(status) => {
+            failed.extend(queued.into_iter().map(|target| (target, status)));
+            Vec::new()
+        }
+    };
+
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        debug!(
+            "Applied {} hooks, {} hooks failed",
+            applied.len(),
+            failed.len()
+        );
+
+        Err(ApplyError::new(applied, failed))
+    }
+}
+
+/// Whether a [`MhHooks`] set was applied right away, or deferred.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ApplyStatus {
+    /// The hooks were applied before returning.
+    Applied,
+    /// The hooks are applied by a worker thread once the loader lock is released.
+    Deferred,
+}
+
+/// The error returned when some hooks of a [`MhHooks`] set could not be applied.
+#[derive(Clone, PartialEq, Eq)]
+pub struct ApplyError {
+    // The targets are only kept as addresses, so the error can be sent to other threads
+    applied: Vec<usize>,
+    failed: Vec<(usize, MH_STATUS)>,
+}
+
+impl ApplyError {
+    fn new(applied: Vec<*mut c_void>, failed: Vec<(*mut c_void, MH_STATUS)>) -> Self {
+        Self {
+            applied: applied.into_iter().map(|target| target as usize).collect(),
+            failed: failed
+                .into_iter()
+                .map(|(target, status)| (target as usize, status))
+                .collect(),
+        }
+    }
+
+    /// Returns the targets of the hooks that were applied.
+    pub fn applied(&self) -> Vec<*mut c_void> {
+        self.applied
+            .iter()
+            .map(|&target| target as *mut c_void)
+            .collect()
+    }
+
+    /// Returns the targets of the hooks that failed, with the status they failed with.
+    pub fn failed(&self) -> Vec<(*mut c_void, MH_STATUS)> {
+        self.failed
+            .iter()
+            .map(|&(target, status)| (target as *mut c_void, status))
+            .collect()
+    }
+}
+
+impl fmt::Debug for ApplyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ApplyError")
+            .field("applied", &self.applied())
+            .field("failed", &self.failed())
+            .finish()
+    }
+}
+
+impl fmt::Display for ApplyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} of {} hooks could not be applied",
+            self.failed.len(),
+            self.failed.len() + self.applied.len()
+        )?;
+
+        for &(target, status) in &self.failed {
+            write!(f, "\n{:?}: {status}", target as *mut c_void)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for ApplyError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::HookTest;
+
+    #[test]
+    fn test_apply_unapply() {
+        let _test = HookTest::start();
+
+        unsafe {
+            let mut hooks = MhHooks::new([
+                MhHook::from_fns::<FnType1>(test_fn1, test_fn1_hook).unwrap(),
+                MhHook::from_fns::<FnType2>(test_fn2, test_fn2_hook).unwrap(),
+            ]);
+            assert_eq!(hooks.len(), 2);
+
+            hooks.apply().unwrap();
+            assert_eq!(test_fn1(), 1);
+            assert_eq!(test_fn2(1), 2);
+
+            hooks.unapply().unwrap();
+            assert_eq!(test_fn1(), 0);
+            assert_eq!(test_fn2(1), 1);
+        }
+
+        type FnType1 = fn() -> i32;
+        type FnType2 = fn(i32) -> i32;
+
+        #[inline(never)]
+        fn test_fn1() -> i32 {
+            0
+        }
+
+        fn test_fn1_hook() -> i32 {
+            1
+        }
+
+        #[inline(never)]
+        fn test_fn2(x: i32) -> i32 {
+            x
+        }
+
+        fn test_fn2_hook(x: i32) -> i32 {
+            x + 1
+        }
+    }
+
+    #[test]
+    fn test_apply_transactional() {
+        let _test = HookTest::start();
+
+        unsafe {
+            let removed = MhHook::from_fns::<FnType>(test_fn1, test_fn1_hook).unwrap();
+            MinHook::remove_hook(removed.target()).unwrap();
+
+            let mut hooks = MhHooks::new([
+                MhHook::from_fns::<FnType>(test_fn2, test_fn2_hook).unwrap(),
+                removed,
+            ]);
+
+            // The first hook is enabled, and disabled again once the removed hook fails.
+            let error = hooks.apply_transactional().unwrap_err();
+            assert_eq!(
+                error.failed(),
+                [(
+                    test_fn1 as FnType as *mut c_void,
+                    MH_STATUS::MH_ERROR_NOT_CREATED
+                )]
+            );
+            assert!(error.applied().is_empty());
+            assert_eq!(test_fn2(), 2);
+
+            // Without the removed hook, the whole set is enabled.
+            let mut hooks = MhHooks::new(std::mem::take(&mut hooks.hooks).into_iter().take(1));
+            hooks.apply_transactional().unwrap();
+            assert_eq!(test_fn2(), 3);
+            hooks.unapply().unwrap();
+        }
+
+        type FnType = fn() -> i32;
+
+        #[inline(never)]
+        fn test_fn1() -> i32 {
+            4
+        }
+
+        fn test_fn1_hook() -> i32 {
+            5
+        }
+
+        #[inline(never)]
+        fn test_fn2() -> i32 {
+            2
+        }
+
+        fn test_fn2_hook() -> i32 {
+            3
+        }
+    }
+
+    #[test]
+    fn test_apply_policy() {
+        let _test = HookTest::start();
+
+        unsafe {
+            let removed = MhHook::from_fns::<FnType>(test_fn1, test_fn1_hook).unwrap();
+            MinHook::remove_hook(removed.target()).unwrap();
+
+            let mut hooks = MhHooks::new([
+                MhHook::from_fns::<FnType>(test_fn2, test_fn2_hook).unwrap(),
+                removed,
+            ])
+            .with_apply_policy(ApplyPolicy::FailFast);
+
+            // The other hook is not enabled either, and the set stays unapplied.
+            let error = hooks.apply().unwrap_err();
+            assert!(error.applied().is_empty());
+            assert_eq!(error.failed().len(), 1);
+            assert!(!hooks.is_applied());
+            assert_eq!(test_fn2(), 22);
+
+            // The canceled hook stays disabled when the queue is applied later.
+            MinHook::apply_queued().unwrap();
+            assert_eq!(test_fn2(), 22);
+
+            std::mem::take(&mut hooks.hooks).remove(0).remove().unwrap();
+        }
+
+        type FnType = fn() -> i32;
+
+        #[inline(never)]
+        fn test_fn1() -> i32 {
+            std::hint::black_box(20)
+        }
+
+        fn test_fn1_hook() -> i32 {
+            21
+        }
+
+        #[inline(never)]
+        fn test_fn2() -> i32 {
+            std::hint::black_box(22)
+        }
+
+        fn test_fn2_hook() -> i32 {
+            23
+        }
+    }
+
+    #[test]
+    fn test_create_and_apply() {
+        let _test = HookTest::start();
+
+        let builders = || {
+            [
+                MhHookBuilder::new()
+                    .target_addr(test_fn as FnType as *mut c_void)
+                    .detour(test_fn_hook as FnType as *mut c_void),
+                MhHookBuilder::new()
+                    .target_rva("not_loaded.dll", 0x1000)
+                    .detour(test_fn_hook as FnType as *mut c_void),
+            ]
+        };
+
+        unsafe {
+            // Nothing is left created if a hook fails.
+            let (hooks, result) = MhHooks::create_and_apply(builders(), ApplyPolicy::FailFast);
+            assert!(hooks.is_empty());
+            assert_eq!(
+                result.unwrap_err().failed(),
+                [(std::ptr::null_mut(), MH_STATUS::MH_ERROR_MODULE_NOT_FOUND)]
+            );
+            assert_eq!(test_fn(), 30);
+
+            // The other hooks are still created and enabled.
+            let (mut hooks, result) =
+                MhHooks::create_and_apply(builders(), ApplyPolicy::BestEffort);
+            assert_eq!(hooks.len(), 1);
+            assert!(hooks.is_applied());
+            assert_eq!(
+                result.unwrap_err().applied(),
+                [test_fn as FnType as *mut c_void]
+            );
+            assert_eq!(test_fn(), 31);
+
+            std::mem::take(&mut hooks.hooks).remove(0).remove().unwrap();
+        }
+
+        type FnType = fn() -> i32;
+
+        #[inline(never)]
+        fn test_fn() -> i32 {
+            std::hint::black_box(30)
+        }
+
+        fn test_fn_hook() -> i32 {
+            31
+        }
+    }
+
+    #[test]
+    fn test_collection() {
+        let _test = HookTest::start();
+
+        unsafe {
+            let mut hooks =
+                MhHooks::try_new([MhHook::from_fns::<FnType>(test_fn1, test_fn1_hook)]).unwrap();
+            hooks.extend([MhHook::from_fns::<FnType>(test_fn2, test_fn2_hook).unwrap()]);
+            assert_eq!(hooks.len(), 2);
+            assert_eq!(hooks[1].target(), test_fn2 as FnType as *mut c_void);
+            assert_eq!(
+                (&hooks)
+                    .into_iter()
+                    .filter(|hook| hook.is_enabled())
+                    .count(),
+                0
+            );
+
+            // Hooks taken out by value stay enabled.
+            hooks.apply().unwrap();
+            let taken = hooks.into_iter().collect::<Vec<_>>();
+            assert_eq!(test_fn1(), 45);
+            let hooks = taken.into_iter().collect::<MhHooks>();
+            drop(hooks);
+            assert_eq!(test_fn1(), 44);
+
+            let status = MhHooks::try_new([MhHook::from_fns::<FnType>(test_fn1, test_fn1_hook)])
+                .unwrap_err();
+            assert_eq!(status, MH_STATUS::MH_ERROR_ALREADY_CREATED);
+        }
+
+        type FnType = fn() -> i32;
+
+        #[inline(never)]
+        fn test_fn1() -> i32 {
+            std::hint::black_box(44)
+        }
+
+        fn test_fn1_hook() -> i32 {
+            45
+        }
+
+        #[inline(never)]
+        fn test_fn2() -> i32 {
+            std::hint::black_box(46)
+        }
+
+        fn test_fn2_hook() -> i32 {
+            47
+        }
+    }
+
+    #[test]
+    fn test_drop_policy() {
+        let _test = HookTest::start();
+
+        unsafe {
+            let mut hooks =
+                MhHooks::new([MhHook::from_fns::<FnType>(test_fn1, test_fn1_hook).unwrap()]);
+            assert_eq!(hooks.drop_policy(), DropPolicy::Disable);
+            hooks.apply().unwrap();
+            drop(hooks);
+            assert_eq!(test_fn1(), 10);
+            assert!(MhHook::from_fns::<FnType>(test_fn1, test_fn1_hook).is_err());
+
+            // Removed hooks can be created again.
+            let mut hooks =
+                MhHooks::new([MhHook::from_fns::<FnType>(test_fn2, test_fn2_hook).unwrap()])
+                    .with_drop_policy(DropPolicy::Remove);
+            hooks.apply().unwrap();
+            drop(hooks);
+            assert_eq!(test_fn2(), 12);
+            MhHook::from_fns::<FnType>(test_fn2, test_fn2_hook)
+                .unwrap()
+                .remove()
+                .unwrap();
+        }
+
+        type FnType = fn() -> i32;
+
+        #[inline(never)]
+        fn test_fn1() -> i32 {
+            std::hint::black_box(10)
+        }
+
+        fn test_fn1_hook() -> i32 {
+            11
+        }
+
+        #[inline(never)]
+        fn test_fn2() -> i32 {
+            std::hint::black_box(12)
+        }
+
+        fn test_fn2_hook() -> i32 {
+            13
+        }
+    }
+
+    #[test]
+    fn test_push_take_remove() {
+        let _test = HookTest::start();
+
+        unsafe {
+            let mut hooks =
+                MhHooks::new([MhHook::from_fns::<FnType>(test_fn1, test_fn1_hook).unwrap()]);
+            hooks.apply().unwrap();
+            assert!(hooks.is_applied());
+
+            // A hook added to an applied set is enabled.
+            hooks
+                .push(MhHook::from_fns::<FnType>(test_fn2, test_fn2_hook).unwrap())
+                .unwrap();
+            assert_eq!(test_fn2(), 3);
+
+            // A taken hook keeps its state.
+            let hook = hooks.take(test_fn2 as FnType as *mut c_void).unwrap();
+            assert_eq!(test_fn2(), 3);
+            hook.disable().unwrap();
+
+            // A removed hook is disabled, and can be created again.
+            assert!(hooks.remove(test_fn1 as FnType as *mut c_void).unwrap());
+            assert!(!hooks.remove(test_fn1 as FnType as *mut c_void).unwrap());
+            assert_eq!(test_fn1(), 0);
+            assert!(hooks.is_empty());
+            MhHook::from_fns::<FnType>(test_fn1, test_fn1_hook).unwrap();
+        }
+
+        type FnType = fn() -> i32;
+
+        #[inline(never)]
+        fn test_fn1() -> i32 {
+            0
+        }
+
+        fn test_fn1_hook() -> i32 {
+            1
+        }
+
+        #[inline(never)]
+        fn test_fn2() -> i32 {
+            2
+        }
+
+        fn test_fn2_hook() -> i32 {
+            3
+        }
+    }
+
+    #[test]
+    fn test_apply_error() {
+        let _test = HookTest::start();
+
+        unsafe {
+            let removed = MhHook::from_fns::<FnType>(test_fn1, test_fn1_hook).unwrap();
+            MinHook::remove_hook(removed.target()).unwrap();
+
+            let mut hooks = MhHooks::new([
+                removed,
+                MhHook::from_fns::<FnType>(test_fn2, test_fn2_hook).unwrap(),
+            ]);
+
+            // The removed hook fails, while the other one is still enabled.
+            let error = hooks.apply().unwrap_err();
+            assert_eq!(
+                error.failed(),
+                [(
+                    test_fn1 as FnType as *mut c_void,
+                    MH_STATUS::MH_ERROR_NOT_CREATED
+                )]
+            );
+            assert_eq!(error.applied(), [test_fn2 as FnType as *mut c_void]);
+            assert_eq!(test_fn1(), 0);
+            assert_eq!(test_fn2(), 3);
+
+            let error = hooks.unapply().unwrap_err();
+            assert_eq!(error.applied(), [test_fn2 as FnType as *mut c_void]);
+            assert_eq!(test_fn2(), 2);
+        }
+
+        type FnType = fn() -> i32;
+
+        #[inline(never)]
+        fn test_fn1() -> i32 {
+            0
+        }
+
+        fn test_fn1_hook() -> i32 {
+            1
+        }
+
+        #[inline(never)]
+        fn test_fn2() -> i32 {
+            2
+        }
+
+        fn test_fn2_hook() -> i32 {
+            3
+        }
+    }
+}
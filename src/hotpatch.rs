@@ -0,0 +1,248 @@
+//! Hooks for functions compiled with `/hotpatch`.
+//!
+//! A hot-patchable function starts with the 2-byte `mov edi, edi` and is preceded by 5 bytes of padding. Such a
+//! function is hooked by writing a jump to the detour into the padding, and then atomically replacing `mov edi, edi`
+//! with a short jump to it. Other threads never see a half-written prologue, so no threads need to be suspended, and
+//! the original function is called by skipping the 2 bytes of its prologue.
+
+use crate::{
+    MH_STATUS, os_error, state,
+    win::{CURRENT_PROCESS, FlushInstructionCache, PAGE_EXECUTE_READWRITE, VirtualProtect},
+};
+use std::{
+    ffi::c_void,
+    sync::atomic::{AtomicBool, AtomicU16, Ordering},
+};
+
+// The size of the padding before a hot-patchable function, which fits a relative jump
+const PADDING_SIZE: usize = 5;
+
+// mov edi, edi
+const MOV_EDI_EDI: [u8; 2] = [0x8B, 0xFF];
+
+// jmp -7, the start of the padding
+const SHORT_JUMP_TO_PADDING: [u8; 2] = [0xEB, 0xF9];
+
+/// Returns whether the function starts with a hot-patchable prologue.
+///
+/// # Safety
+///
+/// The function and the padding before it must be readable.
+pub unsafe fn is_hot_patchable(target: *mut c_void) -> bool {
+    if target.is_null() || !(target as usize).is_multiple_of(2) {
+        return false;
+    }
+
+    let code = unsafe { std::slice::from_raw_parts((target as *const u8).sub(PADDING_SIZE), 7) };
+    let (padding, prologue) = code.split_at(PADDING_SIZE);
+
+    prologue == MOV_EDI_EDI && padding.iter().all(|&byte| byte == 0x90 || byte == 0xCC)
+}
+
+/// A hook that patches the prologue of a hot-patchable function, without going through MinHook.
+///
+/// The hook is created in a disabled state. The jump into the padding is left in place once it is written, since a
+/// thread may still be running it.
+#[derive(Debug)]
+pub struct HotPatchHook {
+    target: *mut c_void,
+    detour: *mut c_void,
+    enabled: AtomicBool,
+}
+
+// The prologue is only changed atomically.
+unsafe impl Send for HotPatchHook {}
+unsafe impl Sync for HotPatchHook {}
+
+impl HotPatchHook {
+    /// Creates a hook for a hot-patchable function.
+    ///
+    /// Returns `MH_ERROR_NOT_EXECUTABLE` if the target is null, `MH_ERROR_ALREADY_CREATED` if MinHook already hooks
+    /// the target, and `MH_ERROR_UNSUPPORTED_FUNCTION` if the target is not hot-patchable or the detour is out of
+    /// reach of a relative jump.
+    ///
+    /// # Safety
+    ///
+    /// The function and the padding before it must be readable.
+    pub unsafe fn new(target: *mut c_void, detour: *mut c_void) -> Result<Self, MH_STATUS> {
+        if target.is_null() {
+            return Err(MH_STATUS::MH_ERROR_NOT_EXECUTABLE);
+        }
+        if state::is_created(target) {
+            return Err(MH_STATUS::MH_ERROR_ALREADY_CREATED);
+        }
+        if !unsafe { is_hot_patchable(target) } {
+            return Err(MH_STATUS::MH_ERROR_UNSUPPORTED_FUNCTION);
+        }
+
+        // The jump in the padding ends right at the target
+        let offset = (detour as isize).wrapping_sub(target as isize);
+        if i32::try_from(offset).is_err() {
+            return Err(MH_STATUS::MH_ERROR_UNSUPPORTED_FUNCTION);
+        }
+
+        Ok(Self {
+            target,
+            detour,
+            enabled: AtomicBool::new(false),
+        })
+    }
+
+    /// Enables the hook by jumping from the prologue to the detour.
+    ///
+    /// # Safety
+    pub unsafe fn enable(&self) -> Result<(), MH_STATUS> {
+        if self.enabled.swap(true, Ordering::SeqCst) {
+            return Err(MH_STATUS::MH_ERROR_ENABLED);
+        }
+
+        unsafe { self.patch(true) }.inspect_err(|_| {
+            self.enabled.store(false, Ordering::SeqCst);
+        })
+    }
+
+    /// Disables the hook by restoring the prologue.
+    ///
+    /// # Safety
+    pub unsafe fn disable(&self) -> Result<(), MH_STATUS> {
+        if !self.enabled.swap(false, Ordering::SeqCst) {
+            return Err(MH_STATUS::MH_ERROR_DISABLED);
+        }
+
+        unsafe { self.patch(false) }.inspect_err(|_| {
+            self.enabled.store(true, Ordering::SeqCst);
+        })
+    }
+
+    /// Removes the hook, restoring the prologue if the hook is enabled.
+    ///
+    /// # Safety
+    pub unsafe fn remove(self) -> Result<(), MH_STATUS> {
+        match unsafe { self.disable() } {
+            Ok(()) | Err(MH_STATUS::MH_ERROR_DISABLED) => Ok(()),
+            Err(status) => Err(status),
+        }
+    }
+
+    /// Returns the address of the hooked function.
+    pub fn target(&self) -> *mut c_void {
+        self.target
+    }
+
+    /// Returns the address that calls the original function, right after its `mov edi, edi`.
+    pub fn trampoline(&self) -> *mut c_void {
+        unsafe { self.target.add(MOV_EDI_EDI.len()) }
+    }
+
+    /// Returns whether the hook is enabled.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::SeqCst)
+    }
+
+    unsafe fn patch(&self, enable: bool) -> Result<(), MH_STATUS> {
+        let padding = unsafe { (self.target as *mut u8).sub(PADDING_SIZE) };
+        let size = PADDING_SIZE + MOV_EDI_EDI.len();
+
+        let mut protect = 0;
+        if unsafe { VirtualProtect(padding as _, size, PAGE_EXECUTE_READWRITE, &mut protect) } == 0
+        {
+            os_error::capture(MH_STATUS::MH_ERROR_MEMORY_PROTECT);
+            return Err(MH_STATUS::MH_ERROR_MEMORY_PROTECT);
+        }
+
+        let prologue = if enable {
+            let offset = (self.detour as isize).wrapping_sub(self.target as isize) as i32;
+            unsafe {
+                padding.write(0xE9);
+                (padding.add(1) as *mut [u8; 4]).write_unaligned(offset.to_le_bytes());
+            }
+            SHORT_JUMP_TO_PADDING
+        } else {
+            MOV_EDI_EDI
+        };
+        unsafe {
+            (*(self.target as *const AtomicU16))
+                .store(u16::from_le_bytes(prologue), Ordering::SeqCst)
+        };
+
+        unsafe {
+            VirtualProtect(padding as _, size, protect, &mut protect);
+            FlushInstructionCache(CURRENT_PROCESS, padding as _, size);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::HookTest;
+
+    unsafe extern "system" {
+        fn VirtualAlloc(
+            lpAddress: *mut c_void,
+            dwSize: usize,
+            flAllocationType: u32,
+            flProtect: u32,
+        ) -> *mut c_void;
+        fn VirtualFree(lpAddress: *mut c_void, dwSize: usize, dwFreeType: u32) -> i32;
+    }
+
+    type FnType = unsafe extern "system" fn() -> i32;
+
+    #[test]
+    fn test_hot_patch_hook() {
+        let _test = HookTest::start();
+
+        #[rustfmt::skip]
+        let code = [
+            // Padding, then the target: mov edi, edi; mov eax, 1; ret
+            0xCC, 0xCC, 0xCC, 0xCC, 0xCC, 0xCC, 0xCC, 0xCC, 0xCC, 0xCC, 0xCC, 0xCC,
+            0x8B, 0xFF, 0xB8, 0x01, 0x00, 0x00, 0x00, 0xC3,
+            // The detour: mov eax, 2; ret
+            0xCC, 0xCC, 0xCC, 0xCC,
+            0xB8, 0x02, 0x00, 0x00, 0x00, 0xC3,
+        ];
+
+        unsafe {
+            let memory = VirtualAlloc(std::ptr::null_mut(), 4096, 0x3000, PAGE_EXECUTE_READWRITE);
+            assert!(!memory.is_null());
+            std::ptr::copy_nonoverlapping(code.as_ptr(), memory as *mut u8, code.len());
+            let target = memory.add(12);
+            let detour = memory.add(24);
+
+            assert!(is_hot_patchable(target));
+            assert!(!is_hot_patchable(detour));
+
+            let hook = HotPatchHook::new(target, detour).unwrap();
+            let target_fn = std::mem::transmute::<*mut c_void, FnType>(target);
+            let trampoline = std::mem::transmute::<*mut c_void, FnType>(hook.trampoline());
+
+            hook.enable().unwrap();
+            assert_eq!(target_fn(), 2);
+            assert_eq!(trampoline(), 1);
+
+            hook.disable().unwrap();
+            assert_eq!(target_fn(), 1);
+            assert_eq!(hook.disable(), Err(MH_STATUS::MH_ERROR_DISABLED));
+
+            hook.remove().unwrap();
+            VirtualFree(memory, 0, 0x8000);
+        }
+    }
+
+    #[test]
+    fn test_hot_patch_hook_unsupported() {
+        let _test = HookTest::start();
+
+        unsafe {
+            let status = HotPatchHook::new(test_fn as FnType as _, test_fn as FnType as _);
+            assert_eq!(status.err(), Some(MH_STATUS::MH_ERROR_UNSUPPORTED_FUNCTION));
+        }
+
+        #[inline(never)]
+        unsafe extern "system" fn test_fn() -> i32 {
+            std::hint::black_box(7)
+        }
+    }
+}
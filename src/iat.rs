@@ -0,0 +1,330 @@
+//! Hooks that patch the import address table of a module instead of the code of the target function.
+//!
+//! Import hooks only redirect calls that go through the patched module's imports, but they work for targets that
+//! MinHook can't hook, such as functions with hot-patched or protected prologues. Imports that are only resolved on
+//! their first call, from delay-loaded DLLs such as `dwmapi.dll` or `d3d12.dll`, are hooked through the delay-load
+//! import table instead.
+
+use crate::{
+    MH_STATUS, ModuleName,
+    logging::debug,
+    pe::{IMAGE_ORDINAL_FLAG, Image},
+    slot::SlotHook,
+    win::{GetModuleHandleW, GetProcAddress, LoadLibraryA},
+};
+use std::{
+    ffi::{CStr, CString, c_void},
+    ptr::null,
+};
+
+/// A hook for an entry in the import address table of a module.
+///
+/// The hook is created in a disabled state. Enabling it replaces the imported address with the detour, and disabling
+/// it restores the original address.
+#[derive(Debug)]
+pub struct IatHook {
+    hook: SlotHook,
+}
+
+impl IatHook {
+    /// Creates a hook for the function `proc_name` that `module` imports from `import_module`.
+    ///
+    /// Returns `MH_ERROR_MODULE_NOT_FOUND` if `module` is not loaded, and `MH_ERROR_FUNCTION_NOT_FOUND` if it does not
+    /// import the function by name.
+    ///
+    /// # Safety
+    pub unsafe fn new<M: Into<ModuleName>>(
+        module: M,
+        import_module: &str,
+        proc_name: &str,
+        detour: *mut c_void,
+    ) -> Result<Self, MH_STATUS> {
+        let module = module.into();
+        let base = unsafe { GetModuleHandleW(module.as_ptr()) };
+
+        unsafe { Self::from_base(base, import_module, proc_name, detour) }
+    }
+
+    /// Creates a hook for the function `proc_name` that the executable of the process imports from `import_module`.
+    ///
+    /// # Safety
+    pub unsafe fn new_in_executable(
+        import_module: &str,
+        proc_name: &str,
+        detour: *mut c_void,
+    ) -> Result<Self, MH_STATUS> {
+        let base = unsafe { GetModuleHandleW(null()) };
+
+        unsafe { Self::from_base(base, import_module, proc_name, detour) }
+    }
+
+    /// Creates a hook for the function `proc_name` that `module` imports from `import_module` through its delay-load
+    /// import table.
+    ///
+    /// If the import is not resolved yet, `import_module` is loaded and the function is resolved right away, like its
+    /// first call would do, so the delay-load helper doesn't overwrite the detour later. Returns
+    /// `MH_ERROR_MODULE_NOT_FOUND` if `module` is not loaded or `import_module` can't be loaded, and
+    /// `MH_ERROR_FUNCTION_NOT_FOUND` if `module` does not delay-load the function by name.
+    ///
+    /// # Safety
+    pub unsafe fn new_delay_load<M: Into<ModuleName>>(
+        module: M,
+        import_module: &str,
+        proc_name: &str,
+        detour: *mut c_void,
+    ) -> Result<Self, MH_STATUS> {
+        let module = module.into();
+        let base = unsafe { GetModuleHandleW(module.as_ptr()) };
+
+        unsafe { Self::from_base_delay_load(base, import_module, proc_name, detour) }
+    }
+
+    /// Creates a hook for the function `proc_name` that the executable of the process delay-loads from
+    /// `import_module`, like [`IatHook::new_delay_load`].
+    ///
+    /// # Safety
+    pub unsafe fn new_delay_load_in_executable(
+        import_module: &str,
+        proc_name: &str,
+        detour: *mut c_void,
+    ) -> Result<Self, MH_STATUS> {
+        let base = unsafe { GetModuleHandleW(null()) };
+
+        unsafe { Self::from_base_delay_load(base, import_module, proc_name, detour) }
+    }
+
+    unsafe fn from_base(
+        base: *mut c_void,
+        import_module: &str,
+        proc_name: &str,
+        detour: *mut c_void,
+    ) -> Result<Self, MH_STATUS> {
+        let image = unsafe { Image::from_base(base as *const u8) }
+            .ok_or(MH_STATUS::MH_ERROR_MODULE_NOT_FOUND)?;
+
+        let slot = unsafe { find_import(&image, import_module, proc_name) }
+            .ok_or(MH_STATUS::MH_ERROR_FUNCTION_NOT_FOUND)?;
+        debug!("Found import {}!{} at {:?}", import_module, proc_name, slot);
+
+        Ok(Self {
+            hook: unsafe { SlotHook::new(slot, detour) },
+        })
+    }
+
+    unsafe fn from_base_delay_load(
+        base: *mut c_void,
+        import_module: &str,
+        proc_name: &str,
+        detour: *mut c_void,
+    ) -> Result<Self, MH_STATUS> {
+        let image = unsafe { Image::from_base(base as *const u8) }
+            .ok_or(MH_STATUS::MH_ERROR_MODULE_NOT_FOUND)?;
+
+        let (dll_name, slot) = unsafe { find_delay_import(&image, import_module, proc_name) }
+            .ok_or(MH_STATUS::MH_ERROR_FUNCTION_NOT_FOUND)?;
+        debug!(
+            "Found delay-load import {}!{} at {:?}",
+            import_module, proc_name, slot
+        );
+
+        // Unresolved imports point to a thunk in the module, which calls the delay-load helper
+        let mut original = unsafe { *slot };
+        if image.contains(original) {
+            original = unsafe { resolve(dll_name, proc_name)? };
+            debug!(
+                "Resolved delay-load import {}!{} to {:?}",
+                import_module, proc_name, original
+            );
+        }
+
+        Ok(Self {
+            hook: unsafe { SlotHook::with_original(slot, original, detour) },
+        })
+    }
+
+    /// Enables the hook by writing the detour into the import address table.
+    ///
+    /// # Safety
+    pub unsafe fn enable(&self) -> Result<(), MH_STATUS> {
+        unsafe { self.hook.enable() }
+    }
+
+    /// Disables the hook by restoring the original address in the import address table.
+    ///
+    /// # Safety
+    pub unsafe fn disable(&self) -> Result<(), MH_STATUS> {
+        unsafe { self.hook.disable() }
+    }
+
+    /// Removes the hook, restoring the original address if the hook is enabled.
+    ///
+    /// # Safety
+    pub unsafe fn remove(self) -> Result<(), MH_STATUS> {
+        unsafe { self.hook.remove() }
+    }
+
+    /// Returns the original imported address, which can be used to call the original function.
+    pub fn original(&self) -> *mut c_void {
+        self.hook.original()
+    }
+
+    /// Returns the address of the patched entry in the import address table.
+    pub fn slot(&self) -> *mut *mut c_void {
+        self.hook.slot()
+    }
+
+    /// Returns whether the hook is enabled.
+    pub fn is_enabled(&self) -> bool {
+        self.hook.is_enabled()
+    }
+}
+
+// Finds the import address table entry for a function imported by name
+unsafe fn find_import(
+    image: &Image,
+    import_module: &str,
+    proc_name: &str,
+) -> Option<*mut *mut c_void> {
+    let descriptor = image.import_descriptors().find(|descriptor| {
+        image
+            .c_str(descriptor.name)
+            .to_bytes()
+            .eq_ignore_ascii_case(import_module.as_bytes())
+    })?;
+
+    // The lookup table keeps the names after the address table is bound
+    let lookup_rva = if descriptor.original_first_thunk != 0 {
+        descriptor.original_first_thunk
+    } else {
+        descriptor.first_thunk
+    };
+
+    unsafe { find_thunk(image, lookup_rva, descriptor.first_thunk, proc_name) }
+}
+
+// Finds the delay-load import address table entry for a function imported by name, and the name of its DLL
+unsafe fn find_delay_import<'a>(
+    image: &'a Image,
+    import_module: &str,
+    proc_name: &str,
+) -> Option<(&'a CStr, *mut *mut c_void)> {
+    let descriptor = image.delay_import_descriptors().find(|descriptor| {
+        image
+            .c_str(descriptor.dll_name_rva)
+            .to_bytes()
+            .eq_ignore_ascii_case(import_module.as_bytes())
+    })?;
+
+    let slot = unsafe {
+        find_thunk(
+            image,
+            descriptor.import_name_table_rva,
+            descriptor.import_address_table_rva,
+            proc_name,
+        )
+    }?;
+
+    Some((image.c_str(descriptor.dll_name_rva), slot))
+}
+
+// Finds the entry of the address table whose entry in the lookup table imports the function by name
+unsafe fn find_thunk(
+    image: &Image,
+    lookup_rva: u32,
+    addresses_rva: u32,
+    proc_name: &str,
+) -> Option<*mut *mut c_void> {
+    let lookup = image.rva::<usize>(lookup_rva);
+    let addresses = image.rva::<*mut c_void>(addresses_rva) as *mut *mut c_void;
+
+    (0..)
+        .map(|index| (index, unsafe { *lookup.add(index) }))
+        .take_while(|&(_, entry)| entry != 0)
+        .filter(|&(_, entry)| entry & IMAGE_ORDINAL_FLAG == 0)
+        // Skip the hint of the import by name
+        .find(|&(_, entry)| image.c_str(entry as u32 + 2).to_bytes() == proc_name.as_bytes())
+        .map(|(index, _)| unsafe { addresses.add(index) })
+}
+
+// Loads the DLL of a delay-load import and resolves the function, like the delay-load helper
+unsafe fn resolve(dll_name: &CStr, proc_name: &str) -> Result<*mut c_void, MH_STATUS> {
+    let module = unsafe { LoadLibraryA(dll_name.as_ptr().cast()) };
+    if module.is_null() {
+        return Err(MH_STATUS::MH_ERROR_MODULE_NOT_FOUND);
+    }
+
+    let proc_name = CString::new(proc_name).map_err(|_| MH_STATUS::MH_ERROR_FUNCTION_NOT_FOUND)?;
+    let address = unsafe { GetProcAddress(module, proc_name.as_ptr().cast()) };
+    if address.is_null() {
+        return Err(MH_STATUS::MH_ERROR_FUNCTION_NOT_FOUND);
+    }
+
+    Ok(address)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::HookTest;
+    use std::ptr::null_mut;
+
+    unsafe extern "system" {
+        fn GetTickCount() -> u32;
+    }
+
+    #[test]
+    fn test_iat_hook() {
+        let _test = HookTest::start();
+
+        unsafe {
+            let hook = IatHook::new_in_executable(
+                "kernel32.dll",
+                "GetTickCount",
+                get_tick_count_hook as FnType as *mut c_void,
+            )
+            .unwrap();
+            assert!(!hook.original().is_null());
+
+            hook.enable().unwrap();
+            assert_eq!(GetTickCount(), 42);
+            assert_eq!(hook.enable(), Err(MH_STATUS::MH_ERROR_ENABLED));
+
+            hook.disable().unwrap();
+            assert_ne!(GetTickCount(), 42);
+            assert!(!hook.is_enabled());
+
+            hook.remove().unwrap();
+        }
+
+        type FnType = unsafe extern "system" fn() -> u32;
+
+        unsafe extern "system" fn get_tick_count_hook() -> u32 {
+            42
+        }
+    }
+
+    #[test]
+    fn test_iat_hook_not_found() {
+        let _test = HookTest::start();
+
+        unsafe {
+            let status = IatHook::new("not_loaded.dll", "kernel32.dll", "GetTickCount", null_mut())
+                .unwrap_err();
+            assert_eq!(status, MH_STATUS::MH_ERROR_MODULE_NOT_FOUND);
+
+            let status =
+                IatHook::new_in_executable("kernel32.dll", "NotAnImport", null_mut()).unwrap_err();
+            assert_eq!(status, MH_STATUS::MH_ERROR_FUNCTION_NOT_FOUND);
+
+            // The test executable doesn't delay-load any DLL.
+            let status =
+                IatHook::new_delay_load_in_executable("dwmapi.dll", "DwmFlush", null_mut())
+                    .unwrap_err();
+            assert_eq!(status, MH_STATUS::MH_ERROR_FUNCTION_NOT_FOUND);
+            let status =
+                IatHook::new_delay_load("not_loaded.dll", "dwmapi.dll", "DwmFlush", null_mut())
+                    .unwrap_err();
+            assert_eq!(status, MH_STATUS::MH_ERROR_MODULE_NOT_FOUND);
+        }
+    }
+}
@@ -0,0 +1,238 @@
+use crate::{
+    MH_STATUS, QUEUED_HOOKS,
+    logging::{debug, warning},
+    module, state,
+    sys::{MH_Initialize, MH_RemoveHook, MH_Uninitialize},
+};
+use std::{
+    ffi::c_void,
+    sync::{
+        Mutex, MutexGuard, PoisonError,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+static INIT_STATE: Mutex<InitState> = Mutex::new(InitState {
+    initialized: false,
+    owned: false,
+    guards: 0,
+});
+
+// Mirrors `InitState::initialized`, so creating a hook doesn't lock the state once MinHook is initialized
+static INITIALIZED: AtomicBool = AtomicBool::new(false);
+
+// Whether MinHook is initialized, whether this crate initialized it, and how many guards keep it initialized
+#[derive(Debug)]
+struct InitState {
+    initialized: bool,
+    owned: bool,
+    guards: usize,
+}
+
+impl InitState {
+    // A failed initialization is not remembered, so the next call tries again
+    fn initialize(&mut self) -> Result<(), MH_STATUS> {
+        if self.initialized {
+            return Ok(());
+        }
+
+        let status = unsafe { MH_Initialize() };
+        debug!("MH_Initialize: {:?}", status);
+
+        match status {
+            MH_STATUS::MH_OK => {
+                if let Some(version) = module::wine_version() {
+                    debug!("Running under Wine {}", version);
+                }
+                self.owned = true;
+            }
+            // Another user of MinHook in the process initialized it, and is the one to uninitialize it
+            MH_STATUS::MH_ERROR_ALREADY_INITIALIZED => self.owned = false,
+            _ => return Err(status),
+        }
+
+        self.initialized = true;
+        INITIALIZED.store(true, Ordering::Release);
+        Ok(())
+    }
+
+    // Only the hooks created through this crate are removed if another user of MinHook initialized it. Returns whether
+    // MinHook was uninitialized, a failure is logged and leaves the state as it is
+    fn uninitialize(&mut self) -> bool {
+        if !self.initialized {
+            return true;
+        }
+
+        if self.owned {
+            let status = unsafe { MH_Uninitialize() };
+            debug!("MH_Uninitialize: {:?}", status);
+
+            match status {
+                // MinHook was already uninitialized behind the back of this crate
+                MH_STATUS::MH_OK | MH_STATUS::MH_ERROR_NOT_INITIALIZED => {}
+                _ => {
+                    warning!("Could not uninitialize MinHook: {:?}", status);
+                    return false;
+                }
+            }
+        } else {
+            for target in state::created_in(0..usize::MAX) {
+                let status = unsafe { MH_RemoveHook(target) };
+                debug!("MH_RemoveHook: {:?}", status);
+            }
+        }
+
+        self.initialized = false;
+        INITIALIZED.store(false, Ordering::Release);
+        self.owned = false;
+        QUEUED_HOOKS.store(0, Ordering::Relaxed);
+        state::clear();
+        true
+    }
+}
+
+/// Keeps MinHook initialized while it is alive.
+///
+/// Guards are reference counted: MinHook is uninitialized when the last guard is dropped, which removes all hooks. It is
+/// initialized again when a new guard is acquired or a hook is created, so hooks can be torn down and recreated
+/// repeatedly, for example across the load and unload cycles of a plugin.
+#[derive(Debug)]
+#[must_use = "MinHook is uninitialized again if the guard is dropped right away"]
+pub struct InitGuard {
+    _private: (),
+}
+
+impl InitGuard {
+    /// Initializes MinHook if it's not initialized yet, and keeps it initialized until the guard is dropped.
+    ///
+    /// Returns the status of `MH_Initialize` if MinHook could not be initialized.
+    pub fn acquire() -> Result<Self, MH_STATUS> {
+        let mut state = lock();
+        state.initialize()?;
+        state.guards += 1;
+
+        Ok(Self { _private: () })
+    }
+
+    /// Returns the number of guards that are alive.
+    pub fn count() -> usize {
+        lock().guards
+    }
+
+    /// Returns whether this crate initialized MinHook, and uninitializes it again. This is `false` while MinHook is not
+    /// initialized, or if another user of MinHook in the process initialized it first.
+    pub fn is_owner() -> bool {
+        lock().owned
+    }
+}
+
+impl Drop for InitGuard {
+    fn drop(&mut self) {
+        let mut state = lock();
+        if state.guards == 0 {
+            // MinHook was torn down by `on_process_detach` while the guard was alive
+            return;
+        }
+        state.guards -= 1;
+
+        if state.guards == 0 {
+            state.uninitialize();
+        }
+    }
+}
+
+// Initializes MinHook if it's not initialized yet
+pub(crate) fn initialize() -> Result<(), MH_STATUS> {
+    if INITIALIZED.load(Ordering::Acquire) {
+        return Ok(());
+    }
+
+    lock().initialize()
+}
+
+// Uninitializes MinHook, unless a guard keeps it initialized. Returns whether MinHook was uninitialized
+pub(crate) fn uninitialize() -> bool {
+    let mut state = lock();
+    if state.guards > 0 {
+        debug!(
+            "Not uninitializing MinHook, {} guards keep it initialized",
+            state.guards
+        );
+        return false;
+    }
+
+    state.uninitialize()
+}
+
+/// Disables and removes all hooks when the DLL containing them is unloaded. Call this from `DllMain` for
+/// `DLL_PROCESS_DETACH`, passing its `lpvReserved` argument.
+///
+/// If the DLL is unloaded with `FreeLibrary`, MinHook is uninitialized even while an [`InitGuard`] is alive, so no
+/// target is left jumping into the unmapped detours. If the process is exiting instead, nothing is done, since the
+/// other threads are already terminated and the DLL stays mapped.
+///
+/// # Safety
+///
+/// This must only be called while the DLL is being detached, since the hooks of the whole DLL are removed.
+pub unsafe fn on_process_detach(reserved: *mut c_void) {
+    if !reserved.is_null() {
+        return;
+    }
+
+    let mut state = lock();
+    debug!(
+        "Tearing down MinHook on process detach, {} guards are alive",
+        state.guards
+    );
+    state.guards = 0;
+    state.uninitialize();
+}
+
+// The state stays consistent even if a panic happened while it was locked
+fn lock() -> MutexGuard<'static, InitState> {
+    INIT_STATE.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::HookTest;
+
+    #[test]
+    fn test_init_guard() {
+        let _test = HookTest::start();
+
+        // Keep MinHook initialized for the other tests running in this process.
+        let outer = InitGuard::acquire().unwrap();
+        let count = InitGuard::count();
+
+        let inner = InitGuard::acquire().unwrap();
+        assert_eq!(InitGuard::count(), count + 1);
+
+        // Uninitializing is refused while guards are alive.
+        assert!(!uninitialize());
+        assert!(lock().initialized);
+
+        drop(inner);
+        assert_eq!(InitGuard::count(), count);
+        assert!(lock().initialized);
+
+        // The tests are the only user of MinHook in the process.
+        assert!(InitGuard::is_owner());
+
+        std::mem::forget(outer);
+    }
+
+    #[test]
+    fn test_on_process_detach_exit() {
+        let _test = HookTest::start();
+
+        let guard = InitGuard::acquire().unwrap();
+
+        // Nothing is torn down while the process exits.
+        unsafe { on_process_detach(std::ptr::dangling_mut()) };
+        assert!(lock().initialized);
+
+        drop(guard);
+    }
+}
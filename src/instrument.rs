@@ -0,0 +1,241 @@
+use crate::{ActiveCalls, FnPtr, Hook, HookStats, MH_STATUS, StaticHook};
+use std::{
+    sync::{PoisonError, RwLock},
+    time::Duration,
+};
+
+/// A hook that only observes the calls of a function, usually declared through
+/// [`instrument_hook!`](crate::instrument_hook).
+///
+/// The detour is generated by the macro. It passes the arguments to the `on_enter` callback, calls the original
+/// function, and passes its return value to the `on_exit` callback. `A` is the tuple of the argument types and `R` is
+/// the return type.
+///
+/// Each call is also recorded in the [`HookStats`] of the hook, along with the time spent in the callbacks and in the
+/// original function, and counted in its [`ActiveCalls`] while it runs.
+#[derive(Debug)]
+pub struct InstrumentHook<F: FnPtr, A, R> {
+    hook: StaticHook<F>,
+    detour: F,
+    callbacks: RwLock<Option<Callbacks<A, R>>>,
+    stats: HookStats,
+    active_calls: ActiveCalls,
+}
+
+// The callbacks given to `InstrumentHook::enable`
+type Callbacks<A, R> = (fn(&A), fn(&R));
+
+impl<F: FnPtr, A, R> InstrumentHook<F, A, R> {
+    /// Creates a hook that isn't initialized yet, with the detour generated by
+    /// [`instrument_hook!`](crate::instrument_hook).
+    pub const fn new(detour: F) -> Self {
+        Self {
+            hook: StaticHook::new(),
+            detour,
+            callbacks: RwLock::new(None),
+            stats: HookStats::new(),
+            active_calls: ActiveCalls::new(),
+        }
+    }
+
+    /// Creates the hook for the target function if it doesn't exist yet, and enables it with the callbacks.
+    ///
+    /// Returns `MH_ERROR_ALREADY_CREATED` if the hook was already created for another target.
+    ///
+    /// # Safety
+    pub unsafe fn enable(
+        &self,
+        target: F,
+        on_enter: fn(&A),
+        on_exit: fn(&R),
+    ) -> Result<(), MH_STATUS> {
+        // The callbacks stay consistent even if a panic happened while they were locked
+        *self
+            .callbacks
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = Some((on_enter, on_exit));
+
+        unsafe { self.hook.enable(target, self.detour) }
+    }
+
+    /// Disables the hook. Returns `MH_ERROR_NOT_CREATED` if the hook was never enabled.
+    ///
+    /// # Safety
+    pub unsafe fn disable(&self) -> Result<(), MH_STATUS> {
+        unsafe { self.hook.disable() }
+    }
+
+    /// Returns the hook, if it was created.
+    pub fn get(&self) -> Option<&Hook<F>> {
+        self.hook.get()
+    }
+
+    /// Returns the trampoline, which calls the original target function.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the hook was never enabled.
+    pub fn original(&self) -> F {
+        self.hook.original()
+    }
+
+    /// Returns the call metrics of the hook, which a [`HookRegistry`](crate::HookRegistry) also makes readable by name
+    /// once they are tracked with [`HookRegistry::track_stats`](crate::HookRegistry::track_stats).
+    pub fn stats(&self) -> &HookStats {
+        &self.stats
+    }
+
+    /// Returns the calls of the generated detour that are running, which
+    /// [`MhHook::remove_when_idle`](crate::MhHook::remove_when_idle) waits for before removing the hook.
+    pub fn active_calls(&self) -> &ActiveCalls {
+        &self.active_calls
+    }
+
+    /// Records a call of the generated detour, which took `total` including the `original` function.
+    pub fn record(&self, total: Duration, original: Duration) {
+        self.stats.record(total.saturating_sub(original), original);
+    }
+
+    /// Calls the `on_enter` callback with the arguments of a call.
+    pub fn enter(&self, args: &A) {
+        if let Some((on_enter, _)) = self.callbacks() {
+            on_enter(args);
+        }
+    }
+
+    /// Calls the `on_exit` callback with the return value of a call.
+    pub fn exit(&self, ret: &R) {
+        if let Some((_, on_exit)) = self.callbacks() {
+            on_exit(ret);
+        }
+    }
+
+    fn callbacks(&self) -> Option<Callbacks<A, R>> {
+        *self
+            .callbacks
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Declares a static [`InstrumentHook`] for a function signature with named arguments, and generates its detour.
+///
+/// Safe and unsafe function signatures with an `extern` ABI are supported as well, such as
+/// `unsafe extern "system" fn(handle: *mut c_void) -> i32`.
+///
+/// # Example
+///
+/// ```rust
+/// use minhook::{instrument_hook, MH_STATUS};
+/// use std::sync::atomic::{AtomicI32, Ordering};
+///
+/// instrument_hook! {
+///     static ADD_HOOK: fn(x: i32, y: i32) -> i32;
+/// }
+///
+/// static LAST_RESULT: AtomicI32 = AtomicI32::new(0);
+///
+/// fn main() -> Result<(), MH_STATUS> {
+///     unsafe {
+///         ADD_HOOK.enable(
+///             add,
+///             |(x, y)| println!("add({x}, {y})"),
+///             |ret| LAST_RESULT.store(*ret, Ordering::SeqCst),
+///         )?
+///     };
+///
+///     // The call goes through to the original function, and is observed on the way
+///     assert_eq!(add(2, 3), 5);
+///     assert_eq!(LAST_RESULT.load(Ordering::SeqCst), 5);
+///
+///     Ok(())
+/// }
+///
+/// fn add(x: i32, y: i32) -> i32 {
+///     x + y
+/// }
+/// ```
+#[macro_export]
+macro_rules! instrument_hook {
+    ($(#[$attr:meta])* $vis:vis static $name:ident: fn($($arg:ident: $ty:ty),* $(,)?) $(-> $ret:ty)?;) => {
+        $crate::instrument_hook!(@impl [$(#[$attr])*] $vis $name [] [$($arg: $ty),*] [$($ret)?]);
+    };
+    ($(#[$attr:meta])* $vis:vis static $name:ident: extern $abi:literal fn($($arg:ident: $ty:ty),* $(,)?) $(-> $ret:ty)?;) => {
+        $crate::instrument_hook!(@impl [$(#[$attr])*] $vis $name [extern $abi] [$($arg: $ty),*] [$($ret)?]);
+    };
+    ($(#[$attr:meta])* $vis:vis static $name:ident: unsafe extern $abi:literal fn($($arg:ident: $ty:ty),* $(,)?) $(-> $ret:ty)?;) => {
+        $crate::instrument_hook!(@impl [$(#[$attr])*] $vis $name [unsafe extern $abi] [$($arg: $ty),*] [$($ret)?]);
+    };
+    (@impl [$($attr:tt)*] $vis:vis $name:ident [$($qual:tt)*] [$($arg:ident: $ty:ty),*] [$($ret:ty)?]) => {
+        $($attr)*
+        $vis static $name: $crate::InstrumentHook<
+            $($qual)* fn($($ty),*) -> $crate::instrument_hook!(@ret $($ret)?),
+            ($($ty,)*),
+            $crate::instrument_hook!(@ret $($ret)?),
+        > = $crate::InstrumentHook::new({
+            #[allow(unused_unsafe, clippy::too_many_arguments)]
+            $($qual)* fn detour($($arg: $ty),*) -> $crate::instrument_hook!(@ret $($ret)?) {
+                let _call = $name.active_calls().enter();
+                let start = ::std::time::Instant::now();
+                let args = ($($arg,)*);
+                $name.enter(&args);
+
+                let ($($arg,)*) = args;
+                let original_start = ::std::time::Instant::now();
+                let ret = unsafe { ($name.original())($($arg),*) };
+                let original = original_start.elapsed();
+                $name.exit(&ret);
+
+                $name.record(start.elapsed(), original);
+                ret
+            }
+
+            detour
+        });
+    };
+    (@ret) => { () };
+    (@ret $ret:ty) => { $ret };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::HookTest;
+
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[test]
+    fn test_instrument_hook() {
+        let _test = HookTest::start();
+
+        crate::instrument_hook! {
+            static TEST_FN_HOOK: unsafe extern "system" fn(x: u32, y: u32) -> u32;
+        }
+
+        static ARGS: AtomicU32 = AtomicU32::new(0);
+        static RET: AtomicU32 = AtomicU32::new(0);
+
+        unsafe {
+            TEST_FN_HOOK
+                .enable(
+                    test_fn,
+                    |&(x, y)| ARGS.store(x * 10 + y, Ordering::SeqCst),
+                    |&ret| RET.store(ret, Ordering::SeqCst),
+                )
+                .unwrap();
+
+            // The call is observed, and still returns what the original function returns.
+            assert_eq!(test_fn(4, 2), 2);
+            assert_eq!(ARGS.load(Ordering::SeqCst), 42);
+            assert_eq!(RET.load(Ordering::SeqCst), 2);
+            assert_eq!(TEST_FN_HOOK.stats().calls(), 1);
+            assert_eq!(TEST_FN_HOOK.active_calls().count(), 0);
+
+            TEST_FN_HOOK.disable().unwrap();
+        }
+
+        #[inline(never)]
+        unsafe extern "system" fn test_fn(x: u32, y: u32) -> u32 {
+            x / y
+        }
+    }
+}
@@ -37,56 +37,126 @@
 //! }
 //! ```
 
-use ffi::{
-    MH_ApplyQueued, MH_CreateHook, MH_CreateHookApi, MH_CreateHookApiEx, MH_DisableHook,
-    MH_EnableHook, MH_Initialize, MH_QueueDisableHook, MH_QueueEnableHook, MH_RemoveHook,
-    MH_Uninitialize,
-};
+use logging::{debug, warning};
+use state::Operation;
 use std::{
-    ffi::{CString, c_void},
+    ffi::{CString, OsStr, c_void},
+    fmt, io,
+    os::windows::ffi::OsStrExt,
     ptr::null_mut,
-    sync::Once,
+    sync::{
+        Once,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant},
+};
+use sys::{
+    MH_ALL_HOOKS, MH_ApplyQueued, MH_CreateHook, MH_CreateHookApiEx, MH_DisableHook, MH_EnableHook,
+    MH_QueueDisableHook, MH_QueueEnableHook, MH_RemoveHook,
 };
-use tracing::debug;
-
-mod ffi;
-
-const MH_ALL_HOOKS: *const i32 = std::ptr::null();
 
-static MINHOOK_INIT: Once = Once::new();
-static MINHOOK_UNINIT: Once = Once::new();
+pub use active::{ActiveCall, ActiveCalls};
+pub use builder::{ForeignDetour, MhHookBuilder};
+pub use chain::HookChain;
+pub use conditional::ConditionalHook;
+pub use deferred::DeferredHook;
+pub use dump::{HookInfo, dump_hooks, log_hooks};
+pub use fn_ptr::FnPtr;
+pub use group::HookGroup;
+pub use hook::{EnabledHookGuard, MhHook};
+pub use hooks::{ApplyError, ApplyPolicy, ApplyStatus, DropPolicy, MhHooks};
+pub use init::{InitGuard, on_process_detach};
+pub use instrument::InstrumentHook;
+#[doc(hidden)]
+pub use log_hook::{log_call as __log_call, log_return as __log_return};
+pub use logging::{LogCallback, LogLevel, set_log_callback};
+pub use os_error::last_os_error;
+pub use pending::PendingHook;
+pub use reentrancy::ReentrancyGuard;
+pub use registry::{HookEvent, HookRegistry, HookSnapshot, SubscriberId};
+pub use report::ApplyReport;
+pub use shared::SharedHook;
+pub use stats::HookStats;
+pub use tamper::{TamperCallback, Watchdog, repair_hooks, tampered_hooks};
+pub use thread_filter::ThreadFilter;
+pub use typed_hook::{Hook, StaticHook};
+pub use unload::remove_hooks_on_unload;
+pub use unwind::{PanicCallback, catch_detour_panic, set_panic_callback};
+pub use validate::{TargetInfo, ValidationError, validate_target};
+
+#[cfg(feature = "capi")]
+pub mod capi;
+pub mod chaos;
+pub mod hotpatch;
+pub mod iat;
+pub mod mock;
+pub mod module;
+#[cfg(feature = "presets")]
+pub mod presets;
+pub mod scan;
+#[cfg(feature = "symbols")]
+pub mod symbols;
+pub mod sys;
+pub mod testing;
+pub mod trampolines;
+pub mod vmt;
+
+mod active;
+mod builder;
+mod chain;
+mod conditional;
+mod deferred;
+mod dump;
+mod fn_ptr;
+mod group;
+mod hook;
+mod hooks;
+mod init;
+mod instrument;
+mod log_hook;
+mod logging;
+mod os_error;
+mod pe;
+mod pending;
+mod reentrancy;
+mod registry;
+mod report;
+mod shared;
+mod slot;
+mod state;
+mod stats;
+mod tamper;
+mod thread_filter;
+mod typed_hook;
+mod unload;
+mod unwind;
+mod validate;
+mod win;
+#[cfg(feature = "windows")]
+mod windows_interop;
+
+static PANIC_CLEANUP: Once = Once::new();
+
+// The number of hooks queued since the last time the queue was applied
+static QUEUED_HOOKS: AtomicUsize = AtomicUsize::new(0);
 
 /// A struct to access the MinHook API.
 pub struct MinHook {}
 
 impl MinHook {
-    // Initialize MinHook
-    fn initialize() {
-        MINHOOK_INIT.call_once(|| {
-            let status = unsafe { MH_Initialize() };
-            debug!("MH_Initialize: {:?}", status);
-
-            match status.ok() {
-                Ok(_) => (), // Initialization successful, do nothing
-                Err(MH_STATUS::MH_ERROR_ALREADY_INITIALIZED) => (), // Ignore if already initialized
-                Err(e) => panic!("Could not initialize MinHook, error: {:?}", e),
-            }
-        });
+    // Initialize MinHook, or return the status it failed with
+    fn initialize() -> Result<(), MH_STATUS> {
+        init::initialize()
     }
 
-    /// Uninitializes MinHook. This function is only possible to call once. If you want to reinitialize MinHook, you need to restart the program.
+    /// Uninitializes MinHook, which removes all hooks. MinHook is initialized again the next time a hook is created.
     ///
-    /// # Safety
-    pub fn uninitialize() {
-        // Make sure we are initialized before we uninitialize
-        Self::initialize();
-
-        MINHOOK_UNINIT.call_once(|| {
-            let status = unsafe { MH_Uninitialize() };
-            debug!("MH_Uninitialize: {:?}", status);
-
-            status.ok().expect("Could not uninitialize MinHook");
-        });
+    /// Nothing happens while an [`InitGuard`] is alive, since the guards uninitialize MinHook once the last of them is
+    /// dropped. If another user of MinHook in the process initialized it, only the hooks created through this crate are
+    /// removed and MinHook stays initialized for them. This returns whether MinHook was uninitialized; a failure of
+    /// `MH_Uninitialize` is logged and returns `false`.
+    pub fn uninitialize() -> bool {
+        init::uninitialize()
     }
 
     /// Creates a hook for the target function and detours it to the detour function. This function returns the original function pointer.
@@ -96,76 +166,74 @@ impl MinHook {
         target: *mut c_void,
         detour: *mut c_void,
     ) -> Result<*mut c_void, MH_STATUS> {
-        Self::initialize();
+        Self::initialize()?;
 
         let mut pp_original: *mut c_void = null_mut();
         let status = unsafe { MH_CreateHook(target, detour, &mut pp_original) };
+        os_error::capture(status);
         debug!("MH_CreateHook: {:?}", status);
+        state::record(target, Operation::Create, status);
         match status {
-            MH_STATUS::MH_OK => Ok(pp_original),
+            MH_STATUS::MH_OK => {
+                // MinHook only patches the target once the hook is enabled
+                let foreign_detour = unsafe { hook::foreign_detour(target) };
+                state::record_created(target, detour, pp_original, foreign_detour);
+                Ok(pp_original)
+            }
             _ => Err(status),
         }
     }
 
     /// Creates a hook for the targeted API function and detours it to the detour function. This function returns the original function pointer.
     ///
+    /// Returns `MH_ERROR_MODULE_NOT_FOUND` if the module is not loaded, and `MH_ERROR_FUNCTION_NOT_FOUND` if the module
+    /// does not export the function.
+    ///
     /// # Safety
-    pub unsafe fn create_hook_api<T: AsRef<str>>(
-        module_name: T,
-        proc_name: T,
+    pub unsafe fn create_hook_api<M: Into<ModuleName>, P: AsRef<str>>(
+        module_name: M,
+        proc_name: P,
         detour: *mut c_void,
     ) -> Result<*mut c_void, MH_STATUS> {
-        Self::initialize();
+        // The target is needed to track the hook, so it's created like `create_hook_api_ex`
+        let (original, _) = unsafe { Self::create_hook_api_ex(module_name, proc_name, detour)? };
 
-        let mut module_name = module_name.as_ref().encode_utf16().collect::<Vec<_>>();
-        module_name.push(0);
-
-        let proc_name = CString::new(proc_name.as_ref()).unwrap();
-        let mut pp_original: *mut c_void = null_mut();
-        let status = unsafe {
-            MH_CreateHookApi(
-                module_name.as_ptr() as *const _,
-                proc_name.as_ptr() as *const _,
-                detour,
-                &mut pp_original,
-            )
-        };
-        debug!("MH_CreateHookApi: {:?}", status);
-        match status {
-            MH_STATUS::MH_OK => Ok(pp_original),
-            _ => Err(status),
-        }
+        Ok(original)
     }
 
-    /// Extended function for creating a hook for the targeted API function and detours it to the detour function. This function returns the original function pointer.
-    /// # Safety
+    /// Creates a hook for the targeted API function and detours it to the detour function. This function returns the original function pointer and the address of the target function.
     ///
-    /// TOOO: Revise if this is correct
-    pub unsafe fn create_hook_api_ex<T: AsRef<str>>(
-        module_name: T,
-        proc_name: T,
+    /// Returns the same errors as [`MinHook::create_hook_api`].
+    ///
+    /// # Safety
+    pub unsafe fn create_hook_api_ex<M: Into<ModuleName>, P: AsRef<str>>(
+        module_name: M,
+        proc_name: P,
         detour: *mut c_void,
-    ) -> Result<(*mut c_void, *mut *mut c_void), MH_STATUS> {
-        Self::initialize();
+    ) -> Result<(*mut c_void, *mut c_void), MH_STATUS> {
+        Self::initialize()?;
 
-        let mut module_name = module_name.as_ref().encode_utf16().collect::<Vec<_>>();
-        module_name.push(0);
-
-        let proc_name = CString::new(proc_name.as_ref()).unwrap();
+        let (module_name, proc_name) = api_names(module_name, proc_name)?;
         let mut pp_original: *mut c_void = null_mut();
-        let pp_target: *mut *mut c_void = null_mut();
+        let mut pp_target: *mut c_void = null_mut();
         let status = unsafe {
             MH_CreateHookApiEx(
-                module_name.as_ptr() as *const _,
+                module_name.as_ptr(),
                 proc_name.as_ptr() as *const _,
                 detour,
                 &mut pp_original,
-                pp_target,
+                &mut pp_target,
             )
         };
+        os_error::capture(status);
         debug!("MH_CreateHookApiEx: {:?}", status);
         match status {
-            MH_STATUS::MH_OK => Ok((pp_original, pp_target)),
+            MH_STATUS::MH_OK => {
+                state::record(pp_target, Operation::Create, status);
+                let foreign_detour = unsafe { hook::foreign_detour(pp_target) };
+                state::record_created(pp_target, detour, pp_original, foreign_detour);
+                Ok((pp_original, pp_target))
+            }
             _ => Err(status),
         }
     }
@@ -174,10 +242,12 @@ impl MinHook {
     ///
     /// # Safety
     pub unsafe fn enable_hook(target: *mut c_void) -> Result<(), MH_STATUS> {
-        Self::initialize();
+        Self::initialize()?;
 
         let status = unsafe { MH_EnableHook(target) };
+        os_error::capture(status);
         debug!("MH_EnableHook: {:?}", status);
+        state::record(target, Operation::Enable, status);
         match status {
             MH_STATUS::MH_OK => Ok(()),
             _ => Err(status),
@@ -188,17 +258,19 @@ impl MinHook {
     ///
     /// # Safety
     pub unsafe fn enable_all_hooks() -> Result<(), MH_STATUS> {
-        unsafe { Self::enable_hook(MH_ALL_HOOKS as *mut _) }
+        unsafe { Self::enable_hook(MH_ALL_HOOKS) }
     }
 
     /// Disables a hook for the target function.
     ///
     /// # Safety
     pub unsafe fn disable_hook(target: *mut c_void) -> Result<(), MH_STATUS> {
-        Self::initialize();
+        Self::initialize()?;
 
         let status = unsafe { MH_DisableHook(target) };
+        os_error::capture(status);
         debug!("MH_DisableHook: {:?}", status);
+        state::record(target, Operation::Disable, status);
         match status {
             MH_STATUS::MH_OK => Ok(()),
             _ => Err(status),
@@ -209,17 +281,19 @@ impl MinHook {
     ///
     /// # Safety
     pub unsafe fn disable_all_hooks() -> Result<(), MH_STATUS> {
-        unsafe { Self::disable_hook(MH_ALL_HOOKS as *mut _) }
+        unsafe { Self::disable_hook(MH_ALL_HOOKS) }
     }
 
     /// Removes a hook for the target function.
     ///
     /// # Safety
     pub unsafe fn remove_hook(target: *mut c_void) -> Result<(), MH_STATUS> {
-        Self::initialize();
+        Self::initialize()?;
 
         let status = unsafe { MH_RemoveHook(target) };
+        os_error::capture(status);
         debug!("MH_RemoveHook: {:?}", status);
+        state::record(target, Operation::Remove, status);
         match status {
             MH_STATUS::MH_OK => Ok(()),
             _ => Err(status),
@@ -230,12 +304,17 @@ impl MinHook {
     ///
     /// # Safety
     pub unsafe fn queue_enable_hook(target: *mut c_void) -> Result<(), MH_STATUS> {
-        Self::initialize();
+        Self::initialize()?;
 
         let status = unsafe { MH_QueueEnableHook(target) };
+        os_error::capture(status);
         debug!("MH_QueueEnableHook: {:?}", status);
+        state::record(target, Operation::QueueEnable, status);
         match status {
-            MH_STATUS::MH_OK => Ok(()),
+            MH_STATUS::MH_OK => {
+                QUEUED_HOOKS.fetch_add(1, Ordering::Relaxed);
+                Ok(())
+            }
             _ => Err(status),
         }
     }
@@ -244,12 +323,17 @@ impl MinHook {
     ///
     /// # Safety
     pub unsafe fn queue_disable_hook(target: *mut c_void) -> Result<(), MH_STATUS> {
-        Self::initialize();
+        Self::initialize()?;
 
         let status = unsafe { MH_QueueDisableHook(target) };
+        os_error::capture(status);
         debug!("MH_QueueDisableHook: {:?}", status);
+        state::record(target, Operation::QueueDisable, status);
         match status {
-            MH_STATUS::MH_OK => Ok(()),
+            MH_STATUS::MH_OK => {
+                QUEUED_HOOKS.fetch_add(1, Ordering::Relaxed);
+                Ok(())
+            }
             _ => Err(status),
         }
     }
@@ -258,15 +342,192 @@ impl MinHook {
     ///
     /// # Safety
     pub unsafe fn apply_queued() -> Result<(), MH_STATUS> {
-        Self::initialize();
+        Self::initialize()?;
 
         let status = unsafe { MH_ApplyQueued() };
+        os_error::capture(status);
         debug!("MH_ApplyQueued: {:?}", status);
         match status {
-            MH_STATUS::MH_OK => Ok(()),
+            MH_STATUS::MH_OK => {
+                state::record_applied();
+                QUEUED_HOOKS.store(0, Ordering::Relaxed);
+                Ok(())
+            }
             _ => Err(status),
         }
     }
+
+    /// Applies all queued hooks, and logs a warning if applying them takes longer than the threshold.
+    ///
+    /// Applying the queue suspends all other threads of the process, so a slow apply usually means a thread was slow
+    /// to suspend or resume. The status is the same as the one returned by [`MinHook::apply_queued`].
+    ///
+    /// # Safety
+    pub unsafe fn apply_queued_with_warn(threshold: Duration) -> Result<(), MH_STATUS> {
+        let queued = QUEUED_HOOKS.load(Ordering::Relaxed);
+
+        let start = Instant::now();
+        let result = unsafe { Self::apply_queued() };
+        let elapsed = start.elapsed();
+
+        if elapsed > threshold {
+            warning!(
+                "MH_ApplyQueued took {:?} for {} queued hooks, exceeding the threshold of {:?}",
+                elapsed,
+                queued,
+                threshold
+            );
+        } else {
+            debug!(
+                "MH_ApplyQueued took {:?} for {} queued hooks",
+                elapsed, queued
+            );
+        }
+
+        result
+    }
+
+    /// Applies all queued hooks like [`MinHook::apply_queued`], and reports how many threads were suspended and how long
+    /// it took, to debug crashes that happen while hooks are applied.
+    ///
+    /// # Safety
+    pub unsafe fn apply_queued_report() -> Result<ApplyReport, MH_STATUS> {
+        let queued = QUEUED_HOOKS.load(Ordering::Relaxed);
+        let threads = report::other_threads();
+
+        let start = Instant::now();
+        unsafe { Self::apply_queued()? };
+        let report = ApplyReport {
+            queued,
+            threads,
+            duration: start.elapsed(),
+        };
+        debug!("{}", report);
+
+        Ok(report)
+    }
+}
+
+// Converts the names of an API function for MinHook. Names with interior nulls can't name a module or an export, and
+// API set names are resolved to their host module, since MinHook can't look them up.
+fn api_names<M: Into<ModuleName>, P: AsRef<str>>(
+    module_name: M,
+    proc_name: P,
+) -> Result<(ModuleName, CString), MH_STATUS> {
+    let module_name = module::resolve_api_set(module_name.into());
+    if module_name.as_wide().contains(&0) {
+        return Err(MH_STATUS::MH_ERROR_MODULE_NOT_FOUND);
+    }
+
+    let proc_name =
+        CString::new(proc_name.as_ref()).map_err(|_| MH_STATUS::MH_ERROR_FUNCTION_NOT_FOUND)?;
+
+    Ok((module_name, proc_name))
+}
+
+/// Installs a panic hook that disables all hooks with [`disable_all_hooks_unchecked`] before the previously installed
+/// panic hook runs.
+///
+/// This keeps a panic in a detour or in setup code from leaving hooks enabled that point into code which is about to be
+/// unwound. Installing the cleanup more than once has no effect.
+pub fn install_panic_cleanup() {
+    PANIC_CLEANUP.call_once(|| {
+        let previous = std::panic::take_hook();
+
+        std::panic::set_hook(Box::new(move |info| {
+            disable_hooks_on_panic();
+            previous(info);
+        }));
+    });
+}
+
+// The cleanup run by the panic hook of `install_panic_cleanup`. The panic may have happened while this crate held one
+// of its locks, or before MinHook was initialized, so the hooks are disabled without locking or initializing anything
+fn disable_hooks_on_panic() {
+    disable_all_hooks_unchecked();
+}
+
+/// Enables all created hooks at once, using MinHook's `MH_ALL_HOOKS`.
+pub fn enable_all_hooks() -> Result<(), MH_STATUS> {
+    unsafe { MinHook::enable_all_hooks() }
+}
+
+/// Disables all created hooks at once, using MinHook's `MH_ALL_HOOKS`.
+///
+/// This restores the original code of every target, without having to keep track of the hooks.
+pub fn disable_all_hooks() -> Result<(), MH_STATUS> {
+    unsafe { MinHook::disable_all_hooks() }
+}
+
+/// Disables all created hooks at once, for use from crash handlers and other emergency paths.
+///
+/// Unlike [`disable_all_hooks`], this calls MinHook directly: it doesn't initialize MinHook, take any locks, or log,
+/// and the status is ignored. Nothing happens if MinHook is not initialized. The states returned by
+/// [`MhHook::is_enabled`] are not updated.
+pub fn disable_all_hooks_unchecked() {
+    let _ = unsafe { MH_DisableHook(MH_ALL_HOOKS) };
+}
+
+/// A null-terminated UTF-16 module name.
+///
+/// MinHook expects module names as wide strings, while procedure names are narrow. This type owns the
+/// encoded module name so it can't be mixed up with a narrow string by accident.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ModuleName(Vec<u16>);
+
+impl ModuleName {
+    /// Creates a module name by encoding the string as UTF-16.
+    pub fn new<T: AsRef<str>>(name: T) -> Self {
+        let mut wide = name.as_ref().encode_utf16().collect::<Vec<_>>();
+        wide.push(0);
+        Self(wide)
+    }
+
+    /// Creates a module name from UTF-16 code units, which may include non-ASCII characters. The name ends at the
+    /// first null, if it contains one.
+    pub fn from_wide(name: &[u16]) -> Self {
+        let mut wide = name
+            .iter()
+            .copied()
+            .take_while(|&c| c != 0)
+            .collect::<Vec<_>>();
+        wide.push(0);
+        Self(wide)
+    }
+
+    /// Returns the UTF-16 code units of the module name, without the terminating null.
+    pub fn as_wide(&self) -> &[u16] {
+        &self.0[..self.0.len() - 1]
+    }
+
+    /// Returns a pointer to the null-terminated UTF-16 module name.
+    pub fn as_ptr(&self) -> *const u16 {
+        self.0.as_ptr()
+    }
+}
+
+impl From<&str> for ModuleName {
+    fn from(name: &str) -> Self {
+        Self::new(name)
+    }
+}
+
+impl From<String> for ModuleName {
+    fn from(name: String) -> Self {
+        Self::new(name)
+    }
+}
+
+impl From<&String> for ModuleName {
+    fn from(name: &String) -> Self {
+        Self::new(name)
+    }
+}
+
+impl From<&OsStr> for ModuleName {
+    fn from(name: &OsStr) -> Self {
+        Self::from_wide(&name.encode_wide().collect::<Vec<_>>())
+    }
 }
 
 /// MinHook status codes.
@@ -315,22 +576,105 @@ impl MH_STATUS {
             Err(self)
         }
     }
+
+    /// Returns the `HRESULT` closest to the status, for returning it from COM methods and other `HRESULT` APIs.
+    ///
+    /// Errors without a matching Windows error are mapped to `E_FAIL`. The OS error behind a memory error is available
+    /// through [`last_os_error`].
+    pub fn to_hresult(self) -> i32 {
+        // HRESULT_FROM_WIN32 for the Windows errors that match the status
+        const fn from_win32(code: u32) -> i32 {
+            (0x8007_0000 | code) as i32
+        }
+
+        match self {
+            MH_STATUS::MH_OK => 0,
+            MH_STATUS::MH_ERROR_ALREADY_INITIALIZED | MH_STATUS::MH_ERROR_ALREADY_CREATED => {
+                from_win32(183) // ERROR_ALREADY_EXISTS
+            }
+            MH_STATUS::MH_ERROR_NOT_INITIALIZED
+            | MH_STATUS::MH_ERROR_ENABLED
+            | MH_STATUS::MH_ERROR_DISABLED => from_win32(5023), // ERROR_INVALID_STATE
+            MH_STATUS::MH_ERROR_NOT_CREATED => from_win32(1168), // ERROR_NOT_FOUND
+            MH_STATUS::MH_ERROR_NOT_EXECUTABLE | MH_STATUS::MH_ERROR_UNSUPPORTED_FUNCTION => {
+                from_win32(87) // E_INVALIDARG
+            }
+            MH_STATUS::MH_ERROR_MEMORY_ALLOC => from_win32(14), // E_OUTOFMEMORY
+            MH_STATUS::MH_ERROR_MEMORY_PROTECT => from_win32(5), // E_ACCESSDENIED
+            MH_STATUS::MH_ERROR_MODULE_NOT_FOUND => from_win32(126), // ERROR_MOD_NOT_FOUND
+            MH_STATUS::MH_ERROR_FUNCTION_NOT_FOUND => from_win32(127), // ERROR_PROC_NOT_FOUND
+            MH_STATUS::MH_UNKNOWN => 0x8000_4005_u32 as i32,    // E_FAIL
+        }
+    }
+}
+
+impl fmt::Display for MH_STATUS {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            MH_STATUS::MH_UNKNOWN => "Unknown error",
+            MH_STATUS::MH_OK => "Successful",
+            MH_STATUS::MH_ERROR_ALREADY_INITIALIZED => "MinHook is already initialized",
+            MH_STATUS::MH_ERROR_NOT_INITIALIZED => {
+                "MinHook is not initialized yet, or already uninitialized"
+            }
+            MH_STATUS::MH_ERROR_ALREADY_CREATED => {
+                "The hook for the specified target function is already created"
+            }
+            MH_STATUS::MH_ERROR_NOT_CREATED => {
+                "The hook for the specified target function is not created yet"
+            }
+            MH_STATUS::MH_ERROR_ENABLED => {
+                "The hook for the specified target function is already enabled"
+            }
+            MH_STATUS::MH_ERROR_DISABLED => {
+                "The hook for the specified target function is not enabled yet, or already disabled"
+            }
+            MH_STATUS::MH_ERROR_NOT_EXECUTABLE => {
+                "The specified pointer is invalid. It points the address of non-allocated and/or non-executable region"
+            }
+            MH_STATUS::MH_ERROR_UNSUPPORTED_FUNCTION => {
+                "The specified target function cannot be hooked"
+            }
+            MH_STATUS::MH_ERROR_MEMORY_ALLOC => "Failed to allocate memory",
+            MH_STATUS::MH_ERROR_MEMORY_PROTECT => "Failed to change the memory protection",
+            MH_STATUS::MH_ERROR_MODULE_NOT_FOUND => "The specified module is not loaded",
+            MH_STATUS::MH_ERROR_FUNCTION_NOT_FOUND => "The specified function is not found",
+        };
+
+        write!(f, "{message} ({self:?})")
+    }
+}
+
+impl std::error::Error for MH_STATUS {}
+
+impl From<MH_STATUS> for io::Error {
+    fn from(status: MH_STATUS) -> Self {
+        let kind = match status {
+            MH_STATUS::MH_ERROR_MODULE_NOT_FOUND | MH_STATUS::MH_ERROR_FUNCTION_NOT_FOUND => {
+                io::ErrorKind::NotFound
+            }
+            MH_STATUS::MH_ERROR_MEMORY_ALLOC => io::ErrorKind::OutOfMemory,
+            MH_STATUS::MH_ERROR_MEMORY_PROTECT => io::ErrorKind::PermissionDenied,
+            _ => io::ErrorKind::Other,
+        };
+
+        io::Error::new(kind, status)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testing::HookTest;
     use once_cell::sync::OnceCell;
     use std::{ffi::c_void, mem};
 
     #[test]
     fn test_hook() {
+        let _test = HookTest::start();
+
         unsafe {
-            MinHook::create_hook(
-                test_fn as FnType as *mut c_void,
-                test_fn_hook as FnType as *mut c_void,
-            )
-            .unwrap();
+            MhHook::from_fns::<FnType>(test_fn, test_fn_hook).unwrap();
 
             // Test that the hook is enabled.
             MinHook::enable_hook(test_fn as FnType as *mut c_void).unwrap();
@@ -343,6 +687,7 @@ mod tests {
 
         type FnType = fn() -> i32;
 
+        #[inline(never)]
         fn test_fn() -> i32 {
             0
         }
@@ -354,17 +699,11 @@ mod tests {
 
     #[test]
     fn test_hooks_queue() {
+        let _test = HookTest::start();
+
         unsafe {
-            MinHook::create_hook(
-                test_fn1 as FnType1 as *mut c_void,
-                test_fn1_hook as FnType1 as *mut c_void,
-            )
-            .unwrap();
-            MinHook::create_hook(
-                test_fn2 as FnType2 as *mut c_void,
-                test_fn2_hook as FnType2 as *mut c_void,
-            )
-            .unwrap();
+            MhHook::from_fns::<FnType1>(test_fn1, test_fn1_hook).unwrap();
+            MhHook::from_fns::<FnType2>(test_fn2, test_fn2_hook).unwrap();
 
             // Queue to enable the hooks, then apply them.
             MinHook::queue_enable_hook(test_fn1 as FnType1 as *mut c_void).unwrap();
@@ -388,6 +727,7 @@ mod tests {
         type FnType1 = fn() -> i32;
         type FnType2 = fn(i32) -> i32;
 
+        #[inline(never)]
         fn test_fn1() -> i32 {
             0
         }
@@ -396,6 +736,7 @@ mod tests {
             1
         }
 
+        #[inline(never)]
         fn test_fn2(x: i32) -> i32 {
             x
         }
@@ -406,14 +747,235 @@ mod tests {
     }
 
     #[test]
-    fn test_hook_trampoline() {
+    fn test_apply_queued_report() {
+        let _test = HookTest::start();
+
         unsafe {
-            // Create a hook for `test_fn_trampoline_orig`
-            let trampoline = MinHook::create_hook(
-                test_fn_trampoline_orig as FnType as *mut c_void,
-                test_fn_trampoline_hook as FnType as *mut c_void,
+            MhHook::from_fns::<FnType>(test_fn, test_fn_hook).unwrap();
+
+            MinHook::queue_enable_hook(test_fn as FnType as *mut c_void).unwrap();
+            let report = MinHook::apply_queued_report().unwrap();
+            assert!(report.queued >= 1);
+            // The test runs on a thread of its own, so at least the main thread was suspended.
+            assert!(report.threads >= 1);
+            assert_eq!(test_fn(), 111);
+
+            MinHook::remove_hook(test_fn as FnType as *mut c_void).unwrap();
+        }
+
+        type FnType = fn() -> i32;
+
+        #[inline(never)]
+        fn test_fn() -> i32 {
+            std::hint::black_box(110)
+        }
+
+        fn test_fn_hook() -> i32 {
+            111
+        }
+    }
+
+    #[test]
+    fn test_apply_queued_with_warn() {
+        let _test = HookTest::start();
+
+        unsafe {
+            MhHook::from_fns::<FnType>(test_fn, test_fn_hook).unwrap();
+
+            // Applying with a zero threshold always warns, but returns the same status as a plain apply.
+            MinHook::queue_enable_hook(test_fn as FnType as *mut c_void).unwrap();
+            assert_eq!(MinHook::apply_queued_with_warn(Duration::ZERO), Ok(()));
+            assert_eq!(test_fn(), 1);
+
+            MinHook::queue_disable_hook(test_fn as FnType as *mut c_void).unwrap();
+            assert_eq!(MinHook::apply_queued(), Ok(()));
+            assert_eq!(test_fn(), 0);
+        }
+
+        type FnType = fn() -> i32;
+
+        #[inline(never)]
+        fn test_fn() -> i32 {
+            0
+        }
+
+        fn test_fn_hook() -> i32 {
+            1
+        }
+    }
+
+    #[test]
+    fn test_panic_cleanup() {
+        let _test = HookTest::start();
+
+        unsafe {
+            let hook = MhHook::from_fns::<FnType>(test_fn, test_fn_hook).unwrap();
+            MinHook::enable_hook(hook.target()).unwrap();
+            assert_eq!(test_fn(), 1);
+        }
+
+        // The cleanup is called directly, as installing the panic hook would affect every other test.
+        disable_hooks_on_panic();
+        assert_eq!(test_fn(), 190);
+
+        type FnType = fn() -> i32;
+
+        #[inline(never)]
+        fn test_fn() -> i32 {
+            std::hint::black_box(190)
+        }
+
+        fn test_fn_hook() -> i32 {
+            1
+        }
+    }
+
+    #[test]
+    fn test_disable_all_hooks_unchecked() {
+        let _test = HookTest::start();
+
+        unsafe {
+            let hook = MhHook::from_fns::<FnType>(test_fn, test_fn_hook).unwrap();
+            hook.enable().unwrap();
+            assert_eq!(test_fn(), 1);
+        }
+
+        // This disables the hooks of every test, which is only safe because HookTest serializes the hook tests.
+        disable_all_hooks_unchecked();
+        assert_eq!(test_fn(), 195);
+
+        type FnType = fn() -> i32;
+
+        #[inline(never)]
+        fn test_fn() -> i32 {
+            std::hint::black_box(195)
+        }
+
+        fn test_fn_hook() -> i32 {
+            1
+        }
+    }
+
+    #[test]
+    fn test_status_into_io_error() {
+        let error = io::Error::from(MH_STATUS::MH_ERROR_MODULE_NOT_FOUND);
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+        assert_eq!(
+            error.to_string(),
+            MH_STATUS::MH_ERROR_MODULE_NOT_FOUND.to_string()
+        );
+
+        let error = io::Error::from(MH_STATUS::MH_ERROR_MEMORY_ALLOC);
+        assert_eq!(error.kind(), io::ErrorKind::OutOfMemory);
+
+        let error = io::Error::from(MH_STATUS::MH_ERROR_ENABLED);
+        assert_eq!(error.kind(), io::ErrorKind::Other);
+    }
+
+    #[test]
+    fn test_status_to_hresult() {
+        assert_eq!(MH_STATUS::MH_OK.to_hresult(), 0);
+        assert_eq!(
+            MH_STATUS::MH_ERROR_MEMORY_ALLOC.to_hresult(),
+            0x8007_000E_u32 as i32
+        );
+        assert_eq!(MH_STATUS::MH_UNKNOWN.to_hresult(), 0x8000_4005_u32 as i32);
+
+        // Every error is a failure HRESULT.
+        assert!(MH_STATUS::MH_ERROR_NOT_CREATED.to_hresult() < 0);
+    }
+
+    #[test]
+    fn test_module_name_unicode() {
+        let path = "C:\\Spiele\\Überprüfung\\モジュール.dll";
+        let module_name = ModuleName::from(path);
+
+        // The name is encoded as UTF-16 and terminated with a null.
+        assert_eq!(
+            module_name.as_wide(),
+            path.encode_utf16().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            unsafe { *module_name.as_ptr().add(module_name.as_wide().len()) },
+            0
+        );
+    }
+
+    #[test]
+    fn test_module_name_wide() {
+        let name = ModuleName::from("kernel32.dll");
+        assert_eq!(ModuleName::from_wide(name.as_wide()), name);
+        assert_eq!(ModuleName::from(OsStr::new("kernel32.dll")), name);
+
+        // The name ends at the first null.
+        let mut wide = name.as_wide().to_vec();
+        wide.extend([0, 'x' as u16]);
+        assert_eq!(ModuleName::from_wide(&wide), name);
+    }
+
+    #[test]
+    fn test_hook_api_unicode_module_not_found() {
+        let _test = HookTest::start();
+
+        unsafe {
+            let status = MinHook::create_hook_api(
+                "Überprüfung_モジュール.dll",
+                "return_0",
+                test_fn_hook as FnType as *mut c_void,
+            );
+            assert_eq!(status, Err(MH_STATUS::MH_ERROR_MODULE_NOT_FOUND));
+        }
+
+        type FnType = fn() -> i32;
+
+        fn test_fn_hook() -> i32 {
+            1
+        }
+    }
+
+    #[test]
+    fn test_hook_api() {
+        let _test = HookTest::start();
+
+        unsafe {
+            // Hooking an exported API by name only succeeds if the module name is passed as UTF-16.
+            let trampoline = MinHook::create_hook_api(
+                "kernel32.dll",
+                "GetCurrentProcessId",
+                get_current_process_id_hook as FnType as *mut c_void,
             )
             .unwrap();
+            assert!(!trampoline.is_null());
+
+            // The hook is not enabled, but the trampoline still calls the original function.
+            let original = mem::transmute::<*mut c_void, FnType>(trampoline);
+            assert_eq!(original(), std::process::id());
+
+            let target = state::created()
+                .into_iter()
+                .find(|info| info.trampoline == trampoline)
+                .unwrap()
+                .target;
+            MinHook::remove_hook(target).unwrap();
+        }
+
+        type FnType = unsafe extern "system" fn() -> u32;
+
+        unsafe extern "system" fn get_current_process_id_hook() -> u32 {
+            0
+        }
+    }
+
+    #[test]
+    fn test_hook_trampoline() {
+        let _test = HookTest::start();
+
+        unsafe {
+            // Create a hook for `test_fn_trampoline_orig`
+            let trampoline =
+                MhHook::from_fns::<FnType>(test_fn_trampoline_orig, test_fn_trampoline_hook)
+                    .unwrap()
+                    .trampoline();
 
             // Store the trampoline function.
             TRAMPOLINE.get_or_init(|| mem::transmute(trampoline));
@@ -427,6 +989,7 @@ mod tests {
         type FnType = fn(i32) -> i32;
         static TRAMPOLINE: OnceCell<FnType> = OnceCell::new();
 
+        #[inline(never)]
         fn test_fn_trampoline_orig(x: i32) -> i32 {
             x
         }
@@ -0,0 +1,149 @@
+use crate::logging::debug;
+use std::fmt;
+
+// Logs the arguments of a call made through a detour generated by `log_hook!`
+#[doc(hidden)]
+pub fn log_call(name: &str, args: &[(&str, &dyn fmt::Debug)]) {
+    debug!("{}({})", name, CallArgs(args));
+}
+
+// Logs the return value of a call made through a detour generated by `log_hook!`
+#[doc(hidden)]
+pub fn log_return(name: &str, ret: &dyn fmt::Debug) {
+    debug!("{} returned {:?}", name, ret);
+}
+
+struct CallArgs<'a>(&'a [(&'a str, &'a dyn fmt::Debug)]);
+
+impl fmt::Display for CallArgs<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, (name, value)) in self.0.iter().enumerate() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{name}: {value:?}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Declares statics holding a [`DeferredHook`](crate::DeferredHook) on an exported function, whose generated detour
+/// logs the arguments and the return value of each call before passing it on to the original function.
+///
+/// The calls are logged at the debug level, like the messages of the crate itself, so they reach the
+/// [log callback](crate::set_log_callback), and `tracing` with the `tracing` feature. The export is looked up by the
+/// name of the function when the hook is first enabled. The argument and return types must implement `Debug`.
+/// Calls made while logging, for example when the hooked function allocates, go straight to the original function.
+///
+/// # Example
+///
+/// ```rust
+/// use minhook::{log_hook, MH_STATUS};
+///
+/// log_hook! {
+///     static GET_TICK_COUNT_HOOK: "kernel32.dll", unsafe extern "system" fn GetTickCount() -> u32;
+/// }
+///
+/// fn main() -> Result<(), MH_STATUS> {
+///     unsafe { GET_TICK_COUNT_HOOK.enable()? };
+///
+///     // Logs "GetTickCount()" and "GetTickCount returned ..."
+///     let _ = unsafe { get_tick_count() };
+///
+///     Ok(())
+/// }
+///
+/// unsafe fn get_tick_count() -> u32 {
+///     unsafe extern "system" {
+///         fn GetTickCount() -> u32;
+///     }
+///
+///     unsafe { GetTickCount() }
+/// }
+/// ```
+#[macro_export]
+macro_rules! log_hook {
+    (@impl [$($attr:tt)*] $vis:vis $name:ident $module:expr, $fn_name:ident [$($qual:tt)*] [$($arg:ident: $ty:ty),*] [$($ret:ty)?]) => {
+        $($attr)*
+        $vis static $name: $crate::DeferredHook<$($qual)* fn($($ty),*) -> $crate::log_hook!(@ret $($ret)?)> =
+            $crate::DeferredHook::new(
+                {
+                    fn resolve() -> ::std::result::Result<*mut ::std::ffi::c_void, $crate::MH_STATUS> {
+                        $crate::deferred_hook!(@resolve api($module, ::std::stringify!($fn_name)))
+                    }
+                    resolve
+                },
+                {
+                    #[allow(unused_unsafe, clippy::too_many_arguments)]
+                    $($qual)* fn detour($($arg: $ty),*) -> $crate::log_hook!(@ret $($ret)?) {
+                        let original = $name.original().expect("The detour only runs once the hook is created");
+                        $crate::reentrancy_guard!(unsafe { original($($arg),*) });
+
+                        $crate::__log_call(
+                            ::std::stringify!($fn_name),
+                            &[$((::std::stringify!($arg), &$arg as &dyn ::std::fmt::Debug)),*],
+                        );
+                        let ret = unsafe { original($($arg),*) };
+                        $crate::__log_return(::std::stringify!($fn_name), &ret);
+
+                        ret
+                    }
+
+                    detour
+                },
+            );
+    };
+    (@ret) => { () };
+    (@ret $ret:ty) => { $ret };
+    () => {};
+    ($(#[$attr:meta])* $vis:vis static $name:ident: $module:expr, fn $fn_name:ident($($arg:ident: $ty:ty),* $(,)?) $(-> $ret:ty)?; $($rest:tt)*) => {
+        $crate::log_hook!(@impl [$(#[$attr])*] $vis $name $module, $fn_name [] [$($arg: $ty),*] [$($ret)?]);
+        $crate::log_hook!($($rest)*);
+    };
+    ($(#[$attr:meta])* $vis:vis static $name:ident: $module:expr, extern $abi:literal fn $fn_name:ident($($arg:ident: $ty:ty),* $(,)?) $(-> $ret:ty)?; $($rest:tt)*) => {
+        $crate::log_hook!(@impl [$(#[$attr])*] $vis $name $module, $fn_name [extern $abi] [$($arg: $ty),*] [$($ret)?]);
+        $crate::log_hook!($($rest)*);
+    };
+    ($(#[$attr:meta])* $vis:vis static $name:ident: $module:expr, unsafe extern $abi:literal fn $fn_name:ident($($arg:ident: $ty:ty),* $(,)?) $(-> $ret:ty)?; $($rest:tt)*) => {
+        $crate::log_hook!(@impl [$(#[$attr])*] $vis $name $module, $fn_name [unsafe extern $abi] [$($arg: $ty),*] [$($ret)?]);
+        $crate::log_hook!($($rest)*);
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::HookTest;
+
+    #[test]
+    fn test_log_hook() {
+        let _test = HookTest::start();
+
+        crate::log_hook! {
+            static GET_OEMCP_HOOK: "kernel32.dll", unsafe extern "system" fn GetOEMCP() -> u32;
+            static MUL_DIV_HOOK: "kernel32.dll", unsafe extern "system" fn MulDiv(number: i32, numerator: i32, denominator: i32) -> i32;
+        }
+
+        unsafe extern "system" {
+            fn GetOEMCP() -> u32;
+            fn MulDiv(number: i32, numerator: i32, denominator: i32) -> i32;
+        }
+
+        unsafe {
+            let code_page = GetOEMCP();
+
+            // The calls still return what the original functions return.
+            GET_OEMCP_HOOK.enable().unwrap();
+            MUL_DIV_HOOK.enable().unwrap();
+            assert_eq!(GetOEMCP(), code_page);
+            assert_eq!(MulDiv(6, 4, 3), 8);
+            GET_OEMCP_HOOK.disable().unwrap();
+            MUL_DIV_HOOK.disable().unwrap();
+        }
+
+        let args: [(&str, &dyn fmt::Debug); 2] = [("number", &6), ("path", &"a.txt")];
+        assert_eq!(CallArgs(&args).to_string(), "number: 6, path: \"a.txt\"");
+        assert_eq!(CallArgs(&[]).to_string(), "");
+    }
+}
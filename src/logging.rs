@@ -0,0 +1,93 @@
+use std::{
+    fmt,
+    sync::atomic::{AtomicPtr, Ordering},
+};
+
+/// The level of a message logged by the crate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LogLevel {
+    /// The status of each MinHook call and other details.
+    Debug,
+    /// Something unexpected that the crate recovered from.
+    Warn,
+}
+
+/// A function that receives the messages logged by the crate, set through [`set_log_callback`].
+pub type LogCallback = fn(LogLevel, fmt::Arguments<'_>);
+
+// The callback set through `set_log_callback`, or null if none is set
+static LOG_CALLBACK: AtomicPtr<()> = AtomicPtr::new(std::ptr::null_mut());
+
+/// Sets a function that receives all messages logged by the crate, for example to route them to an in-game console.
+///
+/// The messages are also emitted as `tracing` events with the `tracing` feature, and reach the callback either way.
+/// Without a callback, logging a message only checks whether one is set and whether `tracing` is interested in it.
+pub fn set_log_callback(callback: Option<LogCallback>) {
+    let callback = callback.map_or(std::ptr::null_mut(), |callback| callback as *mut ());
+    LOG_CALLBACK.store(callback, Ordering::Release);
+}
+
+pub(crate) fn log(level: LogLevel, args: fmt::Arguments<'_>) {
+    let callback = LOG_CALLBACK.load(Ordering::Acquire);
+    if !callback.is_null() {
+        // Only `LogCallback` function pointers are stored
+        let callback = unsafe { std::mem::transmute::<*mut (), LogCallback>(callback) };
+        callback(level, args);
+    }
+}
+
+// Logs a message through `tracing` and the log callback. Without a callback or `tracing`, this only loads the
+// callback, and the message is not formatted
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        match format_args!($($arg)*) {
+            args => {
+                #[cfg(feature = "tracing")]
+                ::tracing::debug!("{}", args);
+                $crate::logging::log($crate::LogLevel::Debug, args);
+            }
+        }
+    };
+}
+
+// Logs a warning through `tracing` and the log callback
+macro_rules! warning {
+    ($($arg:tt)*) => {
+        match format_args!($($arg)*) {
+            args => {
+                #[cfg(feature = "tracing")]
+                ::tracing::warn!("{}", args);
+                $crate::logging::log($crate::LogLevel::Warn, args);
+            }
+        }
+    };
+}
+
+pub(crate) use {debug, warning};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_log_callback() {
+        static MESSAGES: Mutex<Vec<(LogLevel, String)>> = Mutex::new(Vec::new());
+
+        set_log_callback(Some(|level, args| {
+            MESSAGES.lock().unwrap().push((level, args.to_string()))
+        }));
+        warning!("test message {}", 1);
+        debug!("test message {}", 3);
+        set_log_callback(None);
+        warning!("test message {}", 2);
+
+        // Other tests may log while the callback is set.
+        let messages = MESSAGES.lock().unwrap();
+        assert!(messages.contains(&(LogLevel::Warn, "test message 1".to_owned())));
+        assert!(!messages.contains(&(LogLevel::Warn, "test message 2".to_owned())));
+
+        // Debug messages reach the callback with and without the `tracing` feature.
+        assert!(messages.contains(&(LogLevel::Debug, "test message 3".to_owned())));
+    }
+}
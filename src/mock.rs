@@ -0,0 +1,247 @@
+//! Windows APIs replaced by closures in tests, usually through [`mock!`](crate::mock!).
+//!
+//! A mock is installed when it is created and restored when its [`Mock`] guard is dropped, including when the test
+//! panics. Tests that mock the same API must not run at the same time, which a
+//! [`HookTest`](crate::testing::HookTest) takes care of.
+
+use crate::{MH_STATUS, MhHook, ModuleName, logging::warning};
+use std::{
+    ffi::c_void,
+    fmt,
+    ptr::null_mut,
+    sync::{
+        PoisonError, RwLock,
+        atomic::{AtomicPtr, Ordering},
+    },
+};
+
+/// The closure a mocked function is replaced with, which is declared as a static by [`mock!`](crate::mock!).
+pub struct MockFn<T: ?Sized> {
+    closure: RwLock<Option<Box<T>>>,
+    original: AtomicPtr<c_void>,
+}
+
+impl<T: ?Sized> MockFn<T> {
+    /// Creates a function that isn't mocked yet.
+    pub const fn new() -> Self {
+        Self {
+            closure: RwLock::new(None),
+            original: AtomicPtr::new(null_mut()),
+        }
+    }
+
+    /// Calls the function with the closure, or returns `None` if the function isn't mocked.
+    pub fn with<R, F: FnOnce(&T) -> R>(&self, f: F) -> Option<R> {
+        // The closure stays consistent even if a panic happened while it was locked
+        let closure = self.closure.read().unwrap_or_else(PoisonError::into_inner);
+        closure.as_deref().map(f)
+    }
+
+    /// Returns the trampoline to the original function, or null if the function was never mocked.
+    pub fn original(&self) -> *mut c_void {
+        self.original.load(Ordering::Acquire)
+    }
+
+    /// Replaces an exported function with the closure until the returned guard is dropped.
+    ///
+    /// Returns the same errors as [`MhHook::new_api`] and [`MhHook::enable`].
+    ///
+    /// # Safety
+    ///
+    /// The detour must have the signature of the function, and call the closure through [`MockFn::with`].
+    pub unsafe fn install<M: Into<ModuleName>>(
+        &'static self,
+        module_name: M,
+        proc_name: &str,
+        detour: *mut c_void,
+        closure: Box<T>,
+    ) -> Result<Mock<T>, MH_STATUS> {
+        self.set(Some(closure));
+
+        let hook = match unsafe { MhHook::new_api(module_name, proc_name, detour) } {
+            Ok(hook) => hook,
+            Err(status) => {
+                self.set(None);
+                return Err(status);
+            }
+        };
+        self.original.store(hook.trampoline(), Ordering::Release);
+
+        if let Err(status) = unsafe { hook.enable() } {
+            let _ = unsafe { hook.remove() };
+            self.set(None);
+            return Err(status);
+        }
+
+        Ok(Mock {
+            hook: Some(hook),
+            mock: self,
+        })
+    }
+
+    fn set(&self, closure: Option<Box<T>>) {
+        *self.closure.write().unwrap_or_else(PoisonError::into_inner) = closure;
+    }
+}
+
+impl<T: ?Sized> Default for MockFn<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for MockFn<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MockFn")
+            .field("original", &self.original())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Keeps a function mocked while it is alive, returned by [`mock!`](crate::mock!).
+///
+/// Dropping the guard removes the hook first and the closure after it, so a call that is still running when the mock
+/// is restored goes to the original function instead.
+#[must_use = "the function is restored right away if the guard is dropped"]
+pub struct Mock<T: ?Sized + 'static> {
+    hook: Option<MhHook>,
+    mock: &'static MockFn<T>,
+}
+
+impl<T: ?Sized> Mock<T> {
+    /// Returns the hook that replaces the function.
+    pub fn hook(&self) -> &MhHook {
+        self.hook
+            .as_ref()
+            .expect("the hook is only taken when dropped")
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for Mock<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Mock").field("hook", &self.hook).finish()
+    }
+}
+
+impl<T: ?Sized> Drop for Mock<T> {
+    fn drop(&mut self) {
+        if let Some(hook) = self.hook.take() {
+            let target = hook.target();
+            if let Err(status) = unsafe { hook.remove() } {
+                warning!(
+                    "Could not restore mocked function {:?}: {:?}",
+                    target,
+                    status
+                );
+            }
+        }
+
+        self.mock.set(None);
+    }
+}
+
+/// Replaces an exported function with a closure, and restores it when the returned [`Mock`](crate::mock::Mock) is
+/// dropped.
+///
+/// The function is given by its module and its signature with named arguments. Safe and unsafe signatures with an
+/// `extern` ABI are supported, like for [`instrument_hook!`](crate::instrument_hook). The closure receives the same
+/// arguments. The arguments must be `Copy`, as they are passed to the original function if a call races with the mock
+/// being restored.
+///
+/// This evaluates to a `Result<Mock<_>, MH_STATUS>`, and must be used in an `unsafe` block since the signature is not
+/// checked against the function.
+///
+/// # Example
+///
+/// ```rust
+/// use minhook::{mock, testing::HookTest, MH_STATUS};
+///
+/// unsafe extern "system" {
+///     fn GetTickCount() -> u32;
+/// }
+///
+/// fn main() -> Result<(), MH_STATUS> {
+///     let _test = HookTest::start();
+///
+///     let mock = unsafe { mock!("kernel32.dll", unsafe extern "system" fn GetTickCount() -> u32, || 42)? };
+///     assert_eq!(unsafe { GetTickCount() }, 42);
+///
+///     drop(mock);
+///     assert_ne!(unsafe { GetTickCount() }, 42);
+///
+///     Ok(())
+/// }
+/// ```
+#[macro_export]
+macro_rules! mock {
+    ($module:expr, fn $name:ident($($arg:ident: $ty:ty),* $(,)?) $(-> $ret:ty)?, $closure:expr $(,)?) => {
+        $crate::mock!(@impl $module, $name [] [$($arg: $ty),*] [$($ret)?] $closure)
+    };
+    ($module:expr, extern $abi:literal fn $name:ident($($arg:ident: $ty:ty),* $(,)?) $(-> $ret:ty)?, $closure:expr $(,)?) => {
+        $crate::mock!(@impl $module, $name [extern $abi] [$($arg: $ty),*] [$($ret)?] $closure)
+    };
+    ($module:expr, unsafe extern $abi:literal fn $name:ident($($arg:ident: $ty:ty),* $(,)?) $(-> $ret:ty)?, $closure:expr $(,)?) => {
+        $crate::mock!(@impl $module, $name [unsafe extern $abi] [$($arg: $ty),*] [$($ret)?] $closure)
+    };
+    (@impl $module:expr, $name:ident [$($qual:tt)*] [$($arg:ident: $ty:ty),*] [$($ret:ty)?] $closure:expr) => {{
+        static MOCK: $crate::mock::MockFn<
+            dyn Fn($($ty),*) -> $crate::mock!(@ret $($ret)?) + Send + Sync,
+        > = $crate::mock::MockFn::new();
+
+        #[allow(unused_unsafe, clippy::too_many_arguments)]
+        $($qual)* fn detour($($arg: $ty),*) -> $crate::mock!(@ret $($ret)?) {
+            match MOCK.with(|closure| closure($($arg),*)) {
+                Some(ret) => ret,
+                None => unsafe {
+                    let original = ::std::mem::transmute::<
+                        *mut ::std::ffi::c_void,
+                        $($qual)* fn($($ty),*) -> $crate::mock!(@ret $($ret)?),
+                    >(MOCK.original());
+                    original($($arg),*)
+                },
+            }
+        }
+
+        MOCK.install(
+            $module,
+            ::std::stringify!($name),
+            detour as $($qual)* fn($($ty),*) -> $crate::mock!(@ret $($ret)?) as *mut ::std::ffi::c_void,
+            ::std::boxed::Box::new($closure),
+        )
+    }};
+    (@ret) => { () };
+    (@ret $ret:ty) => { $ret };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::HookTest;
+
+    unsafe extern "system" {
+        fn GetSystemTimeAsFileTime(time: *mut u64);
+    }
+
+    #[test]
+    fn test_mock() {
+        let _test = HookTest::start();
+
+        unsafe {
+            let mock = crate::mock!(
+                "kernel32.dll",
+                unsafe extern "system" fn GetSystemTimeAsFileTime(time: *mut u64),
+                |time| *time = 42
+            )
+            .unwrap();
+            assert!(mock.hook().is_enabled());
+
+            let mut time = 0;
+            GetSystemTimeAsFileTime(&mut time);
+            assert_eq!(time, 42);
+
+            // The function is restored with the guard, also when the test panics.
+            drop(mock);
+            GetSystemTimeAsFileTime(&mut time);
+            assert_ne!(time, 42);
+        }
+    }
+}
@@ -0,0 +1,649 @@
+//! Information about the modules loaded in the process.
+
+use crate::{
+    MH_STATUS, ModuleName,
+    pe::Image,
+    win::{
+        API_SET_SCHEMA_VERSION_V6, ApiSetNamespace, ApiSetNamespaceEntry, ApiSetValueEntry,
+        CURRENT_PROCESS, GET_MODULE_HANDLE_EX_FLAG_FROM_ADDRESS,
+        GET_MODULE_HANDLE_EX_FLAG_UNCHANGED_REFCOUNT, GetCurrentThreadId, GetModuleFileNameW,
+        GetModuleHandleExW, GetModuleHandleW, GetProcAddress, K32EnumProcessModules,
+        K32GetModuleInformation, ModuleInfo, NtQueryInformationProcess, PEB_API_SET_MAP_OFFSET,
+        PEB_LOADER_LOCK_OFFSET, PROCESS_BASIC_INFORMATION_CLASS, ProcessBasicInformation,
+        RtlCriticalSection,
+    },
+};
+use std::{
+    ffi::{CStr, OsString, c_char, c_void},
+    fmt,
+    mem::MaybeUninit,
+    os::windows::ffi::OsStringExt,
+    path::PathBuf,
+    sync::OnceLock,
+};
+
+/// A function or variable exported by a module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Export {
+    /// The name of the export, or `None` if it is only exported by ordinal.
+    pub name: Option<String>,
+    /// The ordinal of the export.
+    pub ordinal: u16,
+    /// The address of the export. For forwarded exports, this is the address of the forwarder string.
+    pub address: *mut c_void,
+    /// The export this export is forwarded to, as `module.name` or `module.#ordinal`, or `None` if the module
+    /// implements the export itself.
+    pub forwarder: Option<String>,
+}
+
+// The longest chain of forwarded exports that is followed
+const MAX_FORWARDS: usize = 16;
+
+// The longest path of a module file, in UTF-16 code units
+const MAX_MODULE_PATH: usize = 32768;
+
+/// A module loaded in the process.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadedModule {
+    /// The file name of the module, such as `kernel32.dll`.
+    pub name: String,
+    /// The base address of the module, which is also its handle.
+    pub base: *mut c_void,
+    /// The size of the image of the module, in bytes.
+    pub size: usize,
+    /// The full path of the module file.
+    pub path: PathBuf,
+}
+
+/// Returns the modules loaded in the process, in load order.
+///
+/// Modules that are unloaded while they are enumerated are left out. Returns `MH_UNKNOWN` if the modules can't be
+/// enumerated.
+pub fn loaded_modules() -> Result<impl Iterator<Item = LoadedModule>, MH_STATUS> {
+    let mut handles = Vec::<*mut c_void>::new();
+    loop {
+        let mut needed = 0;
+        let size = size_of_val(handles.as_slice()) as u32;
+        let enumerated = unsafe {
+            K32EnumProcessModules(CURRENT_PROCESS, handles.as_mut_ptr(), size, &mut needed)
+        };
+        if enumerated == 0 {
+            return Err(MH_STATUS::MH_UNKNOWN);
+        }
+
+        // Modules may be loaded while they are enumerated, so the handles are enumerated again until they fit
+        let count = needed as usize / size_of::<*mut c_void>();
+        if needed <= size {
+            handles.truncate(count);
+            break;
+        }
+        handles.resize(count, std::ptr::null_mut());
+    }
+
+    let mut path = vec![0u16; MAX_MODULE_PATH];
+    let modules = handles
+        .into_iter()
+        .filter_map(|handle| {
+            let mut info = MaybeUninit::<ModuleInfo>::zeroed();
+            let found = unsafe {
+                K32GetModuleInformation(
+                    CURRENT_PROCESS,
+                    handle,
+                    info.as_mut_ptr(),
+                    size_of::<ModuleInfo>() as u32,
+                )
+            };
+            if found == 0 {
+                return None;
+            }
+            let info = unsafe { info.assume_init() };
+
+            let length =
+                unsafe { GetModuleFileNameW(handle, path.as_mut_ptr(), path.len() as u32) };
+            if length == 0 {
+                return None;
+            }
+            let path = PathBuf::from(OsString::from_wide(&path[..length as usize]));
+            let name = path.file_name()?.to_string_lossy().into_owned();
+
+            Some(LoadedModule {
+                name,
+                base: info.base_of_dll,
+                size: info.size_of_image as usize,
+                path,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    Ok(modules.into_iter())
+}
+
+/// An address inside a loaded module, given as the offset from the base of the module.
+///
+/// It is displayed as `module.dll+0x1234`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleOffset {
+    /// The file name of the module.
+    pub module: String,
+    /// The offset of the address from the base of the module, in bytes.
+    pub offset: usize,
+}
+
+impl fmt::Display for ModuleOffset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}+{:#x}", self.module, self.offset)
+    }
+}
+
+// Formats an address as the module it is in with `Debug`, followed by its symbol if the `symbols` feature is enabled.
+// Addresses outside of the loaded modules are formatted as they are.
+pub(crate) struct DebugAddress(pub(crate) *const c_void);
+
+impl fmt::Debug for DebugAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(location) = locate(self.0) else {
+            return write!(f, "{:?}", self.0);
+        };
+        write!(f, "{location}")?;
+
+        #[cfg(feature = "symbols")]
+        if let Some(symbol) = crate::symbols::symbolize(self.0) {
+            write!(f, " ({symbol})")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the module the address is in, or `None` if the address is not in the image of a loaded module.
+pub fn locate(address: *const c_void) -> Option<ModuleOffset> {
+    let mut module = std::ptr::null_mut();
+    let found = unsafe {
+        GetModuleHandleExW(
+            GET_MODULE_HANDLE_EX_FLAG_FROM_ADDRESS | GET_MODULE_HANDLE_EX_FLAG_UNCHANGED_REFCOUNT,
+            address.cast(),
+            &mut module,
+        )
+    };
+    if found == 0 {
+        return None;
+    }
+
+    let mut path = vec![0u16; MAX_MODULE_PATH];
+    let length = unsafe { GetModuleFileNameW(module, path.as_mut_ptr(), path.len() as u32) };
+    if length == 0 {
+        return None;
+    }
+    let path = PathBuf::from(OsString::from_wide(&path[..length as usize]));
+
+    Some(ModuleOffset {
+        module: path.file_name()?.to_string_lossy().into_owned(),
+        offset: address as usize - module as usize,
+    })
+}
+
+/// Returns the exports of the loaded module.
+///
+/// Returns `MH_ERROR_MODULE_NOT_FOUND` if the module is not loaded.
+///
+/// # Safety
+///
+/// The module must not be unloaded while its exports are read.
+pub unsafe fn exports<M: Into<ModuleName>>(
+    module: M,
+) -> Result<impl Iterator<Item = Export>, MH_STATUS> {
+    let image = unsafe { image(module)? };
+
+    Ok(image
+        .exports()
+        .into_iter()
+        .map(|export| Export {
+            name: export.name.map(|name| name.to_string_lossy().into_owned()),
+            ordinal: export.ordinal,
+            address: image.rva::<c_void>(export.rva) as *mut c_void,
+            forwarder: export
+                .forwarder
+                .map(|forwarder| forwarder.to_string_lossy().into_owned()),
+        })
+        .collect::<Vec<_>>()
+        .into_iter())
+}
+
+/// Returns the address of the export of the loaded module with the name.
+///
+/// Forwarded exports are followed to the module that implements them, like `GetProcAddress` does, so this returns the
+/// address of the implementation that a hook needs to target for the hook to fire.
+///
+/// Returns `MH_ERROR_MODULE_NOT_FOUND` if the module, or a module the export is forwarded to, is not loaded, and
+/// `MH_ERROR_FUNCTION_NOT_FOUND` if the module does not export the name.
+///
+/// # Safety
+///
+/// The modules must not be unloaded while their exports are read.
+pub unsafe fn find_export<M: Into<ModuleName>>(
+    module: M,
+    name: &str,
+) -> Result<*mut c_void, MH_STATUS> {
+    let export = unsafe { exports(module)? }
+        .find(|export| export.name.as_deref() == Some(name))
+        .ok_or(MH_STATUS::MH_ERROR_FUNCTION_NOT_FOUND)?;
+
+    unsafe { resolve_export(export, MAX_FORWARDS) }
+}
+
+/// Returns the address of the export of the loaded module with the ordinal.
+///
+/// Returns `MH_ERROR_MODULE_NOT_FOUND` if the module is not loaded, and `MH_ERROR_FUNCTION_NOT_FOUND` if the module
+/// does not export the ordinal.
+///
+/// # Safety
+///
+/// The module must not be unloaded while its exports are read.
+pub unsafe fn find_export_by_ordinal<M: Into<ModuleName>>(
+    module: M,
+    ordinal: u16,
+) -> Result<*mut c_void, MH_STATUS> {
+    let export = unsafe { exports(module)? }
+        .find(|export| export.ordinal == ordinal)
+        .ok_or(MH_STATUS::MH_ERROR_FUNCTION_NOT_FOUND)?;
+
+    unsafe { resolve_export(export, MAX_FORWARDS) }
+}
+
+// Follows the export to the module that implements it
+unsafe fn resolve_export(export: Export, forwards: usize) -> Result<*mut c_void, MH_STATUS> {
+    let Some(forwarder) = export.forwarder else {
+        return Ok(export.address);
+    };
+    if forwards == 0 {
+        return Err(MH_STATUS::MH_ERROR_FUNCTION_NOT_FOUND);
+    }
+
+    // The forwarder names the module without its extension, such as `NTDLL.RtlAcquireSRWLockExclusive`
+    let (module, name) = forwarder
+        .rsplit_once('.')
+        .ok_or(MH_STATUS::MH_ERROR_FUNCTION_NOT_FOUND)?;
+    let module = format!("{module}.dll");
+
+    let mut exports = unsafe { exports(module)? };
+    let export = match name.strip_prefix('#') {
+        Some(ordinal) => {
+            let ordinal = ordinal
+                .parse::<u16>()
+                .map_err(|_| MH_STATUS::MH_ERROR_FUNCTION_NOT_FOUND)?;
+            exports.find(|export| export.ordinal == ordinal)
+        }
+        None => exports.find(|export| export.name.as_deref() == Some(name)),
+    }
+    .ok_or(MH_STATUS::MH_ERROR_FUNCTION_NOT_FOUND)?;
+
+    unsafe { resolve_export(export, forwards - 1) }
+}
+
+/// Returns the base address of the loaded module.
+///
+/// Returns `MH_ERROR_MODULE_NOT_FOUND` if the module is not loaded. API set names, such as
+/// `api-ms-win-core-processthreads-l1-1-0.dll`, are resolved to their host module.
+pub fn base_address<M: Into<ModuleName>>(module: M) -> Result<*mut c_void, MH_STATUS> {
+    let module = resolve_api_set(module.into());
+    let base = unsafe { GetModuleHandleW(module.as_ptr()) };
+
+    if base.is_null() {
+        Err(MH_STATUS::MH_ERROR_MODULE_NOT_FOUND)
+    } else {
+        Ok(base)
+    }
+}
+
+/// Returns the handle of the loaded module as a `windows` type.
+///
+/// Returns `MH_ERROR_MODULE_NOT_FOUND` if the module is not loaded.
+#[cfg(feature = "windows")]
+pub fn handle<M: Into<ModuleName>>(
+    module: M,
+) -> Result<windows::Win32::Foundation::HMODULE, MH_STATUS> {
+    base_address(module).map(windows::Win32::Foundation::HMODULE)
+}
+
+/// Returns the module hosting an API set, such as `kernelbase.dll` for `api-ms-win-core-processthreads-l1-1-0.dll`.
+///
+/// Returns `None` if the name is not an API set of the process, or if the API set schema of the process is not the
+/// one used since Windows 10.
+pub fn api_set_host<M: Into<ModuleName>>(name: M) -> Option<ModuleName> {
+    let name = String::from_utf16_lossy(name.into().as_wide()).to_lowercase();
+    let name = name.strip_suffix(".dll").unwrap_or(&name);
+    if !name.starts_with("api-") && !name.starts_with("ext-") {
+        return None;
+    }
+
+    // The API set names are matched without the version after their last hyphen
+    let hashed_name = &name[..name.rfind('-')?];
+    let map = api_set_map()?;
+
+    unsafe {
+        let namespace = &*(map as *const ApiSetNamespace);
+        if namespace.version != API_SET_SCHEMA_VERSION_V6 {
+            return None;
+        }
+
+        let entries = std::slice::from_raw_parts(
+            map.add(namespace.entry_offset as usize) as *const ApiSetNamespaceEntry,
+            namespace.count as usize,
+        );
+        let entry = entries.iter().find(|entry| {
+            let entry_name = api_set_string(map, entry.name_offset, entry.hashed_length);
+            String::from_utf16_lossy(entry_name).eq_ignore_ascii_case(hashed_name)
+        })?;
+
+        // The first value is the default host, used by all modules without a host of their own
+        if entry.value_count == 0 {
+            return None;
+        }
+        let value = &*(map.add(entry.value_offset as usize) as *const ApiSetValueEntry);
+        let host = api_set_string(map, value.value_offset, value.value_length);
+
+        (!host.is_empty()).then(|| ModuleName::from_wide(host))
+    }
+}
+
+// Resolves an API set name to its host module, and keeps any other name as is
+pub(crate) fn resolve_api_set(module: ModuleName) -> ModuleName {
+    api_set_host(module.clone()).unwrap_or(module)
+}
+
+/// Returns whether the current thread holds the loader lock, for example while it runs `DllMain`.
+///
+/// Suspending other threads while holding the loader lock may deadlock, if one of them waits for the lock.
+pub fn is_loader_lock_held() -> bool {
+    let Some(peb) = peb() else {
+        return false;
+    };
+
+    let lock = unsafe { *(peb.add(PEB_LOADER_LOCK_OFFSET) as *const *const RtlCriticalSection) };
+    if lock.is_null() {
+        return false;
+    }
+
+    // The owning thread of a critical section is the id of the thread, not a handle
+    let owner = unsafe { std::ptr::read_volatile(&(*lock).owning_thread) } as usize;
+    owner == unsafe { GetCurrentThreadId() } as usize
+}
+
+/// Returns the version of Wine, such as `"9.0"`, if the process runs under Wine or Proton.
+///
+/// Wine is detected by the `wine_get_version` export of its `ntdll.dll`, which Windows doesn't have.
+pub fn wine_version() -> Option<&'static str> {
+    static WINE_VERSION: OnceLock<Option<String>> = OnceLock::new();
+
+    WINE_VERSION
+        .get_or_init(|| unsafe {
+            let ntdll = GetModuleHandleW(ModuleName::from("ntdll.dll").as_ptr());
+            if ntdll.is_null() {
+                return None;
+            }
+
+            let wine_get_version = GetProcAddress(ntdll, c"wine_get_version".as_ptr() as *const _);
+            if wine_get_version.is_null() {
+                return None;
+            }
+
+            let wine_get_version = std::mem::transmute::<
+                *mut c_void,
+                unsafe extern "C" fn() -> *const c_char,
+            >(wine_get_version);
+            let version = wine_get_version();
+            let version = if version.is_null() {
+                String::new()
+            } else {
+                CStr::from_ptr(version).to_string_lossy().into_owned()
+            };
+
+            Some(version)
+        })
+        .as_deref()
+}
+
+/// Returns whether the process runs under Wine or Proton, like [`wine_version`].
+pub fn is_wine() -> bool {
+    wine_version().is_some()
+}
+
+// Returns the API set map from the process environment block
+fn api_set_map() -> Option<*const u8> {
+    let peb = peb()?;
+    let map = unsafe { *(peb.add(PEB_API_SET_MAP_OFFSET) as *const *const u8) };
+
+    (!map.is_null()).then_some(map)
+}
+
+// Returns the process environment block of the process
+fn peb() -> Option<*const u8> {
+    let mut information = MaybeUninit::<ProcessBasicInformation>::zeroed();
+    let status = unsafe {
+        NtQueryInformationProcess(
+            CURRENT_PROCESS,
+            PROCESS_BASIC_INFORMATION_CLASS,
+            information.as_mut_ptr() as *mut c_void,
+            size_of::<ProcessBasicInformation>() as u32,
+            std::ptr::null_mut(),
+        )
+    };
+    if status < 0 {
+        return None;
+    }
+
+    let peb = unsafe { information.assume_init() }.peb_base_address;
+
+    (!peb.is_null()).then_some(peb)
+}
+
+// Returns a UTF-16 string of the API set map, whose length is in bytes
+unsafe fn api_set_string(map: *const u8, offset: u32, length: u32) -> &'static [u16] {
+    unsafe {
+        std::slice::from_raw_parts(map.add(offset as usize) as *const u16, length as usize / 2)
+    }
+}
+
+/// Returns the address of the entry point of the loaded module, such as its `DllMain`.
+///
+/// Returns `MH_ERROR_MODULE_NOT_FOUND` if the module is not loaded, and `MH_ERROR_FUNCTION_NOT_FOUND` if it has no
+/// entry point.
+///
+/// # Safety
+///
+/// The module must not be unloaded while its headers are read.
+pub unsafe fn entry_point<M: Into<ModuleName>>(module: M) -> Result<*mut c_void, MH_STATUS> {
+    let image = unsafe { image(module)? };
+
+    image
+        .entry_point()
+        .map(|entry_point| entry_point as *mut c_void)
+        .ok_or(MH_STATUS::MH_ERROR_FUNCTION_NOT_FOUND)
+}
+
+/// Returns the addresses of the TLS callbacks of the loaded module, which the loader calls before its entry point
+/// whenever a thread starts or exits.
+///
+/// Returns `MH_ERROR_MODULE_NOT_FOUND` if the module is not loaded.
+///
+/// # Safety
+///
+/// The module must not be unloaded while its headers are read.
+pub unsafe fn tls_callbacks<M: Into<ModuleName>>(module: M) -> Result<Vec<*mut c_void>, MH_STATUS> {
+    let image = unsafe { image(module)? };
+
+    Ok(image
+        .tls_callbacks()
+        .map(|callback| callback as *mut c_void)
+        .collect())
+}
+
+/// Returns the address of the relative virtual address in the loaded module.
+///
+/// Returns `MH_ERROR_MODULE_NOT_FOUND` if the module is not loaded, and `MH_ERROR_NOT_EXECUTABLE` if the address is
+/// outside the image of the module.
+///
+/// # Safety
+///
+/// The module must not be unloaded while its headers are read.
+pub unsafe fn resolve_rva<M: Into<ModuleName>>(
+    module: M,
+    rva: usize,
+) -> Result<*mut c_void, MH_STATUS> {
+    let image = unsafe { image(module)? };
+
+    if rva >= image.nt_headers().optional_header.size_of_image as usize {
+        return Err(MH_STATUS::MH_ERROR_NOT_EXECUTABLE);
+    }
+
+    Ok(image.rva::<c_void>(rva as u32) as *mut c_void)
+}
+
+unsafe fn image<M: Into<ModuleName>>(module: M) -> Result<Image, MH_STATUS> {
+    let base = base_address(module)?;
+
+    unsafe { Image::from_base(base as *const u8) }.ok_or(MH_STATUS::MH_ERROR_MODULE_NOT_FOUND)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::win::GetProcAddress;
+
+    #[test]
+    fn test_exports() {
+        unsafe {
+            let exports = exports("kernel32.dll").unwrap().collect::<Vec<_>>();
+            let export = exports
+                .iter()
+                .find(|export| export.name.as_deref() == Some("GetTickCount"))
+                .unwrap();
+
+            // Looking the export up by name or by ordinal resolves the same address.
+            let module = GetModuleHandleW(ModuleName::from("kernel32.dll").as_ptr());
+            assert_eq!(
+                GetProcAddress(module, c"GetTickCount".as_ptr() as *const _),
+                GetProcAddress(module, export.ordinal as usize as *const _)
+            );
+        }
+    }
+
+    #[test]
+    fn test_forwarded_export() {
+        unsafe {
+            let export = exports("kernel32.dll")
+                .unwrap()
+                .find(|export| export.name.as_deref() == Some("AcquireSRWLockExclusive"))
+                .unwrap();
+            assert!(export.forwarder.unwrap().starts_with("NTDLL."));
+
+            // The forward is followed to the implementation, like the loader does.
+            let module = GetModuleHandleW(ModuleName::from("kernel32.dll").as_ptr());
+            assert_eq!(
+                find_export("kernel32.dll", "AcquireSRWLockExclusive"),
+                Ok(GetProcAddress(
+                    module,
+                    c"AcquireSRWLockExclusive".as_ptr() as *const _
+                ))
+            );
+        }
+    }
+
+    #[test]
+    fn test_api_set_host() {
+        let host = api_set_host("api-ms-win-core-processthreads-l1-1-0.dll").unwrap();
+        assert!(
+            String::from_utf16_lossy(host.as_wide()).eq_ignore_ascii_case("kernelbase.dll"),
+            "{host:?}"
+        );
+        assert_eq!(api_set_host("kernel32.dll"), None);
+
+        // API set names can be used where a module is expected.
+        assert_eq!(
+            base_address("api-ms-win-core-processthreads-l1-1-0.dll"),
+            base_address("kernelbase.dll")
+        );
+    }
+
+    #[test]
+    fn test_loaded_modules() {
+        let modules = loaded_modules().unwrap().collect::<Vec<_>>();
+        let kernel32 = modules
+            .iter()
+            .find(|module| module.name.eq_ignore_ascii_case("kernel32.dll"))
+            .unwrap();
+        assert_eq!(Ok(kernel32.base), base_address("kernel32.dll"));
+        assert!(kernel32.size > 0);
+        assert!(kernel32.path.is_absolute());
+
+        // The executable is loaded first.
+        assert_eq!(modules[0].base, unsafe {
+            GetModuleHandleW(std::ptr::null())
+        });
+    }
+
+    #[test]
+    fn test_locate() {
+        let base = base_address("kernel32.dll").unwrap();
+        let location = locate(base.wrapping_add(0x10)).unwrap();
+        assert!(location.module.eq_ignore_ascii_case("kernel32.dll"));
+        assert_eq!(location.offset, 0x10);
+        assert_eq!(location.to_string(), format!("{}+0x10", location.module));
+
+        assert_eq!(locate(std::ptr::null()), None);
+    }
+
+    #[test]
+    fn test_is_loader_lock_held() {
+        assert!(!is_loader_lock_held());
+    }
+
+    #[test]
+    fn test_is_wine() {
+        assert_eq!(is_wine(), wine_version().is_some());
+        assert_eq!(wine_version(), wine_version());
+    }
+
+    #[test]
+    fn test_entry_point() {
+        let executable = std::env::current_exe().unwrap();
+        let executable = executable.file_name().unwrap().to_str().unwrap();
+
+        unsafe {
+            let address = entry_point(executable).unwrap();
+            assert_eq!(
+                locate(address).unwrap().module.to_lowercase(),
+                executable.to_lowercase()
+            );
+
+            // The callbacks are in the module itself.
+            for callback in tls_callbacks(executable).unwrap() {
+                assert!(locate(callback).is_some());
+            }
+
+            assert_eq!(
+                entry_point("not_loaded.dll"),
+                Err(MH_STATUS::MH_ERROR_MODULE_NOT_FOUND)
+            );
+        }
+    }
+
+    #[test]
+    fn test_resolve_rva() {
+        let base = base_address("kernel32.dll").unwrap();
+        assert_eq!(
+            unsafe { resolve_rva("kernel32.dll", 0x10) },
+            Ok(unsafe { base.add(0x10) })
+        );
+        assert_eq!(
+            unsafe { resolve_rva("kernel32.dll", usize::MAX) },
+            Err(MH_STATUS::MH_ERROR_NOT_EXECUTABLE)
+        );
+    }
+
+    #[test]
+    fn test_exports_module_not_found() {
+        let status = unsafe { exports("not_loaded.dll") }.err();
+        assert_eq!(status, Some(MH_STATUS::MH_ERROR_MODULE_NOT_FOUND));
+    }
+}
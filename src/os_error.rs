@@ -0,0 +1,52 @@
+use crate::{MH_STATUS, win::GetLastError};
+use std::{cell::Cell, io};
+
+thread_local! {
+    // The last-error code of the last call into MinHook on this thread that failed to allocate or protect memory
+    static LAST_OS_ERROR: Cell<Option<u32>> = const { Cell::new(None) };
+}
+
+// Records the last-error code if MinHook failed to allocate or protect memory. This must be called right after the
+// call into MinHook, before anything else can overwrite the last-error code.
+pub(crate) fn capture(status: MH_STATUS) {
+    if matches!(
+        status,
+        MH_STATUS::MH_ERROR_MEMORY_ALLOC | MH_STATUS::MH_ERROR_MEMORY_PROTECT
+    ) {
+        let code = unsafe { GetLastError() };
+        let _ = LAST_OS_ERROR.try_with(|error| error.set(Some(code)));
+    }
+}
+
+/// Returns the OS error behind the last `MH_ERROR_MEMORY_ALLOC` or `MH_ERROR_MEMORY_PROTECT` MinHook returned on the
+/// current thread, such as the error of the failed `VirtualProtect`.
+///
+/// The error is captured as soon as MinHook returns, and stays until the next memory error on the thread. Returns
+/// `None` if MinHook never failed to allocate or protect memory on the thread.
+pub fn last_os_error() -> Option<io::Error> {
+    LAST_OS_ERROR
+        .try_with(Cell::get)
+        .ok()
+        .flatten()
+        .map(|code| io::Error::from_raw_os_error(code as i32))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_last_os_error() {
+        // Other statuses leave the last-error code alone.
+        capture(MH_STATUS::MH_ERROR_NOT_CREATED);
+        assert!(last_os_error().is_none());
+
+        capture(MH_STATUS::MH_ERROR_MEMORY_PROTECT);
+        assert!(last_os_error().is_some());
+
+        // Each thread has its own error.
+        std::thread::spawn(|| assert!(last_os_error().is_none()))
+            .join()
+            .unwrap();
+    }
+}
@@ -0,0 +1,377 @@
+use std::ffi::{CStr, c_char, c_void};
+
+pub const IMAGE_DIRECTORY_ENTRY_EXPORT: usize = 0;
+pub const IMAGE_DIRECTORY_ENTRY_IMPORT: usize = 1;
+pub const IMAGE_DIRECTORY_ENTRY_TLS: usize = 9;
+pub const IMAGE_DIRECTORY_ENTRY_DELAY_IMPORT: usize = 13;
+pub const IMAGE_SCN_MEM_EXECUTE: u32 = 0x2000_0000;
+
+#[cfg(target_pointer_width = "64")]
+pub const IMAGE_ORDINAL_FLAG: usize = 0x8000_0000_0000_0000;
+#[cfg(target_pointer_width = "32")]
+pub const IMAGE_ORDINAL_FLAG: usize = 0x8000_0000;
+
+const IMAGE_DOS_SIGNATURE: u16 = 0x5A4D;
+const IMAGE_NT_SIGNATURE: u32 = 0x0000_4550;
+
+// Set for delay-load descriptors holding relative virtual addresses, which all current linkers emit
+const DLATTR_RVA: u32 = 0x1;
+
+#[repr(C)]
+struct ImageDosHeader {
+    e_magic: u16,
+    e_res: [u16; 29],
+    e_lfanew: i32,
+}
+
+#[repr(C)]
+pub struct ImageFileHeader {
+    pub machine: u16,
+    pub number_of_sections: u16,
+    pub time_date_stamp: u32,
+    pub pointer_to_symbol_table: u32,
+    pub number_of_symbols: u32,
+    pub size_of_optional_header: u16,
+    pub characteristics: u16,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct ImageDataDirectory {
+    pub virtual_address: u32,
+    pub size: u32,
+}
+
+#[cfg(target_pointer_width = "64")]
+#[repr(C)]
+pub struct ImageOptionalHeader {
+    pub magic: u16,
+    pub major_linker_version: u8,
+    pub minor_linker_version: u8,
+    pub size_of_code: u32,
+    pub size_of_initialized_data: u32,
+    pub size_of_uninitialized_data: u32,
+    pub address_of_entry_point: u32,
+    pub base_of_code: u32,
+    pub image_base: u64,
+    pub section_alignment: u32,
+    pub file_alignment: u32,
+    pub major_operating_system_version: u16,
+    pub minor_operating_system_version: u16,
+    pub major_image_version: u16,
+    pub minor_image_version: u16,
+    pub major_subsystem_version: u16,
+    pub minor_subsystem_version: u16,
+    pub win32_version_value: u32,
+    pub size_of_image: u32,
+    pub size_of_headers: u32,
+    pub check_sum: u32,
+    pub subsystem: u16,
+    pub dll_characteristics: u16,
+    pub size_of_stack_reserve: u64,
+    pub size_of_stack_commit: u64,
+    pub size_of_heap_reserve: u64,
+    pub size_of_heap_commit: u64,
+    pub loader_flags: u32,
+    pub number_of_rva_and_sizes: u32,
+    pub data_directory: [ImageDataDirectory; 16],
+}
+
+#[cfg(target_pointer_width = "32")]
+#[repr(C)]
+pub struct ImageOptionalHeader {
+    pub magic: u16,
+    pub major_linker_version: u8,
+    pub minor_linker_version: u8,
+    pub size_of_code: u32,
+    pub size_of_initialized_data: u32,
+    pub size_of_uninitialized_data: u32,
+    pub address_of_entry_point: u32,
+    pub base_of_code: u32,
+    pub base_of_data: u32,
+    pub image_base: u32,
+    pub section_alignment: u32,
+    pub file_alignment: u32,
+    pub major_operating_system_version: u16,
+    pub minor_operating_system_version: u16,
+    pub major_image_version: u16,
+    pub minor_image_version: u16,
+    pub major_subsystem_version: u16,
+    pub minor_subsystem_version: u16,
+    pub win32_version_value: u32,
+    pub size_of_image: u32,
+    pub size_of_headers: u32,
+    pub check_sum: u32,
+    pub subsystem: u16,
+    pub dll_characteristics: u16,
+    pub size_of_stack_reserve: u32,
+    pub size_of_stack_commit: u32,
+    pub size_of_heap_reserve: u32,
+    pub size_of_heap_commit: u32,
+    pub loader_flags: u32,
+    pub number_of_rva_and_sizes: u32,
+    pub data_directory: [ImageDataDirectory; 16],
+}
+
+#[repr(C)]
+pub struct ImageNtHeaders {
+    pub signature: u32,
+    pub file_header: ImageFileHeader,
+    pub optional_header: ImageOptionalHeader,
+}
+
+#[repr(C)]
+pub struct ImageSectionHeader {
+    pub name: [u8; 8],
+    pub virtual_size: u32,
+    pub virtual_address: u32,
+    pub size_of_raw_data: u32,
+    pub pointer_to_raw_data: u32,
+    pub pointer_to_relocations: u32,
+    pub pointer_to_linenumbers: u32,
+    pub number_of_relocations: u16,
+    pub number_of_linenumbers: u16,
+    pub characteristics: u32,
+}
+
+#[repr(C)]
+pub struct ImageExportDirectory {
+    pub characteristics: u32,
+    pub time_date_stamp: u32,
+    pub major_version: u16,
+    pub minor_version: u16,
+    pub name: u32,
+    pub base: u32,
+    pub number_of_functions: u32,
+    pub number_of_names: u32,
+    pub address_of_functions: u32,
+    pub address_of_names: u32,
+    pub address_of_name_ordinals: u32,
+}
+
+/// An entry in the export table of an image.
+pub struct ImageExport<'a> {
+    pub name: Option<&'a CStr>,
+    pub ordinal: u16,
+    pub rva: u32,
+    pub forwarder: Option<&'a CStr>,
+}
+
+#[repr(C)]
+pub struct ImageImportDescriptor {
+    pub original_first_thunk: u32,
+    pub time_date_stamp: u32,
+    pub forwarder_chain: u32,
+    pub name: u32,
+    pub first_thunk: u32,
+}
+
+#[repr(C)]
+pub struct ImageDelayloadDescriptor {
+    pub attributes: u32,
+    pub dll_name_rva: u32,
+    pub module_handle_rva: u32,
+    pub import_address_table_rva: u32,
+    pub import_name_table_rva: u32,
+    pub bound_import_address_table_rva: u32,
+    pub unload_information_table_rva: u32,
+    pub time_date_stamp: u32,
+}
+
+#[repr(C)]
+pub struct ImageTlsDirectory {
+    pub start_address_of_raw_data: usize,
+    pub end_address_of_raw_data: usize,
+    pub address_of_index: usize,
+    pub address_of_callbacks: usize,
+    pub size_of_zero_fill: u32,
+    pub characteristics: u32,
+}
+
+/// A module mapped into the address space of the process.
+#[derive(Clone, Copy)]
+pub struct Image {
+    base: *const u8,
+}
+
+impl Image {
+    /// Creates an image from the base address of a loaded module.
+    ///
+    /// # Safety
+    ///
+    /// The base address must be the start of a module which is loaded for as long as the image is used.
+    pub unsafe fn from_base(base: *const u8) -> Option<Self> {
+        if base.is_null() {
+            return None;
+        }
+
+        let image = Self { base };
+        unsafe {
+            let dos_header = &*(base as *const ImageDosHeader);
+            if dos_header.e_magic != IMAGE_DOS_SIGNATURE {
+                return None;
+            }
+            if image.nt_headers().signature != IMAGE_NT_SIGNATURE {
+                return None;
+            }
+        }
+
+        Some(image)
+    }
+
+    pub fn nt_headers(&self) -> &ImageNtHeaders {
+        unsafe {
+            let dos_header = &*(self.base as *const ImageDosHeader);
+            &*(self.base.offset(dos_header.e_lfanew as isize) as *const ImageNtHeaders)
+        }
+    }
+
+    /// Returns the data directory with the index, if it's present.
+    pub fn data_directory(&self, index: usize) -> Option<ImageDataDirectory> {
+        let optional_header = &self.nt_headers().optional_header;
+        if index >= optional_header.number_of_rva_and_sizes as usize {
+            return None;
+        }
+
+        let directory = optional_header.data_directory[index];
+        (directory.virtual_address != 0).then_some(directory)
+    }
+
+    /// Returns a pointer to the relative virtual address.
+    pub fn rva<T>(&self, rva: u32) -> *const T {
+        unsafe { self.base.add(rva as usize) as *const T }
+    }
+
+    /// Returns whether the address lies within the image.
+    pub fn contains(&self, address: *const c_void) -> bool {
+        let size = self.nt_headers().optional_header.size_of_image as usize;
+
+        (self.base as usize..self.base as usize + size).contains(&(address as usize))
+    }
+
+    /// Returns the null-terminated string at the relative virtual address.
+    pub fn c_str(&self, rva: u32) -> &CStr {
+        unsafe { CStr::from_ptr(self.rva::<c_char>(rva)) }
+    }
+
+    /// Returns the section headers of the image.
+    pub fn sections(&self) -> &[ImageSectionHeader] {
+        let nt_headers = self.nt_headers();
+        let file_header = &nt_headers.file_header;
+
+        unsafe {
+            let optional_header = &nt_headers.optional_header as *const _ as *const u8;
+            std::slice::from_raw_parts(
+                optional_header.add(file_header.size_of_optional_header as usize)
+                    as *const ImageSectionHeader,
+                file_header.number_of_sections as usize,
+            )
+        }
+    }
+
+    /// Returns the bytes of the section, as mapped into memory.
+    pub fn section_bytes(&self, section: &ImageSectionHeader) -> &[u8] {
+        // The virtual size is only missing from object files
+        let len = match section.virtual_size {
+            0 => section.size_of_raw_data,
+            virtual_size => virtual_size,
+        };
+        unsafe { std::slice::from_raw_parts(self.rva(section.virtual_address), len as usize) }
+    }
+
+    /// Returns the entries of the export table of the image.
+    pub fn exports(&self) -> Vec<ImageExport<'_>> {
+        let Some(directory) = self.data_directory(IMAGE_DIRECTORY_ENTRY_EXPORT) else {
+            return Vec::new();
+        };
+
+        // Exports whose address is inside the export directory are forwarded, and point to the forwarder string
+        let forwarders = directory.virtual_address..directory.virtual_address + directory.size;
+
+        let directory = unsafe { &*self.rva::<ImageExportDirectory>(directory.virtual_address) };
+        let functions = unsafe {
+            std::slice::from_raw_parts(
+                self.rva::<u32>(directory.address_of_functions),
+                directory.number_of_functions as usize,
+            )
+        };
+        let names = unsafe {
+            std::slice::from_raw_parts(
+                self.rva::<u32>(directory.address_of_names),
+                directory.number_of_names as usize,
+            )
+        };
+        let name_ordinals = unsafe {
+            std::slice::from_raw_parts(
+                self.rva::<u16>(directory.address_of_name_ordinals),
+                directory.number_of_names as usize,
+            )
+        };
+
+        let mut function_names = vec![None; functions.len()];
+        for (&name, &index) in names.iter().zip(name_ordinals) {
+            if let Some(function_name) = function_names.get_mut(index as usize) {
+                *function_name = Some(self.c_str(name));
+            }
+        }
+
+        functions
+            .iter()
+            .zip(function_names)
+            .enumerate()
+            .filter(|&(_, (&rva, _))| rva != 0)
+            .map(|(index, (&rva, name))| ImageExport {
+                name,
+                ordinal: (directory.base as usize + index) as u16,
+                rva,
+                forwarder: forwarders.contains(&rva).then(|| self.c_str(rva)),
+            })
+            .collect()
+    }
+
+    /// Returns the import descriptors of the image.
+    pub fn import_descriptors(&self) -> impl Iterator<Item = &ImageImportDescriptor> {
+        let first = self
+            .data_directory(IMAGE_DIRECTORY_ENTRY_IMPORT)
+            .map(|directory| self.rva::<ImageImportDescriptor>(directory.virtual_address));
+
+        (0..)
+            .map_while(move |index| first.map(|first| unsafe { &*first.add(index) }))
+            .take_while(|descriptor| descriptor.name != 0)
+    }
+
+    /// Returns the delay-load import descriptors of the image. Descriptors in the old format holding virtual addresses
+    /// are skipped.
+    pub fn delay_import_descriptors(&self) -> impl Iterator<Item = &ImageDelayloadDescriptor> {
+        let first = self
+            .data_directory(IMAGE_DIRECTORY_ENTRY_DELAY_IMPORT)
+            .map(|directory| self.rva::<ImageDelayloadDescriptor>(directory.virtual_address));
+
+        (0..)
+            .map_while(move |index| first.map(|first| unsafe { &*first.add(index) }))
+            .take_while(|descriptor| descriptor.dll_name_rva != 0)
+            .filter(|descriptor| descriptor.attributes & DLATTR_RVA != 0)
+    }
+
+    /// Returns the address of the entry point of the image, if it has one.
+    pub fn entry_point(&self) -> Option<*const u8> {
+        match self.nt_headers().optional_header.address_of_entry_point {
+            0 => None,
+            rva => Some(self.rva(rva)),
+        }
+    }
+
+    /// Returns the addresses of the TLS callbacks of the image.
+    pub fn tls_callbacks(&self) -> impl Iterator<Item = *const u8> {
+        // The TLS directory holds virtual addresses, which the loader relocated like the callbacks themselves
+        let first = self
+            .data_directory(IMAGE_DIRECTORY_ENTRY_TLS)
+            .map(|directory| unsafe { &*self.rva::<ImageTlsDirectory>(directory.virtual_address) })
+            .map(|directory| directory.address_of_callbacks as *const *const u8)
+            .filter(|callbacks| !callbacks.is_null());
+
+        (0..)
+            .map_while(move |index| first.map(|first| unsafe { *first.add(index) }))
+            .take_while(|callback| !callback.is_null())
+    }
+}
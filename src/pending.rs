@@ -0,0 +1,188 @@
+use crate::{
+    MH_STATUS, MhHook, ModuleName,
+    logging::debug,
+    win::{
+        GetModuleHandleW, GetProcAddress, LDR_DLL_NOTIFICATION_REASON_LOADED,
+        LdrDllNotificationData, LdrRegisterDllNotification, LdrUnregisterDllNotification,
+    },
+};
+use std::{
+    ffi::{CString, c_void},
+    ptr::null_mut,
+    sync::OnceLock,
+};
+
+/// A hook for an exported function of a module which may not be loaded yet.
+///
+/// If the module is already loaded, the hook is created and enabled right away. Otherwise the hook is created and
+/// enabled as soon as the loader maps the module, before the entry point of the module runs. This happens while the
+/// loader lock is held by the thread that loads the module.
+///
+/// Dropping the pending hook stops waiting for the module, but leaves a created hook as is.
+#[derive(Debug)]
+pub struct PendingHook {
+    inner: Box<Inner>,
+    cookie: *mut c_void,
+}
+
+#[derive(Debug)]
+struct Inner {
+    module_name: String,
+    proc_name: CString,
+    detour: *mut c_void,
+    hook: OnceLock<MhHook>,
+    status: OnceLock<Result<(), MH_STATUS>>,
+}
+
+// The notification only reads the immutable parts, and the hook is only set once.
+unsafe impl Send for PendingHook {}
+unsafe impl Sync for PendingHook {}
+
+impl PendingHook {
+    /// Creates a hook for the function `proc_name` of the module with the file name `module_name`, like `d3d11.dll`,
+    /// once the module is loaded.
+    ///
+    /// Returns `MH_ERROR_FUNCTION_NOT_FOUND` if the function name contains a null, and `MH_UNKNOWN` if the loader
+    /// notification couldn't be registered.
+    ///
+    /// # Safety
+    pub unsafe fn new(
+        module_name: &str,
+        proc_name: &str,
+        detour: *mut c_void,
+    ) -> Result<Self, MH_STATUS> {
+        let proc_name =
+            CString::new(proc_name).map_err(|_| MH_STATUS::MH_ERROR_FUNCTION_NOT_FOUND)?;
+        let inner = Box::new(Inner {
+            module_name: module_name.to_lowercase(),
+            proc_name,
+            detour,
+            hook: OnceLock::new(),
+            status: OnceLock::new(),
+        });
+
+        let mut cookie = null_mut();
+        let status = unsafe {
+            LdrRegisterDllNotification(
+                0,
+                notification,
+                &*inner as *const Inner as *mut c_void,
+                &mut cookie,
+            )
+        };
+        debug!("LdrRegisterDllNotification: {:#x}", status);
+        if status < 0 {
+            return Err(MH_STATUS::MH_UNKNOWN);
+        }
+
+        // The module may have been loaded before the notification was registered
+        let module = unsafe { GetModuleHandleW(ModuleName::from(module_name).as_ptr()) };
+        if !module.is_null() {
+            unsafe { inner.apply(module) };
+        }
+
+        Ok(Self { inner, cookie })
+    }
+
+    /// Returns the result of creating and enabling the hook, or `None` if the module wasn't loaded yet.
+    pub fn status(&self) -> Option<Result<(), MH_STATUS>> {
+        self.inner.status.get().copied()
+    }
+
+    /// Returns the hook, if it was created.
+    pub fn hook(&self) -> Option<&MhHook> {
+        self.inner.hook.get()
+    }
+
+    /// Returns the trampoline of the hook, if it was created.
+    pub fn trampoline(&self) -> Option<*mut c_void> {
+        self.hook().map(MhHook::trampoline)
+    }
+}
+
+impl Drop for PendingHook {
+    fn drop(&mut self) {
+        let status = unsafe { LdrUnregisterDllNotification(self.cookie) };
+        debug!("LdrUnregisterDllNotification: {:#x}", status);
+    }
+}
+
+impl Inner {
+    unsafe fn apply(&self, module: *mut c_void) {
+        self.status.get_or_init(|| {
+            let target = unsafe { GetProcAddress(module, self.proc_name.as_ptr() as *const _) };
+            if target.is_null() {
+                return Err(MH_STATUS::MH_ERROR_FUNCTION_NOT_FOUND);
+            }
+
+            // The hook is stored before it's enabled, so the detour can always reach the trampoline
+            let hook = unsafe { MhHook::new(target, self.detour)? };
+            let hook = self.hook.get_or_init(|| hook);
+            unsafe { hook.enable() }
+        });
+    }
+}
+
+unsafe extern "system" fn notification(
+    reason: u32,
+    data: *const LdrDllNotificationData,
+    context: *mut c_void,
+) {
+    if reason != LDR_DLL_NOTIFICATION_REASON_LOADED {
+        return;
+    }
+
+    unsafe {
+        let inner = &*(context as *const Inner);
+        let data = &*data;
+
+        let name = String::from_utf16_lossy((*data.base_dll_name).as_wide());
+        if name.to_lowercase() == inner.module_name {
+            inner.apply(data.dll_base);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::HookTest;
+
+    unsafe extern "system" {
+        fn LoadLibraryW(lpLibFileName: *const u16) -> *mut c_void;
+    }
+
+    #[test]
+    fn test_pending_hook() {
+        let _test = HookTest::start();
+
+        unsafe {
+            let pending = PendingHook::new(
+                "winmm.dll",
+                "timeGetTime",
+                time_get_time_hook as FnType as *mut c_void,
+            )
+            .unwrap();
+
+            // Loading the module creates and enables the hook.
+            let module = LoadLibraryW(ModuleName::from("winmm.dll").as_ptr());
+            assert!(!module.is_null());
+            assert_eq!(pending.status(), Some(Ok(())));
+            assert!(pending.trampoline().is_some());
+
+            let time_get_time = std::mem::transmute::<*mut c_void, FnType>(GetProcAddress(
+                module,
+                c"timeGetTime".as_ptr() as *const _,
+            ));
+            assert_eq!(time_get_time(), 42);
+
+            pending.hook().unwrap().disable().unwrap();
+        }
+
+        type FnType = unsafe extern "system" fn() -> u32;
+
+        unsafe extern "system" fn time_get_time_hook() -> u32 {
+            42
+        }
+    }
+}
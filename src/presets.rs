@@ -0,0 +1,327 @@
+//! Ready-made hooks for the functions of common subsystems, which pass each of their calls to a single callback.
+//!
+//! Each preset module declares a [`DeferredHook`](crate::DeferredHook) per function, with the module, the name and the
+//! signature of the function, and enables or disables all of them at once. The generated detours call the original
+//! function, and then pass its name, arguments and return value to the callback set with [`set_callback`]. Calls made
+//! while the callback runs go straight to the original function.
+//!
+//! The functions are hooked in `kernelbase.dll`, where they are implemented, so calls from the other system DLLs are
+//! seen as well, except for the socket functions in `ws2_32.dll`, which must be loaded before they are enabled.
+//!
+//! # Example
+//!
+//! ```rust
+//! use minhook::{presets, MH_STATUS};
+//!
+//! fn main() -> Result<(), MH_STATUS> {
+//!     presets::set_callback(Some(|call| println!("{call}")));
+//!     unsafe { presets::file_io::enable_all()? };
+//!
+//!     // Prints "DeleteFileW(file_name: 0x...) returned 0"
+//!     let _ = std::fs::remove_file("does_not_exist.txt");
+//!
+//!     unsafe { presets::file_io::disable_all()? };
+//!     Ok(())
+//! }
+//! ```
+
+use std::{
+    fmt,
+    sync::atomic::{AtomicPtr, Ordering},
+};
+
+/// A call of a preset function, passed to the callback once the original function returned.
+#[derive(Debug)]
+pub struct PresetCall<'a> {
+    /// The name of the function.
+    pub function: &'a str,
+    /// The names and values of the arguments. Pointers are shown as addresses, and what they point to can be read
+    /// while the callback runs.
+    pub args: &'a [(&'a str, &'a dyn fmt::Debug)],
+    /// The value returned by the original function.
+    pub ret: &'a dyn fmt::Debug,
+}
+
+impl fmt::Display for PresetCall<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}(", self.function)?;
+        for (index, (name, value)) in self.args.iter().enumerate() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{name}: {value:?}")?;
+        }
+        write!(f, ") returned {:?}", self.ret)
+    }
+}
+
+/// A function that receives the calls of all enabled presets, set through [`set_callback`].
+pub type PresetCallback = fn(&PresetCall<'_>);
+
+// The callback set through `set_callback`, or null if none is set
+static CALLBACK: AtomicPtr<()> = AtomicPtr::new(std::ptr::null_mut());
+
+/// Sets the function that receives the calls of all enabled presets. Without a callback, the hooks only call the
+/// original functions.
+pub fn set_callback(callback: Option<PresetCallback>) {
+    let callback = callback.map_or(std::ptr::null_mut(), |callback| callback as *mut ());
+    CALLBACK.store(callback, Ordering::Release);
+}
+
+// The callback set through `set_callback`
+pub(crate) fn callback() -> Option<PresetCallback> {
+    let callback = CALLBACK.load(Ordering::Acquire);
+
+    // Only `PresetCallback` function pointers are stored
+    (!callback.is_null())
+        .then(|| unsafe { std::mem::transmute::<*mut (), PresetCallback>(callback) })
+}
+
+// Declares the hooks of a preset, and the functions that enable and disable all of them
+macro_rules! preset {
+    ($module:literal; $($(#[$attr:meta])* static $name:ident: fn $fn_name:ident($($arg:ident: $ty:ty),* $(,)?) -> $ret:ty;)*) => {
+        use crate::{DeferredHook, MH_STATUS};
+        use std::ffi::c_void;
+
+        $(
+            $(#[$attr])*
+            pub static $name: DeferredHook<unsafe extern "system" fn($($ty),*) -> $ret> = DeferredHook::new(
+                {
+                    fn resolve() -> Result<*mut c_void, MH_STATUS> {
+                        crate::deferred_hook!(@resolve api($module, stringify!($fn_name)))
+                    }
+                    resolve
+                },
+                {
+                    #[allow(clippy::too_many_arguments)]
+                    unsafe extern "system" fn detour($($arg: $ty),*) -> $ret {
+                        let original = $name.original().expect("The detour only runs once the hook is created");
+                        crate::reentrancy_guard!(unsafe { original($($arg),*) });
+
+                        let ret = unsafe { original($($arg),*) };
+                        // The caller may check the last error of the original, which the callback could overwrite
+                        let last_error = unsafe { crate::win::GetLastError() };
+                        if let Some(callback) = crate::presets::callback() {
+                            callback(&crate::presets::PresetCall {
+                                function: stringify!($fn_name),
+                                args: &[$((stringify!($arg), &$arg as &dyn std::fmt::Debug)),*],
+                                ret: &ret,
+                            });
+                        }
+                        unsafe { crate::win::SetLastError(last_error) };
+
+                        ret
+                    }
+
+                    detour
+                },
+            );
+        )*
+
+        /// Enables the hooks of all functions in the preset, and returns the first error if some of them can't be
+        /// enabled. Hooks that are already enabled are left as they are.
+        ///
+        /// # Safety
+        pub unsafe fn enable_all() -> Result<(), MH_STATUS> {
+            let mut result = Ok(());
+            $(
+                match unsafe { $name.enable() } {
+                    Ok(()) | Err(MH_STATUS::MH_ERROR_ENABLED) => (),
+                    Err(status) => result = result.and(Err(status)),
+                }
+            )*
+
+            result
+        }
+
+        /// Disables the hooks of all functions in the preset, and returns the first error if some of them can't be
+        /// disabled. Hooks that were never enabled are skipped.
+        ///
+        /// # Safety
+        pub unsafe fn disable_all() -> Result<(), MH_STATUS> {
+            let mut result = Ok(());
+            $(
+                match unsafe { $name.disable() } {
+                    Ok(()) | Err(MH_STATUS::MH_ERROR_DISABLED | MH_STATUS::MH_ERROR_NOT_CREATED) => (),
+                    Err(status) => result = result.and(Err(status)),
+                }
+            )*
+
+            result
+        }
+    };
+}
+
+/// Opening, reading, writing, moving and deleting files.
+pub mod file_io {
+    preset! {
+        "kernelbase.dll";
+        /// The hook of `CreateFileW`, which returns the file handle.
+        static CREATE_FILE_W: fn CreateFileW(
+            file_name: *const u16,
+            desired_access: u32,
+            share_mode: u32,
+            security_attributes: *mut c_void,
+            creation_disposition: u32,
+            flags_and_attributes: u32,
+            template_file: *mut c_void,
+        ) -> *mut c_void;
+        /// The hook of `ReadFile`.
+        static READ_FILE: fn ReadFile(
+            file: *mut c_void,
+            buffer: *mut c_void,
+            bytes_to_read: u32,
+            bytes_read: *mut u32,
+            overlapped: *mut c_void,
+        ) -> i32;
+        /// The hook of `WriteFile`.
+        static WRITE_FILE: fn WriteFile(
+            file: *mut c_void,
+            buffer: *const c_void,
+            bytes_to_write: u32,
+            bytes_written: *mut u32,
+            overlapped: *mut c_void,
+        ) -> i32;
+        /// The hook of `MoveFileExW`.
+        static MOVE_FILE_EX_W: fn MoveFileExW(
+            existing_file_name: *const u16,
+            new_file_name: *const u16,
+            flags: u32,
+        ) -> i32;
+        /// The hook of `DeleteFileW`.
+        static DELETE_FILE_W: fn DeleteFileW(file_name: *const u16) -> i32;
+    }
+}
+
+/// Opening, reading and writing registry keys.
+pub mod registry {
+    preset! {
+        "kernelbase.dll";
+        /// The hook of `RegOpenKeyExW`, which returns a Win32 error code.
+        static REG_OPEN_KEY_EX_W: fn RegOpenKeyExW(
+            key: *mut c_void,
+            sub_key: *const u16,
+            options: u32,
+            sam_desired: u32,
+            result: *mut *mut c_void,
+        ) -> i32;
+        /// The hook of `RegQueryValueExW`, which returns a Win32 error code.
+        static REG_QUERY_VALUE_EX_W: fn RegQueryValueExW(
+            key: *mut c_void,
+            value_name: *const u16,
+            reserved: *mut u32,
+            value_type: *mut u32,
+            data: *mut u8,
+            data_len: *mut u32,
+        ) -> i32;
+        /// The hook of `RegSetValueExW`, which returns a Win32 error code.
+        static REG_SET_VALUE_EX_W: fn RegSetValueExW(
+            key: *mut c_void,
+            value_name: *const u16,
+            reserved: u32,
+            value_type: u32,
+            data: *const u8,
+            data_len: u32,
+        ) -> i32;
+        /// The hook of `RegCloseKey`, which returns a Win32 error code.
+        static REG_CLOSE_KEY: fn RegCloseKey(key: *mut c_void) -> i32;
+    }
+}
+
+/// Creating, opening and terminating processes.
+pub mod process {
+    preset! {
+        "kernelbase.dll";
+        /// The hook of `CreateProcessW`.
+        static CREATE_PROCESS_W: fn CreateProcessW(
+            application_name: *const u16,
+            command_line: *mut u16,
+            process_attributes: *mut c_void,
+            thread_attributes: *mut c_void,
+            inherit_handles: i32,
+            creation_flags: u32,
+            environment: *mut c_void,
+            current_directory: *const u16,
+            startup_info: *mut c_void,
+            process_information: *mut c_void,
+        ) -> i32;
+        /// The hook of `OpenProcess`, which returns the process handle.
+        static OPEN_PROCESS: fn OpenProcess(
+            desired_access: u32,
+            inherit_handle: i32,
+            process_id: u32,
+        ) -> *mut c_void;
+        /// The hook of `TerminateProcess`.
+        static TERMINATE_PROCESS: fn TerminateProcess(process: *mut c_void, exit_code: u32) -> i32;
+    }
+}
+
+/// Connecting, sending and receiving through Winsock sockets, which are passed as `SOCKET` handles.
+pub mod socket {
+    preset! {
+        "ws2_32.dll";
+        /// The hook of `connect`.
+        static CONNECT: fn connect(socket: usize, name: *const c_void, name_len: i32) -> i32;
+        /// The hook of `send`, which returns the number of bytes sent.
+        static SEND: fn send(socket: usize, buffer: *const u8, len: i32, flags: i32) -> i32;
+        /// The hook of `recv`, which returns the number of bytes received.
+        static RECV: fn recv(socket: usize, buffer: *mut u8, len: i32, flags: i32) -> i32;
+        /// The hook of `closesocket`.
+        static CLOSESOCKET: fn closesocket(socket: usize) -> i32;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::HookTest;
+    use std::{ffi::c_void, ptr::null_mut, sync::Mutex};
+
+    #[link(name = "advapi32")]
+    unsafe extern "system" {
+        fn RegOpenKeyExW(
+            key: *mut c_void,
+            sub_key: *const u16,
+            options: u32,
+            sam_desired: u32,
+            result: *mut *mut c_void,
+        ) -> i32;
+    }
+
+    #[test]
+    fn test_registry_preset() {
+        let _test = HookTest::start();
+
+        static CALLS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+        set_callback(Some(|call| {
+            if call.function == "RegOpenKeyExW" {
+                CALLS.lock().unwrap().push(call.to_string());
+            }
+        }));
+
+        unsafe {
+            registry::enable_all().unwrap();
+
+            // HKEY_CURRENT_USER, and a key that doesn't exist.
+            let sub_key = "minhook_preset_test\0".encode_utf16().collect::<Vec<_>>();
+            let mut key = null_mut();
+            let status = RegOpenKeyExW(
+                0x8000_0001_usize as *mut c_void,
+                sub_key.as_ptr(),
+                0,
+                0x2_0019,
+                &mut key,
+            );
+            assert_ne!(status, 0);
+
+            registry::disable_all().unwrap();
+        }
+        set_callback(None);
+
+        let calls = CALLS.lock().unwrap();
+        assert_eq!(calls.len(), 1);
+        assert!(calls[0].starts_with("RegOpenKeyExW(key: 0x80000001, "));
+        assert!(calls[0].ends_with("returned 2"));
+    }
+}
@@ -0,0 +1,129 @@
+use std::{cell::Cell, thread::LocalKey};
+
+/// Detects a detour being re-entered on the same thread, for example when a detour of `HeapAlloc` logs and the
+/// logging allocates.
+///
+/// Each detour uses its own thread-local flag, which is usually declared by [`reentrancy_guard!`](crate::reentrancy_guard).
+/// The flag is set while a guard is alive, and cleared again when it is dropped.
+#[derive(Debug)]
+#[must_use = "the detour is no longer guarded once the guard is dropped"]
+pub struct ReentrancyGuard {
+    entered: &'static LocalKey<Cell<bool>>,
+}
+
+impl ReentrancyGuard {
+    /// Enters the detour on the current thread. This returns `None` if the detour was already entered, or if the
+    /// thread-local flag was already destroyed because the thread is exiting.
+    pub fn enter(entered: &'static LocalKey<Cell<bool>>) -> Option<Self> {
+        let already_entered = entered
+            .try_with(|entered| entered.replace(true))
+            .unwrap_or(true);
+        if already_entered {
+            None
+        } else {
+            Some(Self { entered })
+        }
+    }
+}
+
+impl Drop for ReentrancyGuard {
+    fn drop(&mut self) {
+        let _ = self.entered.try_with(|entered| entered.set(false));
+    }
+}
+
+/// Guards the rest of a detour against re-entrance on the same thread.
+///
+/// If the detour is re-entered, the given expression is returned instead, which usually calls the original function.
+///
+/// # Example
+///
+/// ```rust
+/// use minhook::{reentrancy_guard, static_hook, MH_STATUS};
+///
+/// static_hook! {
+///     static COUNTDOWN_HOOK: fn(u32) -> u32;
+/// }
+///
+/// fn main() -> Result<(), MH_STATUS> {
+///     unsafe { COUNTDOWN_HOOK.enable(countdown, countdown_hook)? };
+///
+///     // Only the outermost call is detoured, the recursive calls go straight to the original function
+///     assert_eq!(countdown(3), 1000);
+///
+///     Ok(())
+/// }
+///
+/// fn countdown(n: u32) -> u32 {
+///     if n == 0 { 0 } else { countdown(n - 1) }
+/// }
+///
+/// fn countdown_hook(n: u32) -> u32 {
+///     reentrancy_guard!(unsafe { COUNTDOWN_HOOK.call(n) });
+///
+///     countdown(n) + 1000
+/// }
+/// ```
+#[macro_export]
+macro_rules! reentrancy_guard {
+    ($fallback:expr) => {
+        ::std::thread_local! {
+            static ENTERED: ::std::cell::Cell<bool> = const { ::std::cell::Cell::new(false) };
+        }
+
+        let _reentrancy_guard = match $crate::ReentrancyGuard::enter(&ENTERED) {
+            Some(guard) => guard,
+            None => return $fallback,
+        };
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::HookTest;
+
+    #[test]
+    fn test_reentrancy_guard() {
+        thread_local! {
+            static ENTERED: Cell<bool> = const { Cell::new(false) };
+        }
+
+        let guard = ReentrancyGuard::enter(&ENTERED).unwrap();
+        assert!(ReentrancyGuard::enter(&ENTERED).is_none());
+
+        // Other threads have their own flag.
+        std::thread::spawn(|| assert!(ReentrancyGuard::enter(&ENTERED).is_some()))
+            .join()
+            .unwrap();
+
+        drop(guard);
+        assert!(ReentrancyGuard::enter(&ENTERED).is_some());
+    }
+
+    #[test]
+    fn test_reentrancy_guard_macro() {
+        let _test = HookTest::start();
+
+        crate::static_hook! {
+            static TEST_FN_HOOK: fn(u32) -> u32;
+        }
+
+        unsafe {
+            TEST_FN_HOOK.enable(test_fn, test_fn_hook).unwrap();
+            assert_eq!(test_fn(3), 1000);
+            TEST_FN_HOOK.disable().unwrap();
+        }
+
+        #[inline(never)]
+        fn test_fn(n: u32) -> u32 {
+            if n == 0 { 0 } else { test_fn(n - 1) }
+        }
+
+        fn test_fn_hook(n: u32) -> u32 {
+            crate::reentrancy_guard!(unsafe { TEST_FN_HOOK.call(n) });
+
+            test_fn(n) + 1000
+        }
+    }
+}
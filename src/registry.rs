@@ -0,0 +1,645 @@
+use crate::{
+    ApplyError, ApplyPolicy, HookStats, MH_STATUS, MhHook, MinHook,
+    hooks::{self, QueueFn},
+};
+use std::{
+    collections::{HashMap, hash_map::Entry},
+    fmt,
+    ptr::null_mut,
+    sync::{
+        Arc, LazyLock, Mutex, MutexGuard, PoisonError,
+        atomic::{AtomicPtr, Ordering},
+    },
+};
+
+static GLOBAL_REGISTRY: LazyLock<HookRegistry> = LazyLock::new(HookRegistry::new);
+
+/// A thread-safe collection of hooks, registered under names.
+///
+/// The registry is locked while one of its hooks is used, so it must not be used again from within the closure passed
+/// to [`HookRegistry::with`].
+/// Operations on names that aren't registered fail with `MH_ERROR_NOT_CREATED`.
+///
+/// Changes to the hooks are reported to the subscribers added with [`HookRegistry::subscribe`], such as a debug
+/// overlay showing which hooks are enabled.
+///
+/// Detours and threads that must not block, such as the render thread, request changes with
+/// [`HookRegistry::request_enable`] and [`HookRegistry::request_disable`] instead, which a designated thread performs
+/// with [`HookRegistry::process_requests`].
+#[derive(Debug, Default)]
+pub struct HookRegistry {
+    hooks: Mutex<HashMap<String, MhHook>>,
+    subscribers: Arc<Mutex<Subscribers>>,
+    stats: Mutex<HashMap<String, &'static HookStats>>,
+    requests: Requests,
+}
+
+/// A change to a hook of a [`HookRegistry`], reported to its subscribers with the name of the hook.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookEvent<'a> {
+    /// The hook was registered. The registry only holds hooks that are created, so this is also when the hook appears as
+    /// created to the subscribers.
+    Registered(&'a str),
+    /// The hook was unregistered, and possibly removed.
+    Unregistered(&'a str),
+    /// The hook was enabled.
+    Enabled(&'a str),
+    /// The hook was disabled.
+    Disabled(&'a str),
+    /// Enabling or disabling the hook failed with the status.
+    Failed(&'a str, MH_STATUS),
+    /// The [`HookStats`] tracked for the hook with [`HookRegistry::track_stats`] reached the number of calls.
+    CallMilestone(&'a str, u64),
+}
+
+/// Identifies a subscriber of a [`HookRegistry`], to unsubscribe it with [`HookRegistry::unsubscribe`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SubscriberId(u64);
+
+type Subscriber = Arc<dyn Fn(&HookEvent<'_>) + Send + Sync>;
+
+#[derive(Default)]
+struct Subscribers {
+    next_id: u64,
+    subscribers: Vec<(SubscriberId, Subscriber)>,
+}
+
+// Calls the subscribers with the event. They are called without the lock, so they may subscribe and unsubscribe
+fn emit(subscribers: &Mutex<Subscribers>, event: &HookEvent<'_>) {
+    // The subscribers stay consistent even if a panic happened while they were locked
+    let subscribers = subscribers
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .subscribers
+        .iter()
+        .map(|(_, subscriber)| Arc::clone(subscriber))
+        .collect::<Vec<_>>();
+
+    for subscriber in subscribers {
+        subscriber(event);
+    }
+}
+
+impl fmt::Debug for Subscribers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Subscribers")
+            .field("len", &self.subscribers.len())
+            .finish()
+    }
+}
+
+impl HookRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the registry shared by the whole process.
+    pub fn global() -> &'static HookRegistry {
+        &GLOBAL_REGISTRY
+    }
+
+    /// Calls the closure with every change to the hooks of the registry, until it is unsubscribed.
+    ///
+    /// The closure is called on the thread that changed the hook, after the change and while the registry is not locked,
+    /// so it may use the registry.
+    pub fn subscribe<F: Fn(&HookEvent<'_>) + Send + Sync + 'static>(
+        &self,
+        subscriber: F,
+    ) -> SubscriberId {
+        let mut subscribers = self.lock_subscribers();
+        let id = SubscriberId(subscribers.next_id);
+        subscribers.next_id += 1;
+        subscribers.subscribers.push((id, Arc::new(subscriber)));
+
+        id
+    }
+
+    /// Stops calling the subscriber, and returns whether it was subscribed.
+    pub fn unsubscribe(&self, id: SubscriberId) -> bool {
+        let mut subscribers = self.lock_subscribers();
+        let len = subscribers.subscribers.len();
+        subscribers
+            .subscribers
+            .retain(|&(subscriber_id, _)| subscriber_id != id);
+
+        subscribers.subscribers.len() != len
+    }
+
+    /// Tracks the stats under the name, such as the stats of an [`InstrumentHook`](crate::InstrumentHook), so they can
+    /// be read with [`HookRegistry::stats`]. Every `every` calls recorded in them are reported to the subscribers as
+    /// [`HookEvent::CallMilestone`] with the name, and a value of 0 stops reporting them.
+    ///
+    /// The event is emitted on the thread that called the hook, so the subscribers should return quickly.
+    pub fn track_stats<N: Into<String>>(&self, name: N, stats: &'static HookStats, every: u64) {
+        let name = name.into();
+        self.lock_stats().insert(name.clone(), stats);
+        if every == 0 {
+            stats.set_milestones(0, None);
+            return;
+        }
+
+        let subscribers = Arc::clone(&self.subscribers);
+        stats.set_milestones(
+            every,
+            Some(Arc::new(move |calls| {
+                emit(&subscribers, &HookEvent::CallMilestone(&name, calls))
+            })),
+        );
+    }
+
+    /// Returns the stats tracked under the name with [`HookRegistry::track_stats`]. They stay tracked when the hook is
+    /// unregistered.
+    pub fn stats(&self, name: &str) -> Option<&'static HookStats> {
+        self.lock_stats().get(name).copied()
+    }
+
+    /// Registers a hook under the name. This returns the hook that was previously registered under the name.
+    pub fn register<N: Into<String>>(&self, name: N, hook: MhHook) -> Option<MhHook> {
+        let name = name.into();
+        let previous = self.lock().insert(name.clone(), hook);
+        self.emit(&HookEvent::Registered(&name));
+
+        previous
+    }
+
+    /// Registers a hook under the name unless another hook is registered under it, checking and inserting under the same
+    /// lock. This returns the hook back if the name is taken, so the caller can remove it.
+    pub fn register_new<N: Into<String>>(&self, name: N, hook: MhHook) -> Result<(), MhHook> {
+        let name = name.into();
+        match self.lock().entry(name.clone()) {
+            Entry::Occupied(_) => return Err(hook),
+            Entry::Vacant(entry) => {
+                entry.insert(hook);
+            }
+        }
+        self.emit(&HookEvent::Registered(&name));
+
+        Ok(())
+    }
+
+    /// Unregisters the hook with the name and returns it. The hook itself is left as is.
+    pub fn unregister(&self, name: &str) -> Option<MhHook> {
+        let hook = self.lock().remove(name)?;
+        self.emit(&HookEvent::Unregistered(name));
+
+        Some(hook)
+    }
+
+    /// Returns whether a hook is registered under the name.
+    pub fn contains(&self, name: &str) -> bool {
+        self.lock().contains_key(name)
+    }
+
+    /// Calls the closure with the hook registered under the name.
+    pub fn with<R, F: FnOnce(&MhHook) -> R>(&self, name: &str, f: F) -> Option<R> {
+        self.lock().get(name).map(f)
+    }
+
+    /// Returns the names of all registered hooks.
+    pub fn names(&self) -> Vec<String> {
+        self.lock().keys().cloned().collect()
+    }
+
+    /// Returns the number of registered hooks.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Returns whether no hooks are registered.
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Enables the hook with the name.
+    ///
+    /// # Safety
+    pub unsafe fn enable(&self, name: &str) -> Result<(), MH_STATUS> {
+        let result = match self.lock().get(name) {
+            Some(hook) => unsafe { hook.enable() },
+            None => return Err(MH_STATUS::MH_ERROR_NOT_CREATED),
+        };
+        self.emit_result(name, true, result);
+
+        result
+    }
+
+    /// Disables the hook with the name.
+    ///
+    /// # Safety
+    pub unsafe fn disable(&self, name: &str) -> Result<(), MH_STATUS> {
+        let result = match self.lock().get(name) {
+            Some(hook) => unsafe { hook.disable() },
+            None => return Err(MH_STATUS::MH_ERROR_NOT_CREATED),
+        };
+        self.emit_result(name, false, result);
+
+        result
+    }
+
+    /// Enables exactly the hooks with the names, and disables all other registered hooks.
+    ///
+    /// Only the hooks whose state differs are queued, and they are applied with a single `MH_ApplyQueued`, so a menu
+    /// or configuration can state which hooks should be enabled instead of toggling them one by one. Names that aren't
+    /// registered are ignored, so a configuration can name hooks that are not installed in every version of the host.
+    /// Hooks that fail are skipped like with [`ApplyPolicy::BestEffort`].
+    ///
+    /// # Safety
+    pub unsafe fn sync<S: AsRef<str>>(&self, enabled: &[S]) -> Result<(), ApplyError> {
+        unsafe {
+            self.apply_states(|name| Some(enabled.iter().any(|enabled| enabled.as_ref() == name)))
+        }
+    }
+
+    /// Requests the hook with the name to be enabled by the next call to [`HookRegistry::process_requests`].
+    ///
+    /// The request is pushed without locking the registry, so it can be made from a detour or from a thread that MinHook
+    /// may suspend, where enabling the hook directly risks a deadlock.
+    pub fn request_enable<N: Into<String>>(&self, name: N) {
+        self.requests.push(name.into(), true);
+    }
+
+    /// Requests the hook with the name to be disabled by the next call to [`HookRegistry::process_requests`], like
+    /// [`HookRegistry::request_enable`].
+    pub fn request_disable<N: Into<String>>(&self, name: N) {
+        self.requests.push(name.into(), false);
+    }
+
+    /// Performs the requested changes, from a thread where MinHook can safely suspend the other threads.
+    ///
+    /// The last request for each name wins, and the hooks whose state differs are applied with a single
+    /// `MH_ApplyQueued` like for [`HookRegistry::sync`]. Names that aren't registered are ignored, and hooks that fail
+    /// are skipped like with [`ApplyPolicy::BestEffort`].
+    ///
+    /// # Safety
+    pub unsafe fn process_requests(&self) -> Result<(), ApplyError> {
+        let requests = self.requests.take().into_iter().collect::<HashMap<_, _>>();
+        if requests.is_empty() {
+            return Ok(());
+        }
+
+        unsafe { self.apply_states(|name| requests.get(name).copied()) }
+    }
+
+    /// Captures which of the registered hooks are enabled, to bring them back to this state with
+    /// [`HookRegistry::restore`], for example after disabling all hooks around an integrity check.
+    pub fn snapshot(&self) -> HookSnapshot {
+        let states = self
+            .lock()
+            .iter()
+            .map(|(name, hook)| (name.clone(), hook.is_enabled()))
+            .collect();
+
+        HookSnapshot { states }
+    }
+
+    /// Enables and disables the hooks in the snapshot as they were when it was taken, with a single `MH_ApplyQueued`.
+    ///
+    /// Hooks registered since the snapshot was taken are left as they are, and hooks unregistered since are ignored.
+    /// Hooks that fail are skipped like with [`ApplyPolicy::BestEffort`].
+    ///
+    /// # Safety
+    pub unsafe fn restore(&self, snapshot: &HookSnapshot) -> Result<(), ApplyError> {
+        unsafe { self.apply_states(|name| snapshot.is_enabled(name)) }
+    }
+
+    // Enables or disables the hooks to the state returned for their name, leaving them as they are for `None`
+    unsafe fn apply_states<F: Fn(&str) -> Option<bool>>(&self, state: F) -> Result<(), ApplyError> {
+        let hooks = self.lock();
+        let mut changes = Vec::new();
+        let operations = hooks
+            .iter()
+            .filter_map(|(name, hook)| {
+                let enabled = state(name)?;
+                let queue: QueueFn = match (hook.is_enabled(), enabled) {
+                    (false, true) => MinHook::queue_enable_hook,
+                    (true, false) => MinHook::queue_disable_hook,
+                    _ => return None,
+                };
+                changes.push((name.clone(), hook.target(), enabled));
+
+                Some((hook.target(), queue))
+            })
+            .collect::<Vec<_>>();
+
+        if operations.is_empty() {
+            return Ok(());
+        }
+
+        let result = unsafe { hooks::apply_operations(&operations, ApplyPolicy::BestEffort) };
+        drop(hooks);
+
+        for (name, target, enabled) in changes {
+            let status = result.as_ref().err().and_then(|error| {
+                error
+                    .failed()
+                    .iter()
+                    .find(|&&(failed, _)| failed == target)
+                    .map(|&(_, status)| status)
+            });
+            self.emit_result(&name, enabled, status.map_or(Ok(()), Err));
+        }
+
+        result
+    }
+
+    // Reports enabling or disabling the hook to the subscribers
+    fn emit_result(&self, name: &str, enabled: bool, result: Result<(), MH_STATUS>) {
+        let event = match result {
+            Ok(()) if enabled => HookEvent::Enabled(name),
+            Ok(()) => HookEvent::Disabled(name),
+            Err(status) => HookEvent::Failed(name, status),
+        };
+
+        self.emit(&event);
+    }
+
+    fn emit(&self, event: &HookEvent<'_>) {
+        emit(&self.subscribers, event);
+    }
+
+    /// Unregisters the hook with the name and removes it from MinHook.
+    ///
+    /// # Safety
+    pub unsafe fn remove(&self, name: &str) -> Result<(), MH_STATUS> {
+        match self.unregister(name) {
+            Some(hook) => unsafe { hook.remove() },
+            None => Err(MH_STATUS::MH_ERROR_NOT_CREATED),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, MhHook>> {
+        // The map is never left in an inconsistent state, so a poisoned lock can be used
+        self.hooks.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn lock_subscribers(&self) -> MutexGuard<'_, Subscribers> {
+        // The subscribers stay consistent even if a panic happened while they were locked
+        self.subscribers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn lock_stats(&self) -> MutexGuard<'_, HashMap<String, &'static HookStats>> {
+        // The stats stay consistent even if a panic happened while they were locked
+        self.stats.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+// The requests of `HookRegistry::request_enable` and `HookRegistry::request_disable`, as a stack that is pushed to with
+// a compare and swap, and only ever taken as a whole, so it is lock-free
+#[derive(Debug, Default)]
+struct Requests {
+    head: AtomicPtr<Request>,
+}
+
+#[derive(Debug)]
+struct Request {
+    name: String,
+    enable: bool,
+    next: *mut Request,
+}
+
+impl Requests {
+    fn push(&self, name: String, enable: bool) {
+        let request = Box::into_raw(Box::new(Request {
+            name,
+            enable,
+            next: null_mut(),
+        }));
+
+        let mut head = self.head.load(Ordering::Relaxed);
+        loop {
+            // The request is not shared until the swap succeeds
+            unsafe { (*request).next = head };
+            match self.head.compare_exchange_weak(
+                head,
+                request,
+                Ordering::Release,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return,
+                Err(current) => head = current,
+            }
+        }
+    }
+
+    // Takes all requests, in the order they were made
+    fn take(&self) -> Vec<(String, bool)> {
+        let mut request = self.head.swap(null_mut(), Ordering::Acquire);
+        let mut requests = Vec::new();
+        while !request.is_null() {
+            // The swap made this thread the only owner of the taken requests
+            let taken = unsafe { Box::from_raw(request) };
+            request = taken.next;
+            requests.push((taken.name, taken.enable));
+        }
+        requests.reverse();
+
+        requests
+    }
+}
+
+impl Drop for Requests {
+    fn drop(&mut self) {
+        self.take();
+    }
+}
+
+/// Which hooks of a [`HookRegistry`] were enabled, taken by [`HookRegistry::snapshot`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HookSnapshot {
+    states: HashMap<String, bool>,
+}
+
+impl HookSnapshot {
+    /// Returns whether the hook with the name was enabled, or `None` if it wasn't registered.
+    pub fn is_enabled(&self, name: &str) -> Option<bool> {
+        self.states.get(name).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::HookTest;
+    use std::time::Duration;
+
+    #[test]
+    fn test_registry() {
+        let _test = HookTest::start();
+
+        let registry = HookRegistry::new();
+
+        unsafe {
+            let hook = MhHook::from_fns::<FnType>(test_fn, test_fn_hook).unwrap();
+            assert!(registry.register("test_fn", hook).is_none());
+            assert!(registry.contains("test_fn"));
+            assert_eq!(registry.names(), ["test_fn"]);
+
+            registry.enable("test_fn").unwrap();
+            assert_eq!(test_fn(), 1);
+            registry.disable("test_fn").unwrap();
+            assert_eq!(test_fn(), 0);
+
+            // Unknown names are reported as hooks that aren't created.
+            assert_eq!(
+                registry.enable("unknown"),
+                Err(MH_STATUS::MH_ERROR_NOT_CREATED)
+            );
+
+            // Syncing only changes the hooks whose state differs.
+            registry.sync(&["test_fn", "not_installed"]).unwrap();
+            assert_eq!(test_fn(), 1);
+            registry.sync::<&str>(&[]).unwrap();
+            assert_eq!(test_fn(), 0);
+
+            // A snapshot brings back the state from before.
+            registry.enable("test_fn").unwrap();
+            let snapshot = registry.snapshot();
+            assert_eq!(snapshot.is_enabled("test_fn"), Some(true));
+            registry.sync::<&str>(&[]).unwrap();
+            assert_eq!(test_fn(), 0);
+            registry.restore(&snapshot).unwrap();
+            assert_eq!(test_fn(), 1);
+            registry.disable("test_fn").unwrap();
+
+            // Removing the hook unregisters it and allows creating it again.
+            registry.remove("test_fn").unwrap();
+            assert!(registry.is_empty());
+            MhHook::from_fns::<FnType>(test_fn, test_fn_hook).unwrap();
+        }
+
+        type FnType = fn() -> i32;
+
+        #[inline(never)]
+        fn test_fn() -> i32 {
+            0
+        }
+
+        fn test_fn_hook() -> i32 {
+            1
+        }
+    }
+
+    #[test]
+    fn test_requests() {
+        let _test = HookTest::start();
+
+        let registry = HookRegistry::new();
+
+        unsafe {
+            let hook = MhHook::from_fns::<FnType>(test_fn, test_fn_hook).unwrap();
+            registry.register("test_fn", hook);
+
+            // Requests from other threads only take effect once they are processed.
+            std::thread::scope(|scope| {
+                scope.spawn(|| registry.request_enable("test_fn"));
+            });
+            registry.request_enable("not_installed");
+            assert_eq!(test_fn(), 180);
+            registry.process_requests().unwrap();
+            assert_eq!(test_fn(), 181);
+
+            // The last request for a hook wins.
+            registry.request_disable("test_fn");
+            registry.request_enable("test_fn");
+            registry.request_disable("test_fn");
+            registry.process_requests().unwrap();
+            assert_eq!(test_fn(), 180);
+
+            registry.request_enable("test_fn");
+            registry.remove("test_fn").unwrap();
+            registry.process_requests().unwrap();
+        }
+
+        type FnType = fn() -> i32;
+
+        #[inline(never)]
+        fn test_fn() -> i32 {
+            std::hint::black_box(180)
+        }
+
+        fn test_fn_hook() -> i32 {
+            181
+        }
+    }
+
+    #[test]
+    fn test_subscribe() {
+        let _test = HookTest::start();
+
+        static EVENTS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+        let registry = HookRegistry::new();
+        let id = registry.subscribe(|event| EVENTS.lock().unwrap().push(format!("{event:?}")));
+
+        unsafe {
+            let hook = MhHook::from_fns::<FnType>(test_fn, test_fn_hook).unwrap();
+            registry.register("test_fn", hook);
+            registry.enable("test_fn").unwrap();
+            assert_eq!(registry.enable("test_fn"), Err(MH_STATUS::MH_ERROR_ENABLED));
+            registry.sync::<&str>(&[]).unwrap();
+            registry.remove("test_fn").unwrap();
+        }
+
+        // Unsubscribed closures are no longer called.
+        assert!(registry.unsubscribe(id));
+        assert!(!registry.unsubscribe(id));
+        registry.register(
+            "test_fn",
+            unsafe { MhHook::from_fns::<FnType>(test_fn, test_fn_hook) }.unwrap(),
+        );
+
+        assert_eq!(
+            *EVENTS.lock().unwrap(),
+            [
+                "Registered(\"test_fn\")",
+                "Enabled(\"test_fn\")",
+                "Failed(\"test_fn\", MH_ERROR_ENABLED)",
+                "Disabled(\"test_fn\")",
+                "Unregistered(\"test_fn\")",
+            ]
+        );
+        unsafe { registry.remove("test_fn").unwrap() };
+
+        type FnType = fn() -> i32;
+
+        #[inline(never)]
+        fn test_fn() -> i32 {
+            std::hint::black_box(80)
+        }
+
+        fn test_fn_hook() -> i32 {
+            81
+        }
+    }
+
+    #[test]
+    fn test_track_stats() {
+        static EVENTS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+        static STATS: HookStats = HookStats::new();
+
+        let registry = HookRegistry::new();
+        registry.subscribe(|event| EVENTS.lock().unwrap().push(format!("{event:?}")));
+        registry.track_stats("test_fn", &STATS, 2);
+        assert!(std::ptr::eq(registry.stats("test_fn").unwrap(), &STATS));
+        assert!(registry.stats("other_fn").is_none());
+        for _ in 0..5 {
+            STATS.record(Duration::ZERO, Duration::ZERO);
+        }
+
+        // Untracked stats are no longer reported.
+        registry.track_stats("test_fn", &STATS, 0);
+        STATS.record(Duration::ZERO, Duration::ZERO);
+
+        assert_eq!(STATS.calls(), 6);
+        assert_eq!(
+            *EVENTS.lock().unwrap(),
+            [
+                "CallMilestone(\"test_fn\", 2)",
+                "CallMilestone(\"test_fn\", 4)"
+            ]
+        );
+    }
+}
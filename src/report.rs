@@ -0,0 +1,54 @@
+use crate::win::{
+    CloseHandle, CreateToolhelp32Snapshot, GetCurrentProcessId, GetCurrentThreadId,
+    INVALID_HANDLE_VALUE, TH32CS_SNAPTHREAD, Thread32First, Thread32Next, ThreadEntry32,
+};
+use std::{fmt, mem::MaybeUninit, time::Duration};
+
+/// What applying the queued hooks took, returned by [`MinHook::apply_queued_report`](crate::MinHook::apply_queued_report).
+///
+/// MinHook suspends every other thread of the process while it patches the targets, and moves threads that were
+/// suspended inside a patched prologue to the trampoline. It doesn't report which threads it moved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ApplyReport {
+    /// The number of hooks that were queued.
+    pub queued: usize,
+    /// The number of other threads of the process right before applying, which MinHook suspended and resumed.
+    pub threads: usize,
+    /// How long applying took, including suspending and resuming the threads.
+    pub duration: Duration,
+}
+
+impl fmt::Display for ApplyReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Applied {} queued hooks in {:?}, suspending {} threads",
+            self.queued, self.duration, self.threads
+        )
+    }
+}
+
+// Counts the threads of the process other than the current one, the way MinHook enumerates them to suspend them
+pub(crate) fn other_threads() -> usize {
+    let snapshot = unsafe { CreateToolhelp32Snapshot(TH32CS_SNAPTHREAD, 0) };
+    if snapshot == INVALID_HANDLE_VALUE {
+        return 0;
+    }
+
+    let process = unsafe { GetCurrentProcessId() };
+    let thread = unsafe { GetCurrentThreadId() };
+    let mut entry = unsafe { MaybeUninit::<ThreadEntry32>::zeroed().assume_init() };
+    entry.size = size_of::<ThreadEntry32>() as u32;
+
+    let mut threads = 0;
+    let mut found = unsafe { Thread32First(snapshot, &mut entry) } != 0;
+    while found {
+        if entry.owner_process_id == process && entry.thread_id != thread {
+            threads += 1;
+        }
+        found = unsafe { Thread32Next(snapshot, &mut entry) } != 0;
+    }
+    unsafe { CloseHandle(snapshot) };
+
+    threads
+}
@@ -0,0 +1,202 @@
+//! Scanning loaded modules for byte patterns, to find hook targets that aren't exported.
+//!
+//! Patterns are written in the IDA style: bytes in hexadecimal separated by spaces, with `?` or `??` for bytes that
+//! can have any value.
+//!
+//! ```rust
+//! use minhook::scan::Pattern;
+//!
+//! let pattern = "48 8B ?? ?? 89 5C".parse::<Pattern>().unwrap();
+//! let bytes = [0x90, 0x48, 0x8B, 0x01, 0x02, 0x89, 0x5C];
+//! assert_eq!(pattern.find_in(&bytes).collect::<Vec<_>>(), [1]);
+//! ```
+
+use crate::{
+    MH_STATUS, ModuleName,
+    pe::{IMAGE_SCN_MEM_EXECUTE, Image},
+    win::GetModuleHandleW,
+};
+use std::{ffi::c_void, fmt, ptr::null, str::FromStr};
+
+/// A byte pattern where some bytes can have any value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    bytes: Vec<Option<u8>>,
+}
+
+impl Pattern {
+    /// Returns whether the bytes start with the pattern.
+    pub fn matches(&self, bytes: &[u8]) -> bool {
+        bytes.len() >= self.bytes.len()
+            && self
+                .bytes
+                .iter()
+                .zip(bytes)
+                .all(|(pattern, byte)| pattern.is_none_or(|pattern| pattern == *byte))
+    }
+
+    /// Returns the offsets of all matches of the pattern in the bytes.
+    pub fn find_in<'a>(&'a self, bytes: &'a [u8]) -> impl Iterator<Item = usize> + 'a {
+        (0..bytes.len())
+            .filter(move |&offset| !self.bytes.is_empty() && self.matches(&bytes[offset..]))
+    }
+
+    /// Returns the number of bytes in the pattern.
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Returns whether the pattern has no bytes.
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = ParsePatternError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = s
+            .split_whitespace()
+            .map(|byte| match byte {
+                "?" | "??" => Ok(None),
+                _ if byte.len() == 2 => u8::from_str_radix(byte, 16)
+                    .map(Some)
+                    .map_err(|_| ParsePatternError(byte.to_owned())),
+                _ => Err(ParsePatternError(byte.to_owned())),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self { bytes })
+    }
+}
+
+/// An error returned when parsing a [`Pattern`] with an invalid byte.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsePatternError(String);
+
+impl fmt::Display for ParsePatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid byte in pattern: {:?}", self.0)
+    }
+}
+
+impl std::error::Error for ParsePatternError {}
+
+/// Returns the addresses of all matches of the pattern in the executable sections of the module.
+///
+/// Returns `MH_ERROR_MODULE_NOT_FOUND` if the module is not loaded.
+///
+/// # Safety
+///
+/// The module must not be unloaded while it's scanned.
+pub unsafe fn find_in_module<M: Into<ModuleName>>(
+    module: M,
+    pattern: &Pattern,
+) -> Result<Vec<*mut c_void>, MH_STATUS> {
+    let module = module.into();
+    let base = unsafe { GetModuleHandleW(module.as_ptr()) };
+
+    unsafe { find_in_base(base, pattern) }
+}
+
+/// Returns the address of the only match of the pattern in the executable sections of the module.
+///
+/// Returns `MH_ERROR_MODULE_NOT_FOUND` if the module is not loaded, and `MH_ERROR_FUNCTION_NOT_FOUND` if the pattern
+/// matches nowhere or more than once, since hooking the wrong match would detour an unrelated function.
+///
+/// # Safety
+///
+/// The module must not be unloaded while it's scanned.
+pub unsafe fn find_one_in_module<M: Into<ModuleName>>(
+    module: M,
+    pattern: &Pattern,
+) -> Result<*mut c_void, MH_STATUS> {
+    match unsafe { find_in_module(module, pattern)? }[..] {
+        [address] => Ok(address),
+        _ => Err(MH_STATUS::MH_ERROR_FUNCTION_NOT_FOUND),
+    }
+}
+
+/// Returns the addresses of all matches of the pattern in the executable sections of the process executable.
+///
+/// # Safety
+pub unsafe fn find_in_executable(pattern: &Pattern) -> Result<Vec<*mut c_void>, MH_STATUS> {
+    let base = unsafe { GetModuleHandleW(null()) };
+
+    unsafe { find_in_base(base, pattern) }
+}
+
+unsafe fn find_in_base(
+    base: *mut c_void,
+    pattern: &Pattern,
+) -> Result<Vec<*mut c_void>, MH_STATUS> {
+    let image = unsafe { Image::from_base(base as *const u8) }
+        .ok_or(MH_STATUS::MH_ERROR_MODULE_NOT_FOUND)?;
+
+    Ok(image
+        .sections()
+        .iter()
+        .filter(|section| section.characteristics & IMAGE_SCN_MEM_EXECUTE != 0)
+        .flat_map(|section| {
+            let bytes = image.section_bytes(section);
+            pattern
+                .find_in(bytes)
+                .map(|offset| bytes[offset..].as_ptr() as *mut c_void)
+                .collect::<Vec<_>>()
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pattern() {
+        let pattern = "48 8b ? ?? 5C".parse::<Pattern>().unwrap();
+        assert_eq!(pattern.len(), 5);
+        assert!(pattern.matches(&[0x48, 0x8B, 0x00, 0xFF, 0x5C, 0x00]));
+        assert!(!pattern.matches(&[0x48, 0x8B, 0x00, 0xFF, 0x5D]));
+        assert!(!pattern.matches(&[0x48, 0x8B]));
+
+        assert_eq!(
+            "48 8B0".parse::<Pattern>(),
+            Err(ParsePatternError("8B0".to_owned()))
+        );
+        assert_eq!(
+            "48 XX".parse::<Pattern>(),
+            Err(ParsePatternError("XX".to_owned()))
+        );
+    }
+
+    #[test]
+    fn test_find_in_executable() {
+        // Build a pattern from the code of a function in the test executable.
+        let address = test_fn as fn(i32) -> i32 as *const u8;
+        let code = unsafe { std::slice::from_raw_parts(address, 16) };
+        let pattern = code
+            .iter()
+            .map(|byte| format!("{byte:02X}"))
+            .collect::<Vec<_>>()
+            .join(" ")
+            .parse::<Pattern>()
+            .unwrap();
+
+        let matches = unsafe { find_in_executable(&pattern) }.unwrap();
+        assert!(matches.contains(&(address as *mut c_void)));
+        assert_eq!(test_fn(1), 2);
+
+        #[inline(never)]
+        fn test_fn(x: i32) -> i32 {
+            x.wrapping_mul(3).wrapping_sub(1)
+        }
+    }
+
+    #[test]
+    fn test_find_in_module_not_found() {
+        let pattern = "90".parse::<Pattern>().unwrap();
+        let status = unsafe { find_in_module("not_loaded.dll", &pattern) }.unwrap_err();
+        assert_eq!(status, MH_STATUS::MH_ERROR_MODULE_NOT_FOUND);
+    }
+}
@@ -0,0 +1,115 @@
+use crate::{MhHook, logging::warning};
+use std::{ops::Deref, sync::Arc};
+
+/// A hook that several owners share, such as a hook manager and the feature that toggles the hook.
+///
+/// Cloning the handle is cheap and every clone refers to the same hook, which is reachable through `Deref`. The hook
+/// is removed once the last handle is dropped, unless it was taken back with [`SharedHook::try_unwrap`]. Any handle
+/// can enable and disable the hook, so the owners have to agree on its state.
+#[derive(Debug, Clone)]
+pub struct SharedHook {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    hook: Option<MhHook>,
+}
+
+impl SharedHook {
+    /// Shares the hook, which is removed once the last handle is dropped.
+    pub fn new(hook: MhHook) -> Self {
+        Self {
+            inner: Arc::new(Inner { hook: Some(hook) }),
+        }
+    }
+
+    /// Returns the number of handles to the hook, including this one.
+    pub fn handle_count(&self) -> usize {
+        Arc::strong_count(&self.inner)
+    }
+
+    /// Returns the hook if this is its last handle, without removing it. Otherwise the handle is returned as is.
+    pub fn try_unwrap(self) -> Result<MhHook, Self> {
+        match Arc::try_unwrap(self.inner) {
+            Ok(mut inner) => Ok(inner.hook.take().expect("The hook is only taken once")),
+            Err(inner) => Err(Self { inner }),
+        }
+    }
+}
+
+impl From<MhHook> for SharedHook {
+    fn from(hook: MhHook) -> Self {
+        Self::new(hook)
+    }
+}
+
+impl Deref for SharedHook {
+    type Target = MhHook;
+
+    fn deref(&self) -> &Self::Target {
+        self.inner
+            .hook
+            .as_ref()
+            .expect("The hook is only taken from the last handle")
+    }
+}
+
+impl Drop for Inner {
+    fn drop(&mut self) {
+        let Some(hook) = self.hook.take() else {
+            return;
+        };
+
+        if hook.is_created() {
+            let target = hook.target();
+            if let Err(status) = unsafe { hook.remove() } {
+                warning!("Could not remove shared hook for {:?}: {}", target, status);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::HookTest;
+
+    #[test]
+    fn test_shared_hook() {
+        let _test = HookTest::start();
+
+        unsafe {
+            let hook = SharedHook::new(MhHook::from_fns::<FnType>(test_fn, test_fn_hook).unwrap());
+            let clone = hook.clone();
+            assert_eq!(hook.handle_count(), 2);
+
+            clone.enable().unwrap();
+            assert!(hook.is_enabled());
+            assert_eq!(test_fn(), 101);
+
+            // The hook is removed once the last handle is dropped.
+            let hook = hook.try_unwrap().unwrap_err();
+            drop(hook);
+            assert!(clone.is_created());
+            let target = clone.target();
+            drop(clone);
+            assert!(!crate::state::is_created(target));
+            assert_eq!(test_fn(), 100);
+
+            let hook = SharedHook::from(MhHook::from_fns::<FnType>(test_fn, test_fn_hook).unwrap());
+            hook.try_unwrap().unwrap().remove().unwrap();
+        }
+
+        type FnType = fn() -> i32;
+
+        #[inline(never)]
+        fn test_fn() -> i32 {
+            std::hint::black_box(100)
+        }
+
+        fn test_fn_hook() -> i32 {
+            101
+        }
+    }
+}
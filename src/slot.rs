@@ -0,0 +1,115 @@
+use crate::{
+    MH_STATUS, os_error,
+    win::{PAGE_READWRITE, VirtualProtect},
+};
+use std::{
+    ffi::c_void,
+    mem::size_of,
+    sync::atomic::{AtomicBool, AtomicPtr, Ordering},
+};
+
+/// A hook that replaces a function pointer stored in memory, such as an import or a virtual method.
+#[derive(Debug)]
+pub struct SlotHook {
+    slot: *const AtomicPtr<c_void>,
+    original: *mut c_void,
+    detour: *mut c_void,
+    enabled: AtomicBool,
+}
+
+// The slot is only changed atomically.
+unsafe impl Send for SlotHook {}
+unsafe impl Sync for SlotHook {}
+
+impl SlotHook {
+    /// Creates a disabled hook for the function pointer in the slot.
+    ///
+    /// # Safety
+    ///
+    /// The slot must be a valid, aligned function pointer for as long as the hook is used.
+    pub unsafe fn new(slot: *mut *mut c_void, detour: *mut c_void) -> Self {
+        let slot = slot as *const AtomicPtr<c_void>;
+        let original = unsafe { (*slot).load(Ordering::SeqCst) };
+
+        Self {
+            slot,
+            original,
+            detour,
+            enabled: AtomicBool::new(false),
+        }
+    }
+
+    /// Creates a disabled hook for the slot like [`SlotHook::new`], which restores `original` instead of the current
+    /// function pointer when it is disabled.
+    ///
+    /// # Safety
+    ///
+    /// The slot must be a valid, aligned function pointer for as long as the hook is used.
+    pub unsafe fn with_original(
+        slot: *mut *mut c_void,
+        original: *mut c_void,
+        detour: *mut c_void,
+    ) -> Self {
+        Self {
+            slot: slot as *const AtomicPtr<c_void>,
+            original,
+            detour,
+            enabled: AtomicBool::new(false),
+        }
+    }
+
+    pub unsafe fn enable(&self) -> Result<(), MH_STATUS> {
+        if self.enabled.swap(true, Ordering::SeqCst) {
+            return Err(MH_STATUS::MH_ERROR_ENABLED);
+        }
+
+        unsafe { self.write(self.detour) }.inspect_err(|_| {
+            self.enabled.store(false, Ordering::SeqCst);
+        })
+    }
+
+    pub unsafe fn disable(&self) -> Result<(), MH_STATUS> {
+        if !self.enabled.swap(false, Ordering::SeqCst) {
+            return Err(MH_STATUS::MH_ERROR_DISABLED);
+        }
+
+        unsafe { self.write(self.original) }.inspect_err(|_| {
+            self.enabled.store(true, Ordering::SeqCst);
+        })
+    }
+
+    pub unsafe fn remove(self) -> Result<(), MH_STATUS> {
+        match unsafe { self.disable() } {
+            Ok(()) | Err(MH_STATUS::MH_ERROR_DISABLED) => Ok(()),
+            Err(status) => Err(status),
+        }
+    }
+
+    pub fn original(&self) -> *mut c_void {
+        self.original
+    }
+
+    pub fn slot(&self) -> *mut *mut c_void {
+        self.slot as *mut _
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::SeqCst)
+    }
+
+    unsafe fn write(&self, address: *mut c_void) -> Result<(), MH_STATUS> {
+        let slot = self.slot as *const c_void;
+        let size = size_of::<*mut c_void>();
+
+        let mut protect = 0;
+        if unsafe { VirtualProtect(slot, size, PAGE_READWRITE, &mut protect) } == 0 {
+            os_error::capture(MH_STATUS::MH_ERROR_MEMORY_PROTECT);
+            return Err(MH_STATUS::MH_ERROR_MEMORY_PROTECT);
+        }
+
+        unsafe { (*self.slot).store(address, Ordering::SeqCst) };
+
+        unsafe { VirtualProtect(slot, size, protect, &mut protect) };
+        Ok(())
+    }
+}
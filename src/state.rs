@@ -0,0 +1,255 @@
+use crate::{MH_STATUS, dump::HookInfo};
+use std::{
+    collections::HashMap,
+    ffi::c_void,
+    ops::Range,
+    sync::{
+        LazyLock, Mutex, MutexGuard, PoisonError,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+// The state of every target passed to MinHook, keyed by the address of the target
+static HOOK_STATES: LazyLock<Mutex<HashMap<usize, HookState>>> = LazyLock::new(Default::default);
+
+// The next epoch handed out, unique across all targets so a hook created again on a target never reuses an epoch
+static NEXT_EPOCH: AtomicU64 = AtomicU64::new(1);
+
+// The state MinHook keeps for a target, mirrored from the statuses the wrappers in `MinHook` receive
+#[derive(Debug, Clone)]
+struct HookState {
+    created: bool,
+    enabled: bool,
+    queued: Option<bool>,
+    last_status: MH_STATUS,
+    detour: usize,
+    trampoline: usize,
+    name: Option<String>,
+    foreign_detour: usize,
+    // Changes whenever the hook is created, enabled, disabled or removed
+    epoch: u64,
+}
+
+impl Default for HookState {
+    fn default() -> Self {
+        Self {
+            created: false,
+            enabled: false,
+            queued: None,
+            last_status: MH_STATUS::MH_OK,
+            detour: 0,
+            trampoline: 0,
+            name: None,
+            foreign_detour: 0,
+            epoch: 0,
+        }
+    }
+}
+
+// An operation on a target, or on all hooks if the target is `MH_ALL_HOOKS`
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Operation {
+    Create,
+    Enable,
+    Disable,
+    Remove,
+    QueueEnable,
+    QueueDisable,
+}
+
+// Records the status MinHook returned for an operation on the target
+pub(crate) fn record(target: *mut c_void, operation: Operation, status: MH_STATUS) {
+    let mut states = lock();
+
+    // Operations on all hooks only change the hooks that are created
+    if target.is_null() {
+        if status == MH_STATUS::MH_OK {
+            for state in states.values_mut().filter(|state| state.created) {
+                apply(state, operation);
+            }
+        }
+        return;
+    }
+
+    let state = states.entry(target as usize).or_default();
+    state.last_status = status;
+
+    if status == MH_STATUS::MH_OK {
+        apply(state, operation);
+    }
+}
+
+// Records the detour and trampoline of a hook that was created, and the detour of another hooking library the target
+// already jumped to
+pub(crate) fn record_created(
+    target: *mut c_void,
+    detour: *mut c_void,
+    trampoline: *mut c_void,
+    foreign_detour: Option<*mut c_void>,
+) {
+    let mut states = lock();
+    let state = states.entry(target as usize).or_default();
+    state.detour = detour as usize;
+    state.trampoline = trampoline as usize;
+    state.foreign_detour = foreign_detour.map_or(0, |detour| detour as usize);
+}
+
+// Records the detour a hook was switched to after it was created
+#[cfg(target_pointer_width = "64")]
+pub(crate) fn record_detour(target: *mut c_void, detour: *mut c_void) {
+    if let Some(state) = lock().get_mut(&(target as usize)) {
+        state.detour = detour as usize;
+    }
+}
+
+// Records the name given to the hook of the target
+pub(crate) fn record_name(target: *mut c_void, name: Option<String>) {
+    if let Some(state) = lock().get_mut(&(target as usize)) {
+        state.name = name;
+    }
+}
+
+// Records that the queued operations were applied
+pub(crate) fn record_applied() {
+    for state in lock().values_mut() {
+        if let Some(enabled) = state.queued.take() {
+            state.enabled = enabled;
+            state.epoch = next_epoch();
+        }
+    }
+}
+
+// Starts a new epoch for the hook of the target, and returns it. Returns `None` if the target isn't hooked
+pub(crate) fn renew_epoch(target: *mut c_void) -> Option<u64> {
+    let mut states = lock();
+    let state = states
+        .get_mut(&(target as usize))
+        .filter(|state| state.created)?;
+    state.epoch = next_epoch();
+
+    Some(state.epoch)
+}
+
+// Returns the epoch of the hook of the target, or `None` if the target isn't hooked
+pub(crate) fn epoch(target: *mut c_void) -> Option<u64> {
+    lock()
+        .get(&(target as usize))
+        .filter(|state| state.created)
+        .map(|state| state.epoch)
+}
+
+// Records that MinHook was uninitialized, which removes all hooks
+pub(crate) fn clear() {
+    lock().clear();
+}
+
+pub(crate) fn trampoline(target: *mut c_void) -> Option<*mut c_void> {
+    lock()
+        .get(&(target as usize))
+        .filter(|state| state.created)
+        .map(|state| state.trampoline as *mut c_void)
+}
+
+pub(crate) fn detour(target: *mut c_void) -> Option<*mut c_void> {
+    lock()
+        .get(&(target as usize))
+        .filter(|state| state.created)
+        .map(|state| state.detour as *mut c_void)
+}
+
+pub(crate) fn name(target: *mut c_void) -> Option<String> {
+    lock()
+        .get(&(target as usize))
+        .filter(|state| state.created)
+        .and_then(|state| state.name.clone())
+}
+
+pub(crate) fn is_created(target: *mut c_void) -> bool {
+    lock()
+        .get(&(target as usize))
+        .is_some_and(|state| state.created)
+}
+
+// Returns the targets of the created hooks in the address range
+pub(crate) fn created_in(range: Range<usize>) -> Vec<*mut c_void> {
+    lock()
+        .iter()
+        .filter(|(target, state)| state.created && range.contains(target))
+        .map(|(&target, _)| target as *mut c_void)
+        .collect()
+}
+
+// Returns the created hooks, ordered by their target and without their modules
+pub(crate) fn created() -> Vec<HookInfo> {
+    let mut hooks = lock()
+        .iter()
+        .filter(|(_, state)| state.created)
+        .map(|(&target, state)| HookInfo {
+            name: state.name.clone(),
+            target: target as *mut c_void,
+            module: None,
+            detour: state.detour as *mut c_void,
+            trampoline: state.trampoline as *mut c_void,
+            enabled: state.enabled,
+            foreign_detour: (state.foreign_detour != 0)
+                .then_some(state.foreign_detour as *mut c_void),
+        })
+        .collect::<Vec<_>>();
+    hooks.sort_by_key(|hook| hook.target as usize);
+
+    hooks
+}
+
+pub(crate) fn foreign_detour(target: *mut c_void) -> Option<*mut c_void> {
+    lock()
+        .get(&(target as usize))
+        .filter(|state| state.created && state.foreign_detour != 0)
+        .map(|state| state.foreign_detour as *mut c_void)
+}
+
+// The trampoline of the only created hook with the detour
+pub(crate) fn trampoline_of(detour: *mut c_void) -> Option<*mut c_void> {
+    let states = lock();
+    let mut trampolines = states
+        .values()
+        .filter(|state| state.created && state.detour == detour as usize)
+        .map(|state| state.trampoline as *mut c_void);
+
+    match (trampolines.next(), trampolines.next()) {
+        (Some(trampoline), None) => Some(trampoline),
+        _ => None,
+    }
+}
+
+pub(crate) fn is_enabled(target: *mut c_void) -> bool {
+    lock()
+        .get(&(target as usize))
+        .is_some_and(|state| state.enabled)
+}
+
+pub(crate) fn last_status(target: *mut c_void) -> Option<MH_STATUS> {
+    lock()
+        .get(&(target as usize))
+        .map(|state| state.last_status)
+}
+
+fn apply(state: &mut HookState, operation: Operation) {
+    match operation {
+        Operation::Create => state.created = true,
+        Operation::Enable => state.enabled = true,
+        Operation::Disable => state.enabled = false,
+        Operation::Remove => *state = HookState::default(),
+        Operation::QueueEnable => state.queued = Some(true),
+        Operation::QueueDisable => state.queued = Some(false),
+    }
+    state.epoch = next_epoch();
+}
+
+fn next_epoch() -> u64 {
+    NEXT_EPOCH.fetch_add(1, Ordering::Relaxed)
+}
+
+// The states stay consistent even if a panic happened while they were locked
+fn lock() -> MutexGuard<'static, HashMap<usize, HookState>> {
+    HOOK_STATES.lock().unwrap_or_else(PoisonError::into_inner)
+}
@@ -0,0 +1,148 @@
+use std::{
+    fmt,
+    sync::{
+        Arc, PoisonError, RwLock,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+// Told the number of calls whenever it reaches a multiple of the milestone interval
+pub(crate) type MilestoneCallback = Arc<dyn Fn(u64) + Send + Sync>;
+
+/// Call metrics of a hook, collected by [`InstrumentHook`](crate::InstrumentHook) or by a detour calling
+/// [`HookStats::record`].
+///
+/// The counters are updated atomically, but independently of each other, so a snapshot taken while the hook is called
+/// may count a call in one counter and not yet in another.
+///
+/// Once the stats are tracked with [`HookRegistry::track_stats`](crate::HookRegistry::track_stats), a
+/// [`HookRegistry`](crate::HookRegistry) returns them by name and reports every Nth call to its subscribers.
+#[derive(Default)]
+pub struct HookStats {
+    calls: AtomicU64,
+    last_call: AtomicU64,
+    detour_nanos: AtomicU64,
+    original_nanos: AtomicU64,
+    milestone_every: AtomicU64,
+    on_milestone: RwLock<Option<MilestoneCallback>>,
+}
+
+impl HookStats {
+    /// Creates empty metrics.
+    pub const fn new() -> Self {
+        Self {
+            calls: AtomicU64::new(0),
+            last_call: AtomicU64::new(0),
+            detour_nanos: AtomicU64::new(0),
+            original_nanos: AtomicU64::new(0),
+            milestone_every: AtomicU64::new(0),
+            on_milestone: RwLock::new(None),
+        }
+    }
+
+    /// Records a call that spent `detour` in the detour itself and `original` in the original function.
+    pub fn record(&self, detour: Duration, original: Duration) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+
+        let calls = self.calls.fetch_add(1, Ordering::Relaxed) + 1;
+        self.last_call.store(nanos(now), Ordering::Relaxed);
+        self.detour_nanos
+            .fetch_add(nanos(detour), Ordering::Relaxed);
+        self.original_nanos
+            .fetch_add(nanos(original), Ordering::Relaxed);
+
+        let every = self.milestone_every.load(Ordering::Relaxed);
+        if every != 0 && calls.is_multiple_of(every) {
+            // The callback stays consistent even if a panic happened while it was locked
+            let callback = self
+                .on_milestone
+                .read()
+                .unwrap_or_else(PoisonError::into_inner)
+                .clone();
+            if let Some(callback) = callback {
+                callback(calls);
+            }
+        }
+    }
+
+    // Calls the callback whenever the number of calls reaches a multiple of `every`, or stops calling it for `None`
+    pub(crate) fn set_milestones(&self, every: u64, callback: Option<MilestoneCallback>) {
+        let every = if callback.is_some() { every } else { 0 };
+        *self
+            .on_milestone
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = callback;
+        self.milestone_every.store(every, Ordering::Relaxed);
+    }
+
+    /// Returns the number of recorded calls.
+    pub fn calls(&self) -> u64 {
+        self.calls.load(Ordering::Relaxed)
+    }
+
+    /// Returns when the last call was recorded, or `None` if no call was recorded yet.
+    pub fn last_call(&self) -> Option<SystemTime> {
+        match self.last_call.load(Ordering::Relaxed) {
+            0 => None,
+            nanos => Some(UNIX_EPOCH + Duration::from_nanos(nanos)),
+        }
+    }
+
+    /// Returns the time spent in the detour, without the time spent in the original function.
+    pub fn detour_time(&self) -> Duration {
+        Duration::from_nanos(self.detour_nanos.load(Ordering::Relaxed))
+    }
+
+    /// Returns the time spent in the original function.
+    pub fn original_time(&self) -> Duration {
+        Duration::from_nanos(self.original_nanos.load(Ordering::Relaxed))
+    }
+
+    /// Resets all counters.
+    pub fn reset(&self) {
+        self.calls.store(0, Ordering::Relaxed);
+        self.last_call.store(0, Ordering::Relaxed);
+        self.detour_nanos.store(0, Ordering::Relaxed);
+        self.original_nanos.store(0, Ordering::Relaxed);
+    }
+}
+
+impl fmt::Debug for HookStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HookStats")
+            .field("calls", &self.calls())
+            .field("last_call", &self.last_call())
+            .field("detour_time", &self.detour_time())
+            .field("original_time", &self.original_time())
+            .finish_non_exhaustive()
+    }
+}
+
+fn nanos(duration: Duration) -> u64 {
+    duration.as_nanos().try_into().unwrap_or(u64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hook_stats() {
+        let stats = HookStats::new();
+        assert_eq!(stats.calls(), 0);
+        assert_eq!(stats.last_call(), None);
+
+        stats.record(Duration::from_micros(1), Duration::from_micros(5));
+        stats.record(Duration::from_micros(2), Duration::from_micros(5));
+        assert_eq!(stats.calls(), 2);
+        assert!(stats.last_call().is_some());
+        assert_eq!(stats.detour_time(), Duration::from_micros(3));
+        assert_eq!(stats.original_time(), Duration::from_micros(10));
+
+        stats.reset();
+        assert_eq!(stats.calls(), 0);
+    }
+}
@@ -0,0 +1,160 @@
+//! Targets resolved from debug symbols through `dbghelp`, including functions that are not exported.
+//!
+//! Symbols are looked up by `module!name`, such as `ntdll!LdrpLoadDll`. Names of non-exported functions are only
+//! found if the PDB of the module can be loaded, for example through the `_NT_SYMBOL_PATH` environment variable.
+
+use crate::{
+    MH_STATUS, MhHook,
+    logging::debug,
+    win::{
+        CURRENT_PROCESS, SymFromAddr, SymFromName, SymInitialize, SymRefreshModuleList, SymbolInfo,
+    },
+};
+use std::{
+    ffi::{CStr, CString, c_void},
+    mem::MaybeUninit,
+    sync::{Mutex, MutexGuard, PoisonError},
+};
+
+// Whether the symbol handler was initialized. The lock also serializes all calls into dbghelp, which is not thread-safe.
+static SYMBOL_HANDLER: Mutex<bool> = Mutex::new(false);
+
+// The longest symbol name that is looked up by address, in bytes
+const MAX_SYMBOL_NAME: usize = 256;
+
+// A `SYMBOL_INFO` with room for the name of the symbol after it
+#[repr(C)]
+struct SymbolBuffer {
+    info: SymbolInfo,
+    name: [u8; MAX_SYMBOL_NAME],
+}
+
+/// Returns the address of a symbol, such as `ntdll!LdrpLoadDll`.
+///
+/// The symbol handler is initialized on first use, and picks up the modules loaded since the last lookup when a symbol
+/// is not found. Returns `MH_ERROR_FUNCTION_NOT_FOUND` if the symbol is not found, and `MH_UNKNOWN` if the symbol
+/// handler can't be initialized.
+pub fn resolve(name: &str) -> Result<*mut c_void, MH_STATUS> {
+    let name = CString::new(name).map_err(|_| MH_STATUS::MH_ERROR_FUNCTION_NOT_FOUND)?;
+    let _handler = symbol_handler()?;
+
+    let address = from_name(&name)
+        .or_else(|| {
+            refresh_modules();
+            from_name(&name)
+        })
+        .ok_or(MH_STATUS::MH_ERROR_FUNCTION_NOT_FOUND)?;
+    debug!("Resolved symbol {:?} to {:#x}", name, address);
+
+    Ok(address as usize as *mut c_void)
+}
+
+/// Returns the name of the symbol the address is in, followed by the offset into it if the address is not at its start,
+/// such as `LdrpLoadDll+0x1a`.
+///
+/// Returns `None` if no symbol contains the address, or if the symbol handler can't be initialized.
+pub fn symbolize(address: *const c_void) -> Option<String> {
+    let _handler = symbol_handler().ok()?;
+
+    from_address(address).or_else(|| {
+        refresh_modules();
+        from_address(address)
+    })
+}
+
+// Looks up the address of a symbol by name, while the symbol handler is locked
+fn from_name(name: &CStr) -> Option<u64> {
+    let mut symbol = MaybeUninit::<SymbolInfo>::zeroed();
+    unsafe {
+        let symbol = symbol.as_mut_ptr();
+        (*symbol).size_of_struct = size_of::<SymbolInfo>() as u32;
+        if SymFromName(CURRENT_PROCESS, name.as_ptr() as _, symbol) == 0 {
+            return None;
+        }
+        Some((*symbol).address)
+    }
+}
+
+// Looks up the symbol an address is in, while the symbol handler is locked
+fn from_address(address: *const c_void) -> Option<String> {
+    let mut buffer = MaybeUninit::<SymbolBuffer>::zeroed();
+    let mut displacement = 0;
+    let name = unsafe {
+        let symbol = buffer.as_mut_ptr() as *mut SymbolInfo;
+        (*symbol).size_of_struct = size_of::<SymbolInfo>() as u32;
+        (*symbol).max_name_len = MAX_SYMBOL_NAME as u32 + 1;
+        if SymFromAddr(CURRENT_PROCESS, address as u64, &mut displacement, symbol) == 0 {
+            return None;
+        }
+        CStr::from_ptr((*symbol).name.as_ptr() as *const _)
+    };
+    let name = name.to_string_lossy();
+
+    if displacement == 0 {
+        Some(name.into_owned())
+    } else {
+        Some(format!("{name}+{displacement:#x}"))
+    }
+}
+
+// Picks up the modules loaded since the symbol handler last looked, while it is locked
+fn refresh_modules() {
+    unsafe { SymRefreshModuleList(CURRENT_PROCESS) };
+}
+
+// Locks the symbol handler, initializing it on first use
+fn symbol_handler() -> Result<MutexGuard<'static, bool>, MH_STATUS> {
+    // The symbol handler stays consistent even if a panic happened while it was locked
+    let mut initialized = SYMBOL_HANDLER
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    if !*initialized {
+        if unsafe { SymInitialize(CURRENT_PROCESS, std::ptr::null(), 1) } == 0 {
+            return Err(MH_STATUS::MH_UNKNOWN);
+        }
+        *initialized = true;
+    }
+
+    Ok(initialized)
+}
+
+impl MhHook {
+    /// Creates a hook for a function found by its debug symbol, such as `ntdll!LdrpLoadDll`.
+    ///
+    /// Returns the same errors as [`resolve`] and [`MhHook::new`].
+    ///
+    /// # Safety
+    pub unsafe fn new_symbol(name: &str, detour: *mut c_void) -> Result<Self, MH_STATUS> {
+        let target = resolve(name)?;
+
+        unsafe { Self::new(target, detour) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::module;
+
+    #[test]
+    fn test_resolve_symbol() {
+        // Exported functions are found even without a PDB.
+        let target = unsafe { module::find_export("kernelbase.dll", "GetTickCount") }.unwrap();
+        assert_eq!(resolve("kernelbase!GetTickCount").unwrap(), target);
+
+        assert_eq!(
+            resolve("kernelbase!NotASymbol"),
+            Err(MH_STATUS::MH_ERROR_FUNCTION_NOT_FOUND)
+        );
+    }
+
+    #[test]
+    fn test_symbolize() {
+        let target = resolve("kernelbase!GetTickCount").unwrap();
+        assert_eq!(symbolize(target).as_deref(), Some("GetTickCount"));
+        assert_eq!(
+            symbolize(target.wrapping_add(1)).as_deref(),
+            Some("GetTickCount+0x1")
+        );
+    }
+}
@@ -0,0 +1,181 @@
+//! The raw MinHook functions, for use below the wrappers of this crate.
+//!
+//! MinHook must be initialized before these functions are used, which the wrappers do on their own. The state tracked
+//! by the wrappers, such as [`MhHook::is_enabled`](crate::MhHook::is_enabled), is not updated by these functions.
+
+pub use crate::MH_STATUS;
+use std::ffi::c_void;
+
+/// Passed as the target to `MH_EnableHook`, `MH_DisableHook`, `MH_QueueEnableHook` or `MH_QueueDisableHook` to
+/// change all created hooks at once.
+pub const MH_ALL_HOOKS: *mut c_void = std::ptr::null_mut();
+
+// Declares the MinHook functions. They are linked statically, or resolved from an already loaded MinHook DLL with the
+// `dynamic` feature.
+macro_rules! minhook_functions {
+    ($($(#[$attr:meta])* pub fn $name:ident($($arg:ident: $ty:ty),* $(,)?) -> MH_STATUS;)*) => {
+        #[cfg(not(feature = "dynamic"))]
+        unsafe extern "system" {
+            $($(#[$attr])* pub fn $name($($arg: $ty),*) -> MH_STATUS;)*
+        }
+
+        $(
+            #[cfg(feature = "dynamic")]
+            $(#[$attr])*
+            ///
+            /// # Safety
+            ///
+            /// The same requirements as for calling the function of the MinHook DLL apply.
+            #[allow(non_snake_case)]
+            pub unsafe fn $name($($arg: $ty),*) -> MH_STATUS {
+                static ADDRESS: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+                match unsafe { dynamic::resolve(&ADDRESS, concat!(stringify!($name), "\0")) } {
+                    Some(address) => {
+                        let function = unsafe {
+                            std::mem::transmute::<*mut c_void, unsafe extern "system" fn($($ty),*) -> MH_STATUS>(
+                                address,
+                            )
+                        };
+
+                        unsafe { function($($arg),*) }
+                    }
+                    None => MH_STATUS::MH_UNKNOWN,
+                }
+            }
+        )*
+    };
+}
+
+minhook_functions! {
+    /// Initializes the MinHook library. You must call this function in the
+    /// beginning of your program.
+    pub fn MH_Initialize() -> MH_STATUS;
+
+    /// Uninitialize the MinHook library. You must call this function EXACTLY
+    /// ONCE at the end of your program.
+    pub fn MH_Uninitialize() -> MH_STATUS;
+
+    /// Creates a hook for the specified target function, in disabled state.
+    ///
+    /// # Arguments
+    ///
+    /// * `pTarget` \[in\] - A pointer to the target function, which will be overridden by the detour function.
+    /// * `pDetour` \[in\] - A pointer to the detour function, which will override the target function.
+    /// * `ppOriginal` \[out\] - A pointer to the trampoline function, which will be used to call the original target function. This parameter can be NULL.
+    pub fn MH_CreateHook(
+        pTarget: *mut c_void,
+        pDetour: *mut c_void,
+        ppOriginal: *mut *mut c_void,
+    ) -> MH_STATUS;
+
+    /// Creates a hook for the specified API function, in disabled state.
+    ///
+    /// # Arguments
+    ///
+    /// * `pszModule` \[in\] - A pointer to the null-terminated UTF-16 name of the loaded module which contains the target function.
+    /// * `pszProcName` \[in\] - A pointer to the null-terminated ANSI target function name, which will be overridden by the detour function.
+    /// * `pDetour` \[in\] - A pointer to the detour function, which will override the target function.
+    /// * `ppOriginal` \[out\] - A pointer to the trampoline function, which will be used to call the original target function. This parameter can be NULL.
+    pub fn MH_CreateHookApi(
+        pszModule: *const u16,
+        pszProcName: *const u8,
+        pDetour: *mut c_void,
+        ppOriginal: *mut *mut c_void,
+    ) -> MH_STATUS;
+
+    /// Creates a hook for the specified API function, in disabled state.
+    ///
+    /// # Arguments
+    ///
+    /// * `pszModule` \[in\] - A pointer to the null-terminated UTF-16 name of the loaded module which contains the target function.
+    /// * `pszProcName` \[in\] - A pointer to the null-terminated ANSI target function name, which will be overridden by the detour function.
+    /// * `pDetour` \[in\] - A pointer to the detour function, which will override the target function.
+    /// * `ppOriginal` \[out\] - A pointer to the trampoline function, which will be used to call the original target function. This parameter can be NULL.
+    /// * `ppTarget` \[out\] - A pointer to the target function, which will be overridden by the detour function. This parameter can be NULL.
+    pub fn MH_CreateHookApiEx(
+        pszModule: *const u16,
+        pszProcName: *const u8,
+        pDetour: *mut c_void,
+        ppOriginal: *mut *mut c_void,
+        ppTarget: *mut *mut c_void,
+    ) -> MH_STATUS;
+
+    /// Removes an already created hook.
+    ///
+    /// # Arguments
+    ///
+    /// * `pTarget` \[in\] - A pointer to the target function.
+    pub fn MH_RemoveHook(pTarget: *mut c_void) -> MH_STATUS;
+
+    /// Enables an already created hook.
+    ///
+    /// # Arguments
+    ///
+    /// * `pTarget` \[in\] - A pointer to the target function.
+    pub fn MH_EnableHook(pTarget: *mut c_void) -> MH_STATUS;
+
+    /// Disables an already created hook.
+    ///
+    /// # Arguments
+    ///
+    /// * `pTarget` \[in\] - A pointer to the target function.
+    pub fn MH_DisableHook(pTarget: *mut c_void) -> MH_STATUS;
+
+    /// Queues to enable an already created hook.
+    ///
+    /// # Arguments
+    ///
+    /// * `pTarget` \[in\] - A pointer to the target function.
+    pub fn MH_QueueEnableHook(pTarget: *mut c_void) -> MH_STATUS;
+
+    /// Queues to disable an already created hook.
+    ///
+    /// # Arguments
+    ///
+    /// * `pTarget` \[in\] - A pointer to the target function.
+    pub fn MH_QueueDisableHook(pTarget: *mut c_void) -> MH_STATUS;
+
+    /// Applies all queued changes in one go.
+    pub fn MH_ApplyQueued() -> MH_STATUS;
+}
+
+#[cfg(feature = "dynamic")]
+mod dynamic {
+    use crate::{
+        ModuleName,
+        win::{GetModuleHandleW, GetProcAddress},
+    };
+    use std::{
+        ffi::c_void,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    /// The name of the MinHook DLL the functions are resolved from.
+    #[cfg(target_pointer_width = "64")]
+    pub const MINHOOK_DLL: &str = "MinHook.x64.dll";
+    #[cfg(target_pointer_width = "32")]
+    pub const MINHOOK_DLL: &str = "MinHook.x86.dll";
+
+    // Resolves a function from the loaded MinHook DLL, and caches the address once it is found. The name must be
+    // null-terminated.
+    pub unsafe fn resolve(address: &AtomicUsize, name: &str) -> Option<*mut c_void> {
+        let cached = address.load(Ordering::Relaxed);
+        if cached != 0 {
+            return Some(cached as *mut c_void);
+        }
+
+        let module = unsafe { GetModuleHandleW(ModuleName::from(MINHOOK_DLL).as_ptr()) };
+        if module.is_null() {
+            return None;
+        }
+
+        let resolved = unsafe { GetProcAddress(module, name.as_ptr()) };
+        if resolved.is_null() {
+            return None;
+        }
+
+        address.store(resolved as usize, Ordering::Relaxed);
+        Some(resolved)
+    }
+}
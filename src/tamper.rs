@@ -0,0 +1,210 @@
+use crate::{HookInfo, MH_STATUS, MinHook, dump_hooks, hook, logging::warning};
+use std::{
+    fmt,
+    sync::mpsc::{self, RecvTimeoutError, Sender},
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+/// A function that is told about each hook found overwritten by a [`Watchdog`], before it is repaired.
+pub type TamperCallback = fn(&HookInfo);
+
+/// Returns the enabled hooks whose jump to the detour was overwritten, such as by a game patcher restoring the
+/// original code, or by another hooking library patching the same target.
+///
+/// # Safety
+///
+/// The targets of the enabled hooks must still be mapped.
+pub unsafe fn tampered_hooks() -> Vec<HookInfo> {
+    dump_hooks()
+        .into_iter()
+        .filter(|info| {
+            info.enabled
+                && !unsafe { hook::is_patch_intact(info.target, info.trampoline, info.detour) }
+        })
+        .collect()
+}
+
+/// Patches the jump to the detour in again for every hook returned by [`tampered_hooks`], and returns them with the
+/// result of repairing them.
+///
+/// The hook is disabled and enabled again, so MinHook first writes back the original code it saved, replacing
+/// whatever overwrote the jump. Hooks that were disabled or removed since they were found tampered with are skipped,
+/// instead of being enabled again.
+///
+/// # Safety
+///
+/// The targets of the enabled hooks must still be mapped.
+pub unsafe fn repair_hooks() -> Vec<(HookInfo, Result<(), MH_STATUS>)> {
+    unsafe { tampered_hooks() }
+        .into_iter()
+        .filter_map(|info| {
+            let result = match unsafe { MinHook::disable_hook(info.target) } {
+                Ok(()) => unsafe { MinHook::enable_hook(info.target) },
+                Err(MH_STATUS::MH_ERROR_DISABLED | MH_STATUS::MH_ERROR_NOT_CREATED) => return None,
+                Err(status) => Err(status),
+            };
+            Some((info, result))
+        })
+        .collect()
+}
+
+/// Checks the hooks for tampering at an interval on a background thread, and repairs them like [`repair_hooks`].
+///
+/// The thread is stopped when the watchdog is dropped.
+#[must_use = "the watchdog is stopped right away if it is dropped"]
+pub struct Watchdog {
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Watchdog {
+    /// Starts checking the hooks every interval, calling the callback for every hook that is found tampered with.
+    ///
+    /// # Safety
+    ///
+    /// The targets of the enabled hooks must stay mapped while the watchdog runs.
+    pub unsafe fn start(interval: Duration, on_tamper: TamperCallback) -> Self {
+        let (stop, stopped) = mpsc::channel();
+        let thread = thread::spawn(move || {
+            while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                for (info, result) in unsafe { repair_hooks() } {
+                    on_tamper(&info);
+                    if let Err(status) = result {
+                        warning!(
+                            "Could not repair the hook of {:?}: {:?}",
+                            info.target,
+                            status
+                        );
+                    }
+                }
+            }
+        });
+
+        Self {
+            stop: Some(stop),
+            thread: Some(thread),
+        }
+    }
+}
+
+impl fmt::Debug for Watchdog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Watchdog").finish_non_exhaustive()
+    }
+}
+
+impl Drop for Watchdog {
+    fn drop(&mut self) {
+        drop(self.stop.take());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::HookTest;
+    use crate::{
+        MhHook,
+        win::{PAGE_EXECUTE_READWRITE, VirtualProtect},
+    };
+    use std::{
+        ffi::c_void,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    #[test]
+    fn test_repair_hooks() {
+        let _test = HookTest::start();
+
+        unsafe {
+            let hook = MhHook::from_fns::<FnType>(test_fn, test_fn_hook).unwrap();
+            hook.enable().unwrap();
+            assert!(hook.is_intact());
+
+            // Overwrite the jump with nops, like an integrity check restoring the code would.
+            let target = hook.target();
+            let mut protect = 0;
+            VirtualProtect(target, 5, PAGE_EXECUTE_READWRITE, &mut protect);
+            std::ptr::write_bytes(target as *mut u8, 0x90, 5);
+            VirtualProtect(target, 5, protect, &mut protect);
+
+            assert!(!hook.is_intact());
+            assert!(tampered_hooks().iter().any(|info| info.target == target));
+
+            let repaired = repair_hooks();
+            assert!(
+                repaired
+                    .iter()
+                    .any(|(info, result)| info.target == target && result.is_ok())
+            );
+            assert!(hook.is_intact());
+            assert_eq!(test_fn(), 41);
+
+            hook.remove().unwrap();
+        }
+
+        type FnType = fn() -> i32;
+
+        #[inline(never)]
+        fn test_fn() -> i32 {
+            std::hint::black_box(40)
+        }
+
+        fn test_fn_hook() -> i32 {
+            41
+        }
+    }
+
+    #[test]
+    fn test_watchdog() {
+        static TAMPERED: AtomicUsize = AtomicUsize::new(0);
+
+        let _test = HookTest::start();
+
+        unsafe {
+            let hook = MhHook::from_fns::<FnType>(test_fn, test_fn_hook).unwrap();
+            hook.enable().unwrap();
+
+            let target = hook.target();
+            let mut protect = 0;
+            VirtualProtect(target, 5, PAGE_EXECUTE_READWRITE, &mut protect);
+            std::ptr::write_bytes(target as *mut u8, 0x90, 5);
+            VirtualProtect(target, 5, protect, &mut protect);
+
+            // The watchdog finds the overwritten jump and patches it in again.
+            let watchdog = Watchdog::start(Duration::from_millis(1), |info| {
+                if info.target == test_fn as FnType as *mut c_void {
+                    TAMPERED.fetch_add(1, Ordering::SeqCst);
+                }
+            });
+            for _ in 0..1000 {
+                if hook.is_intact() {
+                    break;
+                }
+                thread::sleep(Duration::from_millis(1));
+            }
+            drop(watchdog);
+
+            assert!(hook.is_intact());
+            assert_eq!(TAMPERED.load(Ordering::SeqCst), 1);
+            assert_eq!(test_fn(), 201);
+
+            hook.remove().unwrap();
+        }
+
+        type FnType = fn() -> i32;
+
+        #[inline(never)]
+        fn test_fn() -> i32 {
+            std::hint::black_box(200)
+        }
+
+        fn test_fn_hook() -> i32 {
+            201
+        }
+    }
+}
@@ -0,0 +1,114 @@
+//! Helpers for tests that hook functions.
+//!
+//! MinHook keeps a single state for the whole process, so tests that create hooks interfere with each other when the
+//! test harness runs them in parallel. A [`HookTest`] serializes these tests, and removes the hooks a test left behind.
+//!
+//! Mark the targets in tests `#[inline(never)]`: calls to a function that were inlined are not detoured, and tiny
+//! functions are inlined readily in optimized test builds.
+//!
+//! # Example
+//!
+//! ```rust
+//! use minhook::{testing::HookTest, MhHook};
+//!
+//! let _test = HookTest::start();
+//!
+//! let hook = unsafe { MhHook::from_fns::<fn() -> i32>(return_0, return_1).unwrap() };
+//! unsafe { hook.enable().unwrap() };
+//! assert_eq!(return_0(), 1);
+//!
+//! // The hook is removed when the test ends, even if it panics
+//!
+//! #[inline(never)]
+//! fn return_0() -> i32 {
+//!     0
+//! }
+//!
+//! fn return_1() -> i32 {
+//!     1
+//! }
+//! ```
+
+use crate::{MinHook, logging::warning, state};
+use std::{
+    collections::HashSet,
+    sync::{Mutex, MutexGuard, PoisonError},
+};
+
+static TEST_LOCK: Mutex<()> = Mutex::new(());
+
+/// Runs a test that hooks functions without other such tests running at the same time.
+///
+/// Hooks created while the test is running are removed when it is dropped, including when the test panics. The
+/// [`MhHook`](crate::MhHook)s of these hooks must not be used afterwards, as they are no longer created.
+#[derive(Debug)]
+#[must_use = "the test is no longer serialized once the guard is dropped"]
+pub struct HookTest {
+    existing: HashSet<usize>,
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl HookTest {
+    /// Waits for the other hook tests to finish, and starts the test.
+    pub fn start() -> Self {
+        // A test that panicked has already removed its hooks, so the lock is not poisoned for the other tests
+        let lock = TEST_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+        let existing = state::created()
+            .iter()
+            .map(|hook| hook.target as usize)
+            .collect();
+
+        Self {
+            existing,
+            _lock: lock,
+        }
+    }
+}
+
+impl Drop for HookTest {
+    fn drop(&mut self) {
+        for hook in state::created() {
+            if self.existing.contains(&(hook.target as usize)) {
+                continue;
+            }
+
+            if let Err(status) = unsafe { MinHook::remove_hook(hook.target) } {
+                warning!(
+                    "Could not remove hook of {:?} after the test: {:?}",
+                    hook.target,
+                    status
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MhHook;
+
+    #[test]
+    fn test_hook_test() {
+        let test = HookTest::start();
+        let hook = unsafe { MhHook::from_fns::<FnType>(test_fn, test_fn_hook).unwrap() };
+        unsafe { hook.enable().unwrap() };
+        assert_eq!(test_fn(), 1);
+
+        // The hook is removed with the test.
+        drop(test);
+        assert!(!hook.is_created());
+        assert_eq!(test_fn(), 0);
+
+        type FnType = fn() -> i32;
+
+        #[inline(never)]
+        fn test_fn() -> i32 {
+            0
+        }
+
+        fn test_fn_hook() -> i32 {
+            1
+        }
+    }
+}
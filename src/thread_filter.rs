@@ -0,0 +1,152 @@
+use crate::win::GetCurrentThreadId;
+use std::sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// A list of threads a detour applies to, or doesn't apply to.
+///
+/// The detour checks the filter when it's called, usually through [`thread_filter!`](crate::thread_filter), and calls
+/// the original function right away on threads the filter excludes. This keeps diagnostic hooks off the render thread
+/// or the worker threads of the hook owner.
+#[derive(Debug)]
+pub struct ThreadFilter {
+    except: bool,
+    threads: RwLock<Vec<u32>>,
+}
+
+impl ThreadFilter {
+    /// Creates a filter that only applies to the threads that are added to it.
+    pub const fn only() -> Self {
+        Self {
+            except: false,
+            threads: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// Creates a filter that applies to all threads except the ones that are added to it.
+    pub const fn except() -> Self {
+        Self {
+            except: true,
+            threads: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// Adds a thread by its identifier.
+    pub fn add(&self, thread_id: u32) {
+        let mut threads = self.write();
+        if !threads.contains(&thread_id) {
+            threads.push(thread_id);
+        }
+    }
+
+    /// Adds the calling thread.
+    pub fn add_current(&self) {
+        self.add(unsafe { GetCurrentThreadId() });
+    }
+
+    /// Removes a thread by its identifier.
+    pub fn remove(&self, thread_id: u32) {
+        self.write().retain(|&thread| thread != thread_id);
+    }
+
+    /// Removes the calling thread.
+    pub fn remove_current(&self) {
+        self.remove(unsafe { GetCurrentThreadId() });
+    }
+
+    /// Returns whether the thread was added to the filter.
+    pub fn contains(&self, thread_id: u32) -> bool {
+        self.read().contains(&thread_id)
+    }
+
+    /// Returns whether the filter applies to the thread.
+    pub fn applies_to(&self, thread_id: u32) -> bool {
+        self.contains(thread_id) != self.except
+    }
+
+    /// Returns whether the filter applies to the calling thread.
+    pub fn applies_to_current(&self) -> bool {
+        self.applies_to(unsafe { GetCurrentThreadId() })
+    }
+
+    // The threads stay consistent even if a panic happened while they were locked
+    fn read(&self) -> RwLockReadGuard<'_, Vec<u32>> {
+        self.threads.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, Vec<u32>> {
+        self.threads.write().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Returns the given expression from a detour if the [`ThreadFilter`] doesn't apply to the calling thread.
+///
+/// The expression usually calls the original function.
+///
+/// # Example
+///
+/// ```rust
+/// use minhook::{static_hook, thread_filter, ThreadFilter, MH_STATUS};
+///
+/// static_hook! {
+///     static RETURN_0_HOOK: fn() -> i32;
+/// }
+///
+/// static FILTER: ThreadFilter = ThreadFilter::only();
+///
+/// fn main() -> Result<(), MH_STATUS> {
+///     unsafe { RETURN_0_HOOK.enable(return_0, return_1)? };
+///
+///     // Only the main thread is detoured
+///     FILTER.add_current();
+///     assert_eq!(return_0(), 1);
+///     assert_eq!(std::thread::spawn(return_0).join().unwrap(), 0);
+///
+///     Ok(())
+/// }
+///
+/// fn return_0() -> i32 {
+///     0
+/// }
+///
+/// fn return_1() -> i32 {
+///     thread_filter!(FILTER, unsafe { RETURN_0_HOOK.call() });
+///
+///     1
+/// }
+/// ```
+#[macro_export]
+macro_rules! thread_filter {
+    ($filter:expr, $fallback:expr) => {
+        if !$filter.applies_to_current() {
+            return $fallback;
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_thread_filter() {
+        let only = ThreadFilter::only();
+        let except = ThreadFilter::except();
+        assert!(!only.applies_to_current());
+        assert!(except.applies_to_current());
+
+        only.add_current();
+        except.add_current();
+        assert!(only.applies_to_current());
+        assert!(!except.applies_to_current());
+
+        // Other threads are not added.
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                assert!(!only.applies_to_current());
+                assert!(except.applies_to_current());
+            });
+        });
+
+        only.remove_current();
+        assert!(!only.applies_to_current());
+    }
+}
@@ -0,0 +1,122 @@
+//! Typed trampolines looked up by their detour, so detours can call the original function without a static per hook.
+//!
+//! The trampolines of hooks created through this crate are found without being stored first. Trampolines of hooks
+//! created elsewhere, such as through [`sys`](crate::sys), are stored with [`set`]. Each lookup takes a lock, so a
+//! [`StaticHook`](crate::StaticHook) is cheaper for detours that are called very often.
+//!
+//! # Example
+//!
+//! ```rust
+//! use minhook::{trampolines, MhHook, MH_STATUS};
+//!
+//! type FnType = fn(i32) -> i32;
+//!
+//! fn main() -> Result<(), MH_STATUS> {
+//!     let hook = unsafe { MhHook::from_fns::<FnType>(add_1, add_1_hook)? };
+//!     unsafe { hook.enable()? };
+//!
+//!     assert_eq!(add_1(1), 12);
+//!
+//!     Ok(())
+//! }
+//!
+//! fn add_1(x: i32) -> i32 {
+//!     x + 1
+//! }
+//!
+//! fn add_1_hook(x: i32) -> i32 {
+//!     trampolines::get(add_1_hook as FnType).unwrap()(x + 10)
+//! }
+//! ```
+
+use crate::{FnPtr, state};
+use std::{
+    collections::HashMap,
+    ffi::c_void,
+    sync::{LazyLock, Mutex, MutexGuard, PoisonError},
+};
+
+static TRAMPOLINES: LazyLock<Mutex<HashMap<usize, usize>>> = LazyLock::new(Default::default);
+
+/// Returns the trampoline of the hook with the detour, which calls the original target function.
+///
+/// Trampolines stored with [`set`] are returned first. Otherwise this returns `None` if no hook created through this
+/// crate has the detour, or if several hooks share it, since their trampolines lead to different targets.
+pub fn get<F: FnPtr>(detour: F) -> Option<F> {
+    let detour = detour.as_ptr();
+    let trampoline = lock()
+        .get(&(detour as usize))
+        .map(|&trampoline| trampoline as *mut c_void)
+        .or_else(|| state::trampoline_of(detour))?;
+
+    // The trampoline shares the signature of the target, which the detour was created for
+    (!trampoline.is_null()).then(|| unsafe { F::from_ptr(trampoline) })
+}
+
+/// Stores the trampoline of the hook with the detour, replacing the trampoline stored before.
+///
+/// # Safety
+///
+/// The trampoline must be a function with the signature of the detour.
+pub unsafe fn set<F: FnPtr>(detour: F, trampoline: *mut c_void) {
+    lock().insert(detour.as_ptr() as usize, trampoline as usize);
+}
+
+/// Removes the trampoline stored for the detour with [`set`], and returns whether one was stored.
+pub fn unset<F: FnPtr>(detour: F) -> bool {
+    lock().remove(&(detour.as_ptr() as usize)).is_some()
+}
+
+// The trampolines stay consistent even if a panic happened while they were locked
+fn lock() -> MutexGuard<'static, HashMap<usize, usize>> {
+    TRAMPOLINES.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MhHook;
+    use crate::testing::HookTest;
+
+    #[test]
+    fn test_trampolines() {
+        let _test = HookTest::start();
+
+        unsafe {
+            assert!(get(test_fn_hook as FnType).is_none());
+
+            let hook = MhHook::from_fns::<FnType>(test_fn, test_fn_hook).unwrap();
+            hook.enable().unwrap();
+            assert_eq!(test_fn(), 61);
+            assert_eq!(get(test_fn_hook as FnType).unwrap()(), 60);
+
+            // Stored trampolines take precedence.
+            set(
+                test_fn_hook as FnType,
+                test_fn_other as FnType as *mut c_void,
+            );
+            assert_eq!(test_fn(), 62);
+            assert!(unset(test_fn_hook as FnType));
+            assert!(!unset(test_fn_hook as FnType));
+
+            hook.remove().unwrap();
+            assert!(get(test_fn_hook as FnType).is_none());
+        }
+
+        type FnType = fn() -> i32;
+
+        #[inline(never)]
+        fn test_fn() -> i32 {
+            std::hint::black_box(60)
+        }
+
+        fn test_fn_hook() -> i32 {
+            get(test_fn_hook as FnType).unwrap()() + 1
+        }
+
+        #[inline(never)]
+        fn test_fn_other() -> i32 {
+            std::hint::black_box(61)
+        }
+    }
+}
@@ -0,0 +1,299 @@
+use crate::{FnPtr, MH_STATUS, MhHook};
+use std::{ops::Deref, sync::OnceLock};
+
+/// A hook whose target, detour and trampoline all share the function signature `F`.
+///
+/// The original function is called through [`Hook::call_original`] instead of transmuting the trampoline by hand. The
+/// underlying [`MhHook`] is reachable through `Deref`.
+#[derive(Debug)]
+pub struct Hook<F: FnPtr> {
+    hook: MhHook,
+    original: F,
+}
+
+impl<F: FnPtr> Hook<F> {
+    /// Creates a hook for the target function and detours it to the detour function.
+    ///
+    /// # Safety
+    pub unsafe fn new(target: F, detour: F) -> Result<Self, MH_STATUS> {
+        let hook = unsafe { MhHook::from_fns(target, detour)? };
+        let original = unsafe { F::from_ptr(hook.trampoline()) };
+
+        Ok(Self { hook, original })
+    }
+
+    /// Returns the trampoline, which calls the original target function.
+    pub fn original(&self) -> F {
+        self.original
+    }
+}
+
+impl<F: FnPtr> Deref for Hook<F> {
+    type Target = MhHook;
+
+    fn deref(&self) -> &Self::Target {
+        &self.hook
+    }
+}
+
+/// A hook that can be declared as a static, usually through [`static_hook!`](crate::static_hook).
+///
+/// The hook is created the first time it is enabled, after which the original function can be called through
+/// [`StaticHook::call`] from anywhere, including the detour itself.
+#[derive(Debug)]
+pub struct StaticHook<F: FnPtr> {
+    hook: OnceLock<Hook<F>>,
+}
+
+impl<F: FnPtr> StaticHook<F> {
+    /// Creates a hook that isn't initialized yet.
+    pub const fn new() -> Self {
+        Self {
+            hook: OnceLock::new(),
+        }
+    }
+
+    /// Creates the hook for the target function if it doesn't exist yet, and enables it.
+    ///
+    /// Returns `MH_ERROR_ALREADY_CREATED` if the hook was already created for another target.
+    ///
+    /// # Safety
+    pub unsafe fn enable(&self, target: F, detour: F) -> Result<(), MH_STATUS> {
+        let hook = match self.hook.get() {
+            Some(hook) if hook.target() == target.as_ptr() => hook,
+            Some(_) => return Err(MH_STATUS::MH_ERROR_ALREADY_CREATED),
+            None => {
+                let hook = unsafe { Hook::new(target, detour)? };
+                self.hook.get_or_init(|| hook)
+            }
+        };
+
+        unsafe { hook.enable() }
+    }
+
+    /// Disables the hook. Returns `MH_ERROR_NOT_CREATED` if the hook was never enabled.
+    ///
+    /// # Safety
+    pub unsafe fn disable(&self) -> Result<(), MH_STATUS> {
+        match self.hook.get() {
+            Some(hook) => unsafe { hook.disable() },
+            None => Err(MH_STATUS::MH_ERROR_NOT_CREATED),
+        }
+    }
+
+    /// Returns the hook, if it was created.
+    pub fn get(&self) -> Option<&Hook<F>> {
+        self.hook.get()
+    }
+
+    /// Returns the trampoline, which calls the original target function.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the hook was never enabled.
+    pub fn original(&self) -> F {
+        self.hook
+            .get()
+            .expect("The static hook is not created yet")
+            .original()
+    }
+}
+
+impl<F: FnPtr> Default for StaticHook<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Declares statics holding a [`StaticHook`] for the given function pointer types.
+///
+/// # Example
+///
+/// ```rust
+/// use minhook::{static_hook, MH_STATUS};
+///
+/// static_hook! {
+///     static RETURN_0_HOOK: fn() -> i32;
+/// }
+///
+/// fn main() -> Result<(), MH_STATUS> {
+///     // Create and enable the hook for the return_0 function
+///     unsafe { RETURN_0_HOOK.enable(return_0, return_1)? };
+///
+///     // The detoured return_0 function calls the original function, and adds 1 to it
+///     assert_eq!(return_0(), 1);
+///     assert_eq!(unsafe { RETURN_0_HOOK.call() }, 0);
+///
+///     Ok(())
+/// }
+///
+/// fn return_0() -> i32 {
+///     0
+/// }
+///
+/// fn return_1() -> i32 {
+///     unsafe { RETURN_0_HOOK.call() + 1 }
+/// }
+/// ```
+#[macro_export]
+macro_rules! static_hook {
+    ($($(#[$attr:meta])* $vis:vis static $name:ident: $ty:ty;)*) => {
+        $(
+            $(#[$attr])*
+            $vis static $name: $crate::StaticHook<$ty> = $crate::StaticHook::new();
+        )*
+    };
+}
+
+macro_rules! impl_call {
+    ($($arg:ident: $ty:ident),*) => {
+        impl_call!(@abi "Rust", $($arg: $ty),*);
+        impl_call!(@abi "C", $($arg: $ty),*);
+        impl_call!(@abi "system", $($arg: $ty),*);
+        #[cfg(target_arch = "x86")]
+        impl_call!(@abi "stdcall", $($arg: $ty),*);
+        #[cfg(target_arch = "x86")]
+        impl_call!(@abi "fastcall", $($arg: $ty),*);
+        #[cfg(target_arch = "x86")]
+        impl_call!(@abi "thiscall", $($arg: $ty),*);
+    };
+    (@abi $abi:literal, $($arg:ident: $ty:ident),*) => {
+        impl_call!(@wrapper Hook::call_original, $abi, $($arg: $ty),*);
+        impl_call!(@wrapper StaticHook::call, $abi, $($arg: $ty),*);
+    };
+    (@wrapper $wrapper:ident::$method:ident, $abi:literal, $($arg:ident: $ty:ident),*) => {
+        impl<R, $($ty),*> $wrapper<extern $abi fn($($ty),*) -> R> {
+            /// Calls the original target function through the trampoline.
+            ///
+            /// # Safety
+            ///
+            /// The hook must not have been removed, e.g. by [`MinHook::uninitialize`](crate::MinHook::uninitialize),
+            /// as removing it frees the trampoline.
+            #[allow(clippy::too_many_arguments)]
+            pub unsafe fn $method(&self, $($arg: $ty),*) -> R {
+                (self.original())($($arg),*)
+            }
+        }
+
+        impl<R, $($ty),*> $wrapper<unsafe extern $abi fn($($ty),*) -> R> {
+            /// Calls the original target function through the trampoline.
+            ///
+            /// # Safety
+            ///
+            /// The hook must not have been removed, e.g. by [`MinHook::uninitialize`](crate::MinHook::uninitialize),
+            /// as removing it frees the trampoline. The same requirements as for calling the target function apply.
+            #[allow(clippy::too_many_arguments)]
+            pub unsafe fn $method(&self, $($arg: $ty),*) -> R {
+                unsafe { (self.original())($($arg),*) }
+            }
+        }
+    };
+}
+
+impl_call!();
+impl_call!(a: A);
+impl_call!(a: A, b: B);
+impl_call!(a: A, b: B, c: C);
+impl_call!(a: A, b: B, c: C, d: D);
+impl_call!(a: A, b: B, c: C, d: D, e: E);
+impl_call!(a: A, b: B, c: C, d: D, e: E, f: F);
+impl_call!(a: A, b: B, c: C, d: D, e: E, f: F, g: G);
+impl_call!(a: A, b: B, c: C, d: D, e: E, f: F, g: G, h: H);
+impl_call!(a: A, b: B, c: C, d: D, e: E, f: F, g: G, h: H, i: I);
+impl_call!(a: A, b: B, c: C, d: D, e: E, f: F, g: G, h: H, i: I, j: J);
+impl_call!(a: A, b: B, c: C, d: D, e: E, f: F, g: G, h: H, i: I, j: J, k: K);
+impl_call!(a: A, b: B, c: C, d: D, e: E, f: F, g: G, h: H, i: I, j: J, k: K, l: L);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MinHook;
+    use crate::testing::HookTest;
+
+    #[test]
+    fn test_call_original() {
+        let _test = HookTest::start();
+
+        unsafe {
+            let hook = Hook::<FnType>::new(test_fn, test_fn_hook).unwrap();
+            MinHook::enable_hook(hook.target()).unwrap();
+
+            // The target is detoured, while the original is still reachable.
+            assert_eq!(test_fn(2, 3), 6);
+            assert_eq!(hook.call_original(2, 3), 5);
+
+            MinHook::disable_hook(hook.target()).unwrap();
+            assert_eq!(test_fn(2, 3), 5);
+        }
+
+        type FnType = fn(i32, i32) -> i32;
+
+        #[inline(never)]
+        fn test_fn(x: i32, y: i32) -> i32 {
+            x + y
+        }
+
+        fn test_fn_hook(x: i32, y: i32) -> i32 {
+            x * y
+        }
+    }
+
+    #[cfg(target_arch = "x86")]
+    #[test]
+    fn test_call_original_stdcall() {
+        let _test = HookTest::start();
+
+        unsafe {
+            let hook = Hook::<FnType>::new(test_fn, test_fn_hook).unwrap();
+            hook.enable().unwrap();
+
+            assert_eq!(test_fn(2, 3), 6);
+            assert_eq!(hook.call_original(2, 3), 5);
+
+            hook.disable().unwrap();
+        }
+
+        type FnType = extern "stdcall" fn(i32, i32) -> i32;
+
+        #[inline(never)]
+        extern "stdcall" fn test_fn(x: i32, y: i32) -> i32 {
+            x + y
+        }
+
+        extern "stdcall" fn test_fn_hook(x: i32, y: i32) -> i32 {
+            x * y
+        }
+    }
+
+    #[test]
+    fn test_static_hook() {
+        let _test = HookTest::start();
+
+        crate::static_hook! {
+            static TEST_FN_HOOK: fn(i32) -> i32;
+        }
+
+        unsafe {
+            TEST_FN_HOOK.enable(test_fn, test_fn_hook).unwrap();
+            assert_eq!(test_fn(1), 2);
+            assert_eq!(TEST_FN_HOOK.call(1), 1);
+
+            TEST_FN_HOOK.disable().unwrap();
+            assert_eq!(test_fn(1), 1);
+
+            // Enabling again reuses the created hook.
+            TEST_FN_HOOK.enable(test_fn, test_fn_hook).unwrap();
+            assert_eq!(test_fn(1), 2);
+            TEST_FN_HOOK.disable().unwrap();
+        }
+
+        #[inline(never)]
+        fn test_fn(x: i32) -> i32 {
+            x
+        }
+
+        fn test_fn_hook(x: i32) -> i32 {
+            unsafe { TEST_FN_HOOK.call(x) + 1 }
+        }
+    }
+}
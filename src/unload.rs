@@ -0,0 +1,116 @@
+use crate::{
+    MH_STATUS, MinHook,
+    logging::debug,
+    state,
+    win::{
+        LDR_DLL_NOTIFICATION_REASON_UNLOADED, LdrDllNotificationData, LdrRegisterDllNotification,
+        LdrUnregisterDllNotification,
+    },
+};
+use std::{
+    ffi::c_void,
+    ptr::null_mut,
+    sync::{Mutex, PoisonError},
+};
+
+// The cookie of the unload notification, while hooks are removed on unload
+static COOKIE: Mutex<Option<usize>> = Mutex::new(None);
+
+/// Removes hooks automatically when the module containing their target is unloaded.
+///
+/// Without this, the hooks of a module that is unloaded stay created, and MinHook writes into the unmapped memory the
+/// next time the hooks are enabled, disabled or removed, such as when MinHook is uninitialized. The hooks are removed
+/// right before the module is unmapped, while the loader lock is held. Their [`MhHook`](crate::MhHook) values report
+/// them as no longer created, and new hooks can be created once the module is loaded again.
+///
+/// Returns `MH_UNKNOWN` if the loader notification couldn't be registered.
+pub fn remove_hooks_on_unload(enabled: bool) -> Result<(), MH_STATUS> {
+    // The cookie stays consistent even if a panic happened while it was locked
+    let mut cookie = COOKIE.lock().unwrap_or_else(PoisonError::into_inner);
+
+    match (enabled, *cookie) {
+        (true, None) => {
+            let mut new_cookie = null_mut();
+            let status =
+                unsafe { LdrRegisterDllNotification(0, notification, null_mut(), &mut new_cookie) };
+            debug!("LdrRegisterDllNotification: {:#x}", status);
+            if status < 0 {
+                return Err(MH_STATUS::MH_UNKNOWN);
+            }
+
+            *cookie = Some(new_cookie as usize);
+        }
+        (false, Some(old_cookie)) => {
+            let status = unsafe { LdrUnregisterDllNotification(old_cookie as *mut c_void) };
+            debug!("LdrUnregisterDllNotification: {:#x}", status);
+
+            *cookie = None;
+        }
+        _ => (),
+    }
+
+    Ok(())
+}
+
+unsafe extern "system" fn notification(
+    reason: u32,
+    data: *const LdrDllNotificationData,
+    _context: *mut c_void,
+) {
+    if reason != LDR_DLL_NOTIFICATION_REASON_UNLOADED {
+        return;
+    }
+
+    let data = unsafe { &*data };
+    let base = data.dll_base as usize;
+    for target in state::created_in(base..base + data.size_of_image as usize) {
+        debug!("Removing hook for {:?} of an unloading module", target);
+        let _ = unsafe { MinHook::remove_hook(target) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::HookTest;
+    use crate::{MhHook, ModuleName, win::GetModuleHandleW};
+
+    unsafe extern "system" {
+        fn LoadLibraryW(lpLibFileName: *const u16) -> *mut c_void;
+        fn FreeLibrary(hLibModule: *mut c_void) -> i32;
+    }
+
+    #[test]
+    fn test_remove_hooks_on_unload() {
+        let _test = HookTest::start();
+
+        let module_name = ModuleName::from("hid.dll");
+
+        unsafe {
+            // The module is only unloaded if nothing else loaded it.
+            if !GetModuleHandleW(module_name.as_ptr()).is_null() {
+                return;
+            }
+
+            let module = LoadLibraryW(module_name.as_ptr());
+            assert!(!module.is_null());
+            let hook = MhHook::new_api(
+                "hid.dll",
+                "HidD_GetHidGuid",
+                hid_d_get_hid_guid_hook as FnType as *mut c_void,
+            )
+            .unwrap();
+            hook.enable().unwrap();
+
+            remove_hooks_on_unload(true).unwrap();
+            FreeLibrary(module);
+            remove_hooks_on_unload(false).unwrap();
+
+            assert!(!hook.is_created());
+        }
+
+        type FnType = unsafe extern "system" fn(*mut c_void);
+
+        unsafe extern "system" fn hid_d_get_hid_guid_hook(_hid_guid: *mut c_void) {}
+    }
+}
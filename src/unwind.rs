@@ -0,0 +1,142 @@
+use crate::logging::warning;
+use std::{
+    any::Any,
+    panic::{self, AssertUnwindSafe},
+    sync::atomic::{AtomicPtr, Ordering},
+};
+
+/// A function that is told about panics caught by [`catch_detour_panic`], set through [`set_panic_callback`].
+pub type PanicCallback = fn(&(dyn Any + Send));
+
+// The callback set through `set_panic_callback`, or null if none is set
+static PANIC_CALLBACK: AtomicPtr<()> = AtomicPtr::new(std::ptr::null_mut());
+
+/// Sets a function that receives the payload of every panic caught in a detour. Without a callback, the panic message
+/// is logged as a warning.
+pub fn set_panic_callback(callback: Option<PanicCallback>) {
+    let callback = callback.map_or(std::ptr::null_mut(), |callback| callback as *mut ());
+    PANIC_CALLBACK.store(callback, Ordering::Release);
+}
+
+/// Runs the body of a detour, and runs the fallback instead of unwinding if the body panics. The fallback usually calls
+/// the original function.
+///
+/// A panic that unwinds out of an `extern` detour into the code that called the target aborts the process, or worse if
+/// the caller is foreign code. The panic is reported through the callback set by [`set_panic_callback`]. The panic hook
+/// still runs first, so the hooks are disabled if [`install_panic_cleanup`](crate::install_panic_cleanup) was called.
+///
+/// State the body changed before it panicked is not rolled back, the same as for [`AssertUnwindSafe`].
+pub fn catch_detour_panic<R, B: FnOnce() -> R, F: FnOnce() -> R>(body: B, fallback: F) -> R {
+    match panic::catch_unwind(AssertUnwindSafe(body)) {
+        Ok(ret) => ret,
+        Err(payload) => {
+            report(payload.as_ref());
+            fallback()
+        }
+    }
+}
+
+fn report(payload: &(dyn Any + Send)) {
+    let callback = PANIC_CALLBACK.load(Ordering::Acquire);
+    if !callback.is_null() {
+        // Only `PanicCallback` function pointers are stored
+        let callback = unsafe { std::mem::transmute::<*mut (), PanicCallback>(callback) };
+        callback(payload);
+        return;
+    }
+
+    let message = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("<non-string payload>");
+    warning!("Caught a panic in a detour: {}", message);
+}
+
+/// Runs the body of a detour like [`catch_detour_panic`], evaluating the fallback expression instead if the body
+/// panics.
+///
+/// # Example
+///
+/// ```rust
+/// use minhook::{contain_panic, static_hook, MH_STATUS};
+///
+/// static_hook! {
+///     static DIVIDE_HOOK: extern "C" fn(i32, i32) -> i32;
+/// }
+///
+/// fn main() -> Result<(), MH_STATUS> {
+///     unsafe { DIVIDE_HOOK.enable(divide, divide_hook)? };
+///
+///     // The detour panics on a division by zero, so the original function is called instead
+///     assert_eq!(divide(6, 0), -1);
+///     assert_eq!(divide(6, 3), 20);
+///
+///     Ok(())
+/// }
+///
+/// extern "C" fn divide(x: i32, y: i32) -> i32 {
+///     x.checked_div(y).unwrap_or(-1)
+/// }
+///
+/// extern "C" fn divide_hook(x: i32, y: i32) -> i32 {
+///     contain_panic!(unsafe { DIVIDE_HOOK.call(x, y) }, {
+///         x / y * 10
+///     })
+/// }
+/// ```
+#[macro_export]
+macro_rules! contain_panic {
+    ($fallback:expr, $body:block) => {
+        $crate::catch_detour_panic(|| $body, || $fallback)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::HookTest;
+    use std::sync::atomic::AtomicBool;
+
+    #[test]
+    fn test_catch_detour_panic() {
+        let _test = HookTest::start();
+
+        static REPORTED: AtomicBool = AtomicBool::new(false);
+
+        crate::static_hook! {
+            static TEST_FN_HOOK: extern "C" fn(u32) -> u32;
+        }
+
+        set_panic_callback(Some(|payload| {
+            assert_eq!(payload.downcast_ref::<&str>(), Some(&"test panic"));
+            REPORTED.store(true, Ordering::SeqCst);
+        }));
+
+        unsafe {
+            TEST_FN_HOOK.enable(test_fn, test_fn_hook).unwrap();
+            assert_eq!(test_fn(1), 1000);
+
+            // The panic is caught and reported, and the original function is called instead.
+            assert_eq!(test_fn(0), 0);
+            assert!(REPORTED.load(Ordering::SeqCst));
+
+            TEST_FN_HOOK.disable().unwrap();
+        }
+        set_panic_callback(None);
+
+        #[inline(never)]
+        extern "C" fn test_fn(x: u32) -> u32 {
+            x
+        }
+
+        extern "C" fn test_fn_hook(x: u32) -> u32 {
+            crate::contain_panic!(unsafe { TEST_FN_HOOK.call(x) }, {
+                if x == 0 {
+                    panic!("test panic");
+                }
+                x * 1000
+            })
+        }
+    }
+}
@@ -0,0 +1,198 @@
+use crate::{
+    MH_STATUS,
+    hook::PATCH_SIZE,
+    state,
+    win::{MEM_COMMIT, MemoryBasicInformation, PAGE_EXECUTE_FLAGS, VirtualQuery},
+};
+use std::{ffi::c_void, fmt, mem::MaybeUninit};
+
+/// Information about a target that passed [`validate_target`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TargetInfo {
+    /// The base address of the memory region containing the target.
+    pub region_base: *mut c_void,
+    /// The size of the memory region containing the target, in bytes.
+    pub region_size: usize,
+    /// The page protection of the memory region, such as `PAGE_EXECUTE_READ`.
+    pub protection: u32,
+    /// The first bytes of the target, which the jump overwrites. They are not disassembled.
+    pub prologue: [u8; PATCH_SIZE],
+}
+
+/// The reason a target can't be hooked, returned by [`validate_target`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationError {
+    /// The target is a null pointer.
+    Null,
+    /// The target is not in committed memory.
+    NotCommitted,
+    /// The target is in memory that is not executable, with the given page protection.
+    NotExecutable(u32),
+    /// The target is already hooked by this crate.
+    AlreadyHooked,
+    /// The executable memory ends before the jump can be patched, leaving only the given number of bytes.
+    TooShort(usize),
+    /// The target starts with a breakpoint, or with a return that is not followed by padding.
+    ReturnOrBreakpoint([u8; PATCH_SIZE]),
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Null => write!(f, "The target is a null pointer"),
+            Self::NotCommitted => write!(f, "The target is not in committed memory"),
+            Self::NotExecutable(protection) => {
+                write!(
+                    f,
+                    "The target is not executable (protection {protection:#x})"
+                )
+            }
+            Self::AlreadyHooked => write!(f, "The target is already hooked"),
+            Self::TooShort(available) => write!(
+                f,
+                "The target has only {available} executable bytes, {PATCH_SIZE} are needed"
+            ),
+            Self::ReturnOrBreakpoint(prologue) => {
+                write!(
+                    f,
+                    "The target starts with a return or a breakpoint {prologue:02x?}"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+impl From<ValidationError> for MH_STATUS {
+    /// Returns the status MinHook would return for the target.
+    fn from(error: ValidationError) -> Self {
+        match error {
+            ValidationError::Null
+            | ValidationError::NotCommitted
+            | ValidationError::NotExecutable(_) => MH_STATUS::MH_ERROR_NOT_EXECUTABLE,
+            ValidationError::AlreadyHooked => MH_STATUS::MH_ERROR_ALREADY_CREATED,
+            ValidationError::TooShort(_) | ValidationError::ReturnOrBreakpoint(_) => {
+                MH_STATUS::MH_ERROR_UNSUPPORTED_FUNCTION
+            }
+        }
+    }
+}
+
+/// Checks a target for the common reasons `MH_CreateHook` fails, before it fails with a less specific status.
+///
+/// The target must be in committed, executable memory with room for the jump, not hooked yet, and must not start with
+/// a return or a breakpoint. The room for the jump may span the next region. The instructions are not disassembled,
+/// so MinHook may still reject a target that passed, for example if the jump would split an instruction that can't be
+/// relocated.
+///
+/// # Safety
+pub unsafe fn validate_target(target: *mut c_void) -> Result<TargetInfo, ValidationError> {
+    if target.is_null() {
+        return Err(ValidationError::Null);
+    }
+
+    let Some(region) = (unsafe { query(target) }) else {
+        return Err(ValidationError::NotCommitted);
+    };
+
+    if region.state != MEM_COMMIT {
+        return Err(ValidationError::NotCommitted);
+    }
+    if region.protect & PAGE_EXECUTE_FLAGS == 0 {
+        return Err(ValidationError::NotExecutable(region.protect));
+    }
+    if state::is_created(target) {
+        return Err(ValidationError::AlreadyHooked);
+    }
+
+    let mut available = region.base_address as usize + region.region_size - target as usize;
+    if available < PATCH_SIZE {
+        // The jump may continue into the next region if that one is executable too
+        let next = unsafe { query(region.base_address.wrapping_byte_add(region.region_size)) };
+        available += next
+            .filter(|next| next.state == MEM_COMMIT && next.protect & PAGE_EXECUTE_FLAGS != 0)
+            .map_or(0, |next| next.region_size);
+    }
+    if available < PATCH_SIZE {
+        return Err(ValidationError::TooShort(available));
+    }
+
+    let prologue = unsafe { (target as *const [u8; PATCH_SIZE]).read_unaligned() };
+    // A return is only relocated if padding follows it, and a breakpoint is usually padding between functions
+    let padding = |byte: &u8| matches!(byte, 0x00 | 0x90 | 0xCC);
+    let unsupported = match prologue[0] {
+        0xC3 => !prologue[1..].iter().all(padding),
+        0xCC => true,
+        _ => false,
+    };
+    if unsupported {
+        return Err(ValidationError::ReturnOrBreakpoint(prologue));
+    }
+
+    Ok(TargetInfo {
+        region_base: region.base_address,
+        region_size: region.region_size,
+        protection: region.protect,
+        prologue,
+    })
+}
+
+// Returns the memory region containing the address, or `None` if it can't be queried
+unsafe fn query(address: *const c_void) -> Option<MemoryBasicInformation> {
+    let mut region = MaybeUninit::<MemoryBasicInformation>::zeroed();
+    let queried = unsafe {
+        VirtualQuery(
+            address,
+            region.as_mut_ptr(),
+            size_of::<MemoryBasicInformation>(),
+        )
+    };
+    (queried != 0).then(|| unsafe { region.assume_init() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MhHook;
+    use crate::testing::HookTest;
+
+    #[test]
+    fn test_validate_target() {
+        let _test = HookTest::start();
+
+        unsafe {
+            let info = validate_target(test_fn as FnType as *mut c_void).unwrap();
+            assert_ne!(info.protection & PAGE_EXECUTE_FLAGS, 0);
+
+            // Data and targets that are already hooked are rejected.
+            let data = [0u8; 16];
+            assert!(matches!(
+                validate_target(data.as_ptr() as *mut c_void),
+                Err(ValidationError::NotExecutable(_))
+            ));
+            assert_eq!(
+                validate_target(std::ptr::null_mut()),
+                Err(ValidationError::Null)
+            );
+
+            let hook = MhHook::from_fns::<FnType>(test_fn, test_fn_hook).unwrap();
+            assert_eq!(
+                validate_target(test_fn as FnType as *mut c_void),
+                Err(ValidationError::AlreadyHooked)
+            );
+            hook.remove().unwrap();
+        }
+
+        type FnType = fn(u64) -> u64;
+
+        #[inline(never)]
+        fn test_fn(x: u64) -> u64 {
+            x.wrapping_mul(31).rotate_left(7)
+        }
+
+        fn test_fn_hook(x: u64) -> u64 {
+            x
+        }
+    }
+}
@@ -0,0 +1,269 @@
+//! Hooks for virtual methods of C++ objects.
+//!
+//! A virtual method can either be hooked by swapping its entry in the vtable with [`VmtHook`], or by hooking the code
+//! of the method with [`MhHook::new`](crate::MhHook::new) on the address returned by [`method_address`].
+//!
+//! The methods of Rust trait objects can be hooked the same way with [`VmtHook::from_trait_object`].
+
+use crate::{MH_STATUS, slot::SlotHook};
+use std::ffi::c_void;
+
+/// Returns the address of the virtual method with the index.
+///
+/// # Safety
+///
+/// The object must start with a pointer to a vtable with more than `index` entries.
+pub unsafe fn method_address(object: *const c_void, index: usize) -> *mut c_void {
+    unsafe { *vtable(object).add(index) }
+}
+
+unsafe fn vtable(object: *const c_void) -> *mut *mut c_void {
+    unsafe { *(object as *const *mut *mut c_void) }
+}
+
+// The entries of the vtable of a Rust trait object before its methods: `drop_in_place`, the size and the alignment
+const TRAIT_VTABLE_HEADER: usize = 3;
+
+/// Returns the vtable of a Rust trait object such as `&dyn Trait`, or null if the reference is not a wide pointer.
+///
+/// # Safety
+///
+/// The reference must be a trait object. This relies on the current layout of trait objects, a data pointer followed
+/// by a vtable pointer, which Rust does not guarantee.
+pub unsafe fn trait_object_vtable<T: ?Sized>(object: &T) -> *mut *mut c_void {
+    if size_of::<&T>() != size_of::<[*mut c_void; 2]>() {
+        return std::ptr::null_mut();
+    }
+
+    let [_, vtable] = unsafe { std::mem::transmute_copy::<&T, [*mut c_void; 2]>(&object) };
+    vtable as *mut *mut c_void
+}
+
+/// A hook that swaps an entry of a vtable.
+///
+/// The hook is created in a disabled state. The code of the method is left untouched, and only calls through the
+/// patched vtable are redirected. A vtable is usually shared by all objects of the same class.
+#[derive(Debug)]
+pub struct VmtHook {
+    hook: SlotHook,
+    index: usize,
+}
+
+impl VmtHook {
+    /// Creates a hook for the virtual method with the index, in the vtable of the object.
+    ///
+    /// Returns `MH_ERROR_NOT_EXECUTABLE` if the object or its vtable is null.
+    ///
+    /// # Safety
+    ///
+    /// The object must start with a pointer to a vtable with more than `index` entries.
+    pub unsafe fn new(
+        object: *const c_void,
+        index: usize,
+        detour: *mut c_void,
+    ) -> Result<Self, MH_STATUS> {
+        if object.is_null() {
+            return Err(MH_STATUS::MH_ERROR_NOT_EXECUTABLE);
+        }
+
+        unsafe { Self::from_vtable(vtable(object), index, detour) }
+    }
+
+    /// Creates a hook for the entry with the index in the vtable.
+    ///
+    /// Returns `MH_ERROR_NOT_EXECUTABLE` if the vtable is null.
+    ///
+    /// # Safety
+    ///
+    /// The vtable must have more than `index` entries.
+    pub unsafe fn from_vtable(
+        vtable: *mut *mut c_void,
+        index: usize,
+        detour: *mut c_void,
+    ) -> Result<Self, MH_STATUS> {
+        if vtable.is_null() {
+            return Err(MH_STATUS::MH_ERROR_NOT_EXECUTABLE);
+        }
+
+        Ok(Self {
+            hook: unsafe { SlotHook::new(vtable.add(index), detour) },
+            index,
+        })
+    }
+
+    /// Creates a hook for a method of a Rust trait object, such as `&dyn Trait`. The index counts the methods of the
+    /// trait in the order they are declared, after the methods of its supertraits.
+    ///
+    /// The detour takes the data pointer of the object in place of `&self`. Only calls through the vtable of this
+    /// type and trait are redirected, which Rust may duplicate across codegen units and crates, and calls the compiler
+    /// devirtualized are not redirected at all. Returns `MH_ERROR_NOT_EXECUTABLE` if the reference is not a trait
+    /// object.
+    ///
+    /// # Safety
+    ///
+    /// The trait must have more than `index` methods, and see [`trait_object_vtable`] for the layout this relies on.
+    pub unsafe fn from_trait_object<T: ?Sized>(
+        object: &T,
+        index: usize,
+        detour: *mut c_void,
+    ) -> Result<Self, MH_STATUS> {
+        let vtable = unsafe { trait_object_vtable(object) };
+
+        unsafe { Self::from_vtable(vtable, TRAIT_VTABLE_HEADER + index, detour) }
+    }
+
+    /// Enables the hook by writing the detour into the vtable.
+    ///
+    /// # Safety
+    pub unsafe fn enable(&self) -> Result<(), MH_STATUS> {
+        unsafe { self.hook.enable() }
+    }
+
+    /// Disables the hook by restoring the original method in the vtable.
+    ///
+    /// # Safety
+    pub unsafe fn disable(&self) -> Result<(), MH_STATUS> {
+        unsafe { self.hook.disable() }
+    }
+
+    /// Removes the hook, restoring the original method if the hook is enabled.
+    ///
+    /// # Safety
+    pub unsafe fn remove(self) -> Result<(), MH_STATUS> {
+        unsafe { self.hook.remove() }
+    }
+
+    /// Returns the address of the original method, which can be used to call it.
+    pub fn original(&self) -> *mut c_void {
+        self.hook.original()
+    }
+
+    /// Returns the index of the hooked method in the vtable.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns whether the hook is enabled.
+    pub fn is_enabled(&self) -> bool {
+        self.hook.is_enabled()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::HookTest;
+
+    #[repr(C)]
+    struct Object {
+        vtable: *const *mut c_void,
+    }
+
+    type MethodType = unsafe extern "system" fn(*const Object) -> i32;
+
+    unsafe fn call_method(object: &Object, index: usize) -> i32 {
+        unsafe {
+            let method = method_address(object as *const _ as *const c_void, index);
+            std::mem::transmute::<*mut c_void, MethodType>(method)(object)
+        }
+    }
+
+    #[test]
+    fn test_vmt_hook() {
+        let _test = HookTest::start();
+
+        let vtable = Box::new([
+            method_0 as MethodType as *mut c_void,
+            method_1 as MethodType as *mut c_void,
+        ]);
+        let object = Object {
+            vtable: vtable.as_ptr(),
+        };
+
+        unsafe {
+            let hook = VmtHook::new(
+                &object as *const _ as *const c_void,
+                1,
+                method_1_hook as MethodType as *mut c_void,
+            )
+            .unwrap();
+            assert_eq!(hook.original(), method_1 as MethodType as *mut c_void);
+
+            hook.enable().unwrap();
+            assert_eq!(call_method(&object, 0), 0);
+            assert_eq!(call_method(&object, 1), 2);
+
+            hook.disable().unwrap();
+            assert_eq!(call_method(&object, 1), 1);
+
+            hook.remove().unwrap();
+        }
+
+        #[inline(never)]
+        unsafe extern "system" fn method_0(_this: *const Object) -> i32 {
+            0
+        }
+
+        #[inline(never)]
+        unsafe extern "system" fn method_1(_this: *const Object) -> i32 {
+            1
+        }
+
+        unsafe extern "system" fn method_1_hook(_this: *const Object) -> i32 {
+            2
+        }
+    }
+
+    #[test]
+    fn test_trait_object_hook() {
+        let _test = HookTest::start();
+
+        trait Shape {
+            fn sides(&self) -> u32;
+            fn corners(&self) -> u32;
+        }
+
+        struct Square;
+
+        impl Shape for Square {
+            fn sides(&self) -> u32 {
+                4
+            }
+
+            fn corners(&self) -> u32 {
+                4
+            }
+        }
+
+        let shape: &dyn Shape = &Square;
+
+        unsafe {
+            let hook =
+                VmtHook::from_trait_object(shape, 1, corners_hook as CornersType as _).unwrap();
+            hook.enable().unwrap();
+            assert_eq!(std::hint::black_box(shape).sides(), 4);
+            assert_eq!(std::hint::black_box(shape).corners(), 5);
+            hook.remove().unwrap();
+            assert_eq!(std::hint::black_box(shape).corners(), 4);
+
+            // Thin references have no vtable.
+            assert!(trait_object_vtable(&Square).is_null());
+        }
+
+        type CornersType = fn(*const c_void) -> u32;
+
+        fn corners_hook(_this: *const c_void) -> u32 {
+            5
+        }
+    }
+
+    #[test]
+    fn test_vmt_hook_null() {
+        let _test = HookTest::start();
+
+        unsafe {
+            let status = VmtHook::new(std::ptr::null(), 0, std::ptr::null_mut()).unwrap_err();
+            assert_eq!(status, MH_STATUS::MH_ERROR_NOT_EXECUTABLE);
+        }
+    }
+}
@@ -0,0 +1,434 @@
+use std::ffi::c_void;
+
+pub const PAGE_READWRITE: u32 = 0x04;
+pub const PAGE_EXECUTE_READWRITE: u32 = 0x40;
+pub const PAGE_EXECUTE_FLAGS: u32 = 0x10 | 0x20 | 0x40 | 0x80;
+pub const MEM_COMMIT: u32 = 0x1000;
+pub const GET_MODULE_HANDLE_EX_FLAG_UNCHANGED_REFCOUNT: u32 = 0x02;
+pub const GET_MODULE_HANDLE_EX_FLAG_FROM_ADDRESS: u32 = 0x04;
+
+#[repr(C)]
+pub struct MemoryBasicInformation {
+    pub base_address: *mut c_void,
+    pub allocation_base: *mut c_void,
+    pub allocation_protect: u32,
+    #[cfg(target_pointer_width = "64")]
+    pub partition_id: u16,
+    pub region_size: usize,
+    pub state: u32,
+    pub protect: u32,
+    pub type_: u32,
+}
+
+unsafe extern "system" {
+    /// Retrieves a module handle for the specified module. The module must have been loaded by the calling process.
+    ///
+    /// # Arguments
+    ///
+    /// * `lpModuleName` \[in\] - The null-terminated UTF-16 name of the loaded module. If this parameter is NULL, a handle to the executable of the calling process is returned.
+    pub fn GetModuleHandleW(lpModuleName: *const u16) -> *mut c_void;
+
+    /// Retrieves a module handle for the specified module, or for the module that contains an address.
+    ///
+    /// # Arguments
+    ///
+    /// * `dwFlags` \[in\] - The `GET_MODULE_HANDLE_EX_FLAG_*` flags.
+    /// * `lpModuleName` \[in\] - The name of the loaded module, or an address in it with `GET_MODULE_HANDLE_EX_FLAG_FROM_ADDRESS`.
+    /// * `phModule` \[out\] - A pointer to a variable that receives the handle of the module.
+    pub fn GetModuleHandleExW(
+        dwFlags: u32,
+        lpModuleName: *const u16,
+        phModule: *mut *mut c_void,
+    ) -> i32;
+
+    /// Changes the protection on a region of committed pages in the virtual address space of the calling process.
+    ///
+    /// # Arguments
+    ///
+    /// * `lpAddress` \[in\] - The address of the starting page of the region of pages whose access protection attributes are to be changed.
+    /// * `dwSize` \[in\] - The size of the region whose access protection attributes are to be changed, in bytes.
+    /// * `flNewProtect` \[in\] - The memory protection option.
+    /// * `lpflOldProtect` \[out\] - A pointer to a variable that receives the previous access protection value.
+    pub fn VirtualProtect(
+        lpAddress: *const c_void,
+        dwSize: usize,
+        flNewProtect: u32,
+        lpflOldProtect: *mut u32,
+    ) -> i32;
+
+    /// Retrieves information about a range of pages in the virtual address space of the calling process.
+    ///
+    /// # Arguments
+    ///
+    /// * `lpAddress` \[in\] - A pointer to the base address of the region of pages to be queried.
+    /// * `lpBuffer` \[out\] - A pointer to a `MEMORY_BASIC_INFORMATION` structure that receives the information.
+    /// * `dwLength` \[in\] - The size of the buffer pointed to by `lpBuffer`, in bytes.
+    pub fn VirtualQuery(
+        lpAddress: *const c_void,
+        lpBuffer: *mut MemoryBasicInformation,
+        dwLength: usize,
+    ) -> usize;
+
+    /// Flushes the instruction cache for the specified process.
+    ///
+    /// # Arguments
+    ///
+    /// * `hProcess` \[in\] - A handle to a process whose instruction cache is to be flushed.
+    /// * `lpBaseAddress` \[in\] - A pointer to the base of the region to be flushed.
+    /// * `dwSize` \[in\] - The size of the region to be flushed, in bytes.
+    pub fn FlushInstructionCache(
+        hProcess: *mut c_void,
+        lpBaseAddress: *const c_void,
+        dwSize: usize,
+    ) -> i32;
+}
+
+unsafe extern "system" {
+    /// Retrieves the address of an exported function or variable from the specified module.
+    ///
+    /// # Arguments
+    ///
+    /// * `hModule` \[in\] - A handle to the module that contains the function or variable.
+    /// * `lpProcName` \[in\] - The null-terminated ANSI function or variable name, or the ordinal value in the low-order word.
+    pub fn GetProcAddress(hModule: *mut c_void, lpProcName: *const u8) -> *mut c_void;
+
+    /// Loads the specified module into the address space of the calling process, or increments its reference count if
+    /// it is already loaded.
+    ///
+    /// # Arguments
+    ///
+    /// * `lpLibFileName` \[in\] - The null-terminated ANSI name of the module.
+    pub fn LoadLibraryA(lpLibFileName: *const u8) -> *mut c_void;
+}
+
+pub const TH32CS_SNAPTHREAD: u32 = 0x04;
+pub const INVALID_HANDLE_VALUE: *mut c_void = -1isize as *mut c_void;
+
+#[repr(C)]
+pub struct ThreadEntry32 {
+    pub size: u32,
+    pub usage: u32,
+    pub thread_id: u32,
+    pub owner_process_id: u32,
+    pub base_priority: i32,
+    pub delta_priority: i32,
+    pub flags: u32,
+}
+
+unsafe extern "system" {
+    /// Retrieves the thread identifier of the calling thread.
+    pub fn GetCurrentThreadId() -> u32;
+
+    /// Retrieves the process identifier of the calling process.
+    pub fn GetCurrentProcessId() -> u32;
+
+    /// Takes a snapshot of the specified processes, as well as the heaps, modules, and threads used by these processes.
+    ///
+    /// # Arguments
+    ///
+    /// * `dwFlags` \[in\] - The portions of the system to be included in the snapshot, such as `TH32CS_SNAPTHREAD`.
+    /// * `th32ProcessID` \[in\] - The process identifier of the process to be included in the snapshot. This parameter is ignored for thread snapshots, which include all threads in the system.
+    pub fn CreateToolhelp32Snapshot(dwFlags: u32, th32ProcessID: u32) -> *mut c_void;
+
+    /// Retrieves information about the first thread of any process encountered in a system snapshot.
+    ///
+    /// # Arguments
+    ///
+    /// * `hSnapshot` \[in\] - A handle to the snapshot returned from a previous call to `CreateToolhelp32Snapshot`.
+    /// * `lpte` \[in, out\] - A pointer to a `THREADENTRY32` structure, whose `dwSize` member must be set to its size.
+    pub fn Thread32First(hSnapshot: *mut c_void, lpte: *mut ThreadEntry32) -> i32;
+
+    /// Retrieves information about the next thread of any process encountered in the system memory snapshot.
+    ///
+    /// # Arguments
+    ///
+    /// * `hSnapshot` \[in\] - A handle to the snapshot returned from a previous call to `CreateToolhelp32Snapshot`.
+    /// * `lpte` \[out\] - A pointer to a `THREADENTRY32` structure.
+    pub fn Thread32Next(hSnapshot: *mut c_void, lpte: *mut ThreadEntry32) -> i32;
+
+    /// Closes an open object handle.
+    ///
+    /// # Arguments
+    ///
+    /// * `hObject` \[in\] - A valid handle to an open object.
+    pub fn CloseHandle(hObject: *mut c_void) -> i32;
+
+    /// Retrieves the last-error code value of the calling thread.
+    pub fn GetLastError() -> u32;
+
+    /// Sets the last-error code for the calling thread.
+    ///
+    /// # Arguments
+    ///
+    /// * `dwErrCode` \[in\] - The last-error code for the thread.
+    #[cfg(feature = "presets")]
+    pub fn SetLastError(dwErrCode: u32);
+}
+
+#[repr(C)]
+pub struct ModuleInfo {
+    pub base_of_dll: *mut c_void,
+    pub size_of_image: u32,
+    pub entry_point: *mut c_void,
+}
+
+unsafe extern "system" {
+    /// Retrieves a handle for each module in the specified process.
+    ///
+    /// # Arguments
+    ///
+    /// * `hProcess` \[in\] - A handle to the process.
+    /// * `lphModule` \[out\] - An array that receives the list of module handles.
+    /// * `cb` \[in\] - The size of the `lphModule` array, in bytes.
+    /// * `lpcbNeeded` \[out\] - The number of bytes required to store all module handles in the `lphModule` array.
+    pub fn K32EnumProcessModules(
+        hProcess: *mut c_void,
+        lphModule: *mut *mut c_void,
+        cb: u32,
+        lpcbNeeded: *mut u32,
+    ) -> i32;
+
+    /// Retrieves information about the specified module.
+    ///
+    /// # Arguments
+    ///
+    /// * `hProcess` \[in\] - A handle to the process that contains the module.
+    /// * `hModule` \[in\] - A handle to the module.
+    /// * `lpmodinfo` \[out\] - A pointer to the `MODULEINFO` structure that receives information about the module.
+    /// * `cb` \[in\] - The size of the `MODULEINFO` structure, in bytes.
+    pub fn K32GetModuleInformation(
+        hProcess: *mut c_void,
+        hModule: *mut c_void,
+        lpmodinfo: *mut ModuleInfo,
+        cb: u32,
+    ) -> i32;
+
+    /// Retrieves the fully qualified path of the file that contains the specified module.
+    ///
+    /// # Arguments
+    ///
+    /// * `hModule` \[in\] - A handle to the loaded module whose path is being requested.
+    /// * `lpFilename` \[out\] - A pointer to a buffer that receives the UTF-16 path of the module.
+    /// * `nSize` \[in\] - The size of the `lpFilename` buffer, in characters.
+    pub fn GetModuleFileNameW(hModule: *mut c_void, lpFilename: *mut u16, nSize: u32) -> u32;
+}
+
+pub const LDR_DLL_NOTIFICATION_REASON_LOADED: u32 = 1;
+pub const LDR_DLL_NOTIFICATION_REASON_UNLOADED: u32 = 2;
+
+#[repr(C)]
+pub struct UnicodeString {
+    pub length: u16,
+    pub maximum_length: u16,
+    pub buffer: *const u16,
+}
+
+impl UnicodeString {
+    /// Returns the UTF-16 code units of the string.
+    ///
+    /// # Safety
+    ///
+    /// The buffer must be valid for the length of the string.
+    pub unsafe fn as_wide(&self) -> &[u16] {
+        if self.buffer.is_null() {
+            &[]
+        } else {
+            unsafe { std::slice::from_raw_parts(self.buffer, self.length as usize / 2) }
+        }
+    }
+}
+
+#[repr(C)]
+pub struct LdrDllNotificationData {
+    pub flags: u32,
+    pub full_dll_name: *const UnicodeString,
+    pub base_dll_name: *const UnicodeString,
+    pub dll_base: *mut c_void,
+    pub size_of_image: u32,
+}
+
+pub type LdrDllNotificationFunction =
+    unsafe extern "system" fn(u32, *const LdrDllNotificationData, *mut c_void);
+
+#[link(name = "ntdll")]
+unsafe extern "system" {
+    /// Registers for notification when a DLL is first loaded or unloaded.
+    ///
+    /// # Arguments
+    ///
+    /// * `Flags` \[in\] - This parameter must be zero.
+    /// * `NotificationFunction` \[in\] - A pointer to the function to call when the DLL is loaded or unloaded.
+    /// * `Context` \[in\] - A pointer to context data for the callback function.
+    /// * `Cookie` \[out\] - A pointer to a variable that receives the cookie to unregister the callback.
+    pub fn LdrRegisterDllNotification(
+        Flags: u32,
+        NotificationFunction: LdrDllNotificationFunction,
+        Context: *mut c_void,
+        Cookie: *mut *mut c_void,
+    ) -> i32;
+
+    /// Cancels DLL load notification previously registered by calling `LdrRegisterDllNotification`.
+    ///
+    /// # Arguments
+    ///
+    /// * `Cookie` \[in\] - The cookie returned by `LdrRegisterDllNotification`.
+    pub fn LdrUnregisterDllNotification(Cookie: *mut c_void) -> i32;
+}
+
+pub const PROCESS_BASIC_INFORMATION_CLASS: u32 = 0;
+
+#[repr(C)]
+pub struct ProcessBasicInformation {
+    pub exit_status: i32,
+    pub peb_base_address: *const u8,
+    pub affinity_mask: usize,
+    pub base_priority: i32,
+    pub unique_process_id: usize,
+    pub inherited_from_unique_process_id: usize,
+}
+
+// The offset of the API set map in the process environment block
+#[cfg(target_pointer_width = "64")]
+pub const PEB_API_SET_MAP_OFFSET: usize = 0x68;
+#[cfg(target_pointer_width = "32")]
+pub const PEB_API_SET_MAP_OFFSET: usize = 0x38;
+
+// The offset of the loader lock in the process environment block
+#[cfg(target_pointer_width = "64")]
+pub const PEB_LOADER_LOCK_OFFSET: usize = 0x110;
+#[cfg(target_pointer_width = "32")]
+pub const PEB_LOADER_LOCK_OFFSET: usize = 0xA0;
+
+#[repr(C)]
+pub struct RtlCriticalSection {
+    pub debug_info: *mut c_void,
+    pub lock_count: i32,
+    pub recursion_count: i32,
+    pub owning_thread: *mut c_void,
+    pub lock_semaphore: *mut c_void,
+    pub spin_count: usize,
+}
+
+// The API set schema version used since Windows 10
+pub const API_SET_SCHEMA_VERSION_V6: u32 = 6;
+
+#[repr(C)]
+pub struct ApiSetNamespace {
+    pub version: u32,
+    pub size: u32,
+    pub flags: u32,
+    pub count: u32,
+    pub entry_offset: u32,
+    pub hash_offset: u32,
+    pub hash_factor: u32,
+}
+
+#[repr(C)]
+pub struct ApiSetNamespaceEntry {
+    pub flags: u32,
+    pub name_offset: u32,
+    pub name_length: u32,
+    pub hashed_length: u32,
+    pub value_offset: u32,
+    pub value_count: u32,
+}
+
+#[repr(C)]
+pub struct ApiSetValueEntry {
+    pub flags: u32,
+    pub name_offset: u32,
+    pub name_length: u32,
+    pub value_offset: u32,
+    pub value_length: u32,
+}
+
+#[link(name = "ntdll")]
+unsafe extern "system" {
+    /// Retrieves information about the specified process.
+    ///
+    /// # Arguments
+    ///
+    /// * `ProcessHandle` \[in\] - A handle to the process for which information is to be retrieved.
+    /// * `ProcessInformationClass` \[in\] - The type of process information to be retrieved.
+    /// * `ProcessInformation` \[out\] - A pointer to a buffer that receives the process information.
+    /// * `ProcessInformationLength` \[in\] - The size of the buffer pointed to by `ProcessInformation`, in bytes.
+    /// * `ReturnLength` \[out\] - A pointer to a variable that receives the size of the requested information. This parameter can be NULL.
+    pub fn NtQueryInformationProcess(
+        ProcessHandle: *mut c_void,
+        ProcessInformationClass: u32,
+        ProcessInformation: *mut c_void,
+        ProcessInformationLength: u32,
+        ReturnLength: *mut u32,
+    ) -> i32;
+}
+
+// The handle of the current process, as returned by `GetCurrentProcess`
+pub const CURRENT_PROCESS: *mut c_void = -1isize as *mut c_void;
+
+#[cfg(feature = "symbols")]
+#[repr(C)]
+pub struct SymbolInfo {
+    pub size_of_struct: u32,
+    pub type_index: u32,
+    pub reserved: [u64; 2],
+    pub index: u32,
+    pub size: u32,
+    pub mod_base: u64,
+    pub flags: u32,
+    pub value: u64,
+    pub address: u64,
+    pub register: u32,
+    pub scope: u32,
+    pub tag: u32,
+    pub name_len: u32,
+    pub max_name_len: u32,
+    pub name: [u8; 1],
+}
+
+#[cfg(feature = "symbols")]
+#[link(name = "dbghelp")]
+unsafe extern "system" {
+    /// Initializes the symbol handler for a process.
+    ///
+    /// # Arguments
+    ///
+    /// * `hProcess` \[in\] - A handle that identifies the caller.
+    /// * `UserSearchPath` \[in\] - The null-terminated path, or series of paths separated by semicolons, used to search for symbol files. If this parameter is NULL, the default search path is used.
+    /// * `fInvadeProcess` \[in\] - If this value is TRUE, the symbols of every loaded module of the process are loaded.
+    pub fn SymInitialize(
+        hProcess: *mut c_void,
+        UserSearchPath: *const u8,
+        fInvadeProcess: i32,
+    ) -> i32;
+
+    /// Refreshes the module list of the process, loading the symbols of modules that were loaded since.
+    ///
+    /// # Arguments
+    ///
+    /// * `hProcess` \[in\] - A handle to the process that was passed to `SymInitialize`.
+    pub fn SymRefreshModuleList(hProcess: *mut c_void) -> i32;
+
+    /// Retrieves symbol information for the specified name.
+    ///
+    /// # Arguments
+    ///
+    /// * `hProcess` \[in\] - A handle to the process that was passed to `SymInitialize`.
+    /// * `Name` \[in\] - The null-terminated name of the symbol, optionally prefixed by a module name and `!`.
+    /// * `Symbol` \[in, out\] - A pointer to a `SYMBOL_INFO` structure that receives the symbol information.
+    pub fn SymFromName(hProcess: *mut c_void, Name: *const u8, Symbol: *mut SymbolInfo) -> i32;
+
+    /// Retrieves symbol information for the specified address.
+    ///
+    /// # Arguments
+    ///
+    /// * `hProcess` \[in\] - A handle to the process that was passed to `SymInitialize`.
+    /// * `Address` \[in\] - The address for which a symbol should be located.
+    /// * `Displacement` \[out, optional\] - The displacement from the beginning of the symbol, or zero.
+    /// * `Symbol` \[in, out\] - A pointer to a `SYMBOL_INFO` structure that receives the symbol information.
+    pub fn SymFromAddr(
+        hProcess: *mut c_void,
+        Address: u64,
+        Displacement: *mut u64,
+        Symbol: *mut SymbolInfo,
+    ) -> i32;
+}
@@ -0,0 +1,100 @@
+use crate::{MH_STATUS, MhHook, ModuleName};
+use std::ffi::c_void;
+use windows::{
+    Win32::Foundation::FARPROC,
+    core::{PCSTR, PCWSTR},
+};
+
+impl MhHook {
+    /// Creates a hook for a function returned by `GetProcAddress`.
+    ///
+    /// Returns `MH_ERROR_FUNCTION_NOT_FOUND` if the function is `None`.
+    ///
+    /// # Safety
+    pub unsafe fn from_farproc(target: FARPROC, detour: *mut c_void) -> Result<Self, MH_STATUS> {
+        let target = target.ok_or(MH_STATUS::MH_ERROR_FUNCTION_NOT_FOUND)?;
+
+        unsafe { Self::new(target as *mut c_void, detour) }
+    }
+
+    /// Creates a hook for an exported function like [`MhHook::new_api`], from names given as `windows` strings.
+    ///
+    /// Returns `MH_ERROR_FUNCTION_NOT_FOUND` if the function name is not valid UTF-8.
+    ///
+    /// # Safety
+    ///
+    /// Both names must be null-terminated strings.
+    pub unsafe fn new_api_pcstr(
+        module_name: PCWSTR,
+        proc_name: PCSTR,
+        detour: *mut c_void,
+    ) -> Result<Self, MH_STATUS> {
+        let module_name = unsafe { ModuleName::from_pcwstr(module_name) };
+        let proc_name =
+            unsafe { proc_name.to_string() }.map_err(|_| MH_STATUS::MH_ERROR_FUNCTION_NOT_FOUND)?;
+
+        unsafe { Self::new_api(module_name, proc_name, detour) }
+    }
+}
+
+impl ModuleName {
+    /// Creates a module name from a `windows` wide string.
+    ///
+    /// # Safety
+    ///
+    /// The name must be a null-terminated string.
+    pub unsafe fn from_pcwstr(name: PCWSTR) -> Self {
+        let mut wide = unsafe { name.as_wide() }.to_vec();
+        wide.push(0);
+
+        Self(wide)
+    }
+
+    /// Returns the module name as a `windows` wide string, which is valid as long as the module name is.
+    pub fn as_pcwstr(&self) -> PCWSTR {
+        PCWSTR(self.as_ptr())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::HookTest;
+    use crate::{module, win::GetProcAddress};
+    use windows::core::{s, w};
+
+    #[test]
+    fn test_windows_interop() {
+        let _test = HookTest::start();
+
+        unsafe {
+            let module = module::handle("kernel32.dll").unwrap();
+            let target = std::mem::transmute::<*mut c_void, FARPROC>(GetProcAddress(
+                module.0,
+                s!("GetProcessHeap").0,
+            ));
+
+            // Hooks created from a FARPROC and from windows strings share the same target.
+            let hook = MhHook::from_farproc(target, get_process_heap_hook as FnType as _).unwrap();
+            let status = MhHook::new_api_pcstr(
+                w!("kernel32.dll"),
+                s!("GetProcessHeap"),
+                get_process_heap_hook as FnType as _,
+            )
+            .unwrap_err();
+            assert_eq!(status, MH_STATUS::MH_ERROR_ALREADY_CREATED);
+
+            assert_eq!(
+                MhHook::from_farproc(None, get_process_heap_hook as FnType as _).unwrap_err(),
+                MH_STATUS::MH_ERROR_FUNCTION_NOT_FOUND
+            );
+            hook.remove().unwrap();
+        }
+
+        type FnType = unsafe extern "system" fn() -> *mut c_void;
+
+        unsafe extern "system" fn get_process_heap_hook() -> *mut c_void {
+            std::ptr::null_mut()
+        }
+    }
+}